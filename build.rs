@@ -19,4 +19,18 @@ fn main() {
     )
     .write_bindings(StructGenerator, &mut file)
     .unwrap();
+
+    if env::var_os("CARGO_FEATURE_HEADLESS").is_some() {
+        let mut file = File::create(dst.join("egl_bindings.rs")).unwrap();
+
+        Registry::new(
+            Api::Egl,
+            (1, 5),
+            Profile::Core,
+            Fallbacks::All,
+            ["EGL_EXT_platform_base", "EGL_MESA_platform_surfaceless"],
+        )
+        .write_bindings(StructGenerator, &mut file)
+        .unwrap();
+    }
 }
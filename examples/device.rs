@@ -17,6 +17,7 @@ fn main() -> anyhow::Result<()> {
         );
 
         println!("{:#?}", grr.limits());
+        println!("{:#?}", grr.features());
     }
 
     Ok(())
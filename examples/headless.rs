@@ -0,0 +1,76 @@
+fn main() -> anyhow::Result<()> {
+    unsafe {
+        let grr = grr::Device::new_headless(grr::DeviceDesc {
+            debug: grr::Debug::Enable {
+                callback: Box::new(|msg| {
+                    println!("{:?}: {:?}", msg.severity, msg.message);
+                }),
+                flags: grr::DebugReport::FULL,
+            },
+            ..Default::default()
+        })?;
+
+        let (image, view) = grr.create_image_and_view(
+            grr::ImageType::D2 {
+                width: 4,
+                height: 4,
+                layers: 1,
+                samples: 1,
+            },
+            grr::Format::R8G8B8A8_UNORM,
+            1,
+            None,
+        )?;
+        let framebuffer = grr.create_framebuffer(None)?;
+        grr.bind_attachments(
+            framebuffer,
+            &[(grr::Attachment::Color(0), grr::AttachmentView::Image(view))],
+        );
+        // Without a window surface the initial scissor box is empty.
+        grr.set_scissor(
+            0,
+            &[grr::Region {
+                x: 0,
+                y: 0,
+                w: 4,
+                h: 4,
+            }],
+        );
+        grr.clear_attachment(
+            framebuffer,
+            grr::ClearAttachment::ColorFloat(0, [1.0, 0.5, 0.0, 1.0]),
+        );
+
+        let mut texels = [0u8; 4 * 4 * 4];
+        grr.copy_image_to_host(
+            image,
+            &mut texels,
+            grr::HostImageCopy {
+                host_layout: grr::MemoryLayout {
+                    base_format: grr::BaseFormat::RGBA,
+                    format_layout: grr::FormatLayout::U8,
+                    row_length: 4,
+                    image_height: 4,
+                    alignment: 4,
+                },
+                image_subresource: grr::SubresourceLayers {
+                    level: 0,
+                    layers: 0..1,
+                },
+                image_offset: grr::Offset::ORIGIN,
+                image_extent: grr::Extent {
+                    width: 4,
+                    height: 4,
+                    depth: 1,
+                },
+            },
+        );
+        println!("{:?}", &texels[..4]);
+
+        grr.delete_framebuffer(framebuffer);
+        grr.delete_image_view(view);
+        grr.delete_image(image);
+    }
+
+    Ok(())
+}
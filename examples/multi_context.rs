@@ -120,24 +120,28 @@ fn main() -> anyhow::Result<()> {
         let mut present_ctxt = ErasedWindowContext::new(present_ctxt);
         present_ctxt.make_current().unwrap();
 
-        let swapchain = grr::Device::new(
+        // Contexts share their objects, the group keeps track of which device owns
+        // the non-shared container objects (e.g. framebuffers).
+        let group = grr::DeviceGroup::new();
+
+        let swapchain = group.create_device(
             |symbol| present_ctxt.get_proc_address(symbol) as *const _,
-            grr::Debug::Disable,
+            grr::DeviceDesc::default(),
         );
 
-        let present_fbo = swapchain.create_framebuffer()?;
+        let present_fbo = swapchain.create_framebuffer(None)?;
 
         let mut context = ErasedContext::new(context);
         context.make_current().unwrap();
 
-        let grr = grr::Device::new(
+        let grr = group.create_device(
             |symbol| context.get_proc_address(symbol) as *const _,
-            grr::Debug::Enable {
-                callback: |_, _, _, _, msg| {
-                    println!("{:?}", msg);
-                },
+            grr::DeviceDesc::default().debug(grr::Debug::Enable {
+                callback: Box::new(|msg| {
+                    println!("{:?}", msg.message);
+                }),
                 flags: grr::DebugReport::FULL,
-            },
+            }),
         );
 
         let vs = grr.create_shader(
@@ -145,12 +149,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             VERTEX_SRC.as_bytes(),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             FRAGMENT_SRC.as_bytes(),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let pipeline = grr.create_graphics_pipeline(
@@ -162,27 +168,31 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
-        let vertex_array = grr.create_vertex_array(&[
-            grr::VertexAttributeDesc {
-                location: 0,
-                binding: 0,
-                format: grr::VertexFormat::Xy32Float,
-                offset: 0,
-            },
-            grr::VertexAttributeDesc {
-                location: 1,
-                binding: 0,
-                format: grr::VertexFormat::Xyz32Float,
-                offset: (2 * std::mem::size_of::<f32>()) as _,
-            },
-        ])?;
+        let vertex_array = grr.create_vertex_array(
+            &[
+                grr::VertexAttributeDesc {
+                    location: 0,
+                    binding: 0,
+                    format: grr::VertexFormat::Xy32Float,
+                    offset: 0,
+                },
+                grr::VertexAttributeDesc {
+                    location: 1,
+                    binding: 0,
+                    format: grr::VertexFormat::Xyz32Float,
+                    offset: (2 * std::mem::size_of::<f32>()) as _,
+                },
+            ],
+            None,
+        )?;
 
         let triangle_data =
-            grr.create_buffer_from_host(grr::as_u8_slice(&VERTICES), grr::MemoryFlags::empty())?;
+            grr.create_buffer_from_host(grr::as_bytes(&VERTICES), grr::MemoryFlags::empty(), None)?;
 
-        let ctxt_fbo = grr.create_framebuffer()?;
+        let ctxt_fbo = grr.create_framebuffer(None)?;
 
         let size = window.inner_size();
         let present_image = grr.create_image(
@@ -194,8 +204,12 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R8G8B8A8_SRGB,
             1,
+            None,
         )?;
 
+        // Image returned from the presenting device after blitting.
+        let mut present_return = None;
+
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
 
@@ -214,6 +228,9 @@ fn main() -> anyhow::Result<()> {
                     let size = window.inner_size();
 
                     context.make_current().unwrap();
+                    if let Some(transfer) = present_return.take() {
+                        grr.acquire(transfer);
+                    }
                     grr.bind_pipeline(pipeline);
                     grr.bind_vertex_array(vertex_array);
                     grr.bind_vertex_buffers(
@@ -263,8 +280,10 @@ fn main() -> anyhow::Result<()> {
                         grr::ClearAttachment::ColorFloat(0, [0.5, 0.5, 0.5, 1.0]),
                     );
                     grr.draw(grr::Primitive::Triangles, 0..3, 0..1);
+                    let transfer = grr.transfer(present_image);
 
                     present_ctxt.make_current().unwrap();
+                    swapchain.acquire(transfer);
 
                     swapchain.set_color_attachments(present_fbo, &[0]);
                     swapchain.bind_attachments(
@@ -289,6 +308,7 @@ fn main() -> anyhow::Result<()> {
                         grr::Filter::Linear,
                     );
                     present_ctxt.swap_buffers().unwrap();
+                    present_return = Some(swapchain.transfer(present_image));
                 }
                 _ => (),
             }
@@ -16,11 +16,12 @@ use std::{fs, io, mem, path::Path, slice, time};
 
 const NANOS_PER_SEC: u64 = 1_000_000_000;
 
-#[repr(C)]
-struct Vertex {
-    pub pos: [f32; 3],
-    pub uv: [f32; 2],
-    pub normals: [f32; 3],
+grr::pod! {
+    struct Vertex {
+        pub pos: [f32; 3],
+        pub uv: [f32; 2],
+        pub normals: [f32; 3],
+    }
 }
 
 struct Geometry {
@@ -86,9 +87,9 @@ fn main() -> anyhow::Result<()> {
         let grr = grr::Device::new(
             |symbol| context.get_proc_address(symbol) as *const _,
             grr::Debug::Enable {
-                callback: |report, _, _, _, msg| {
-                    println!("{:?}: {:?}", report, msg);
-                },
+                callback: Box::new(|msg| {
+                    println!("{:?}: {:?}", msg.severity, msg.message);
+                }),
                 flags: grr::DebugReport::FULL,
             },
         );
@@ -114,6 +115,7 @@ fn main() -> anyhow::Result<()> {
         let mesh_data = grr.create_buffer(
             mesh_data_len,
             grr::MemoryFlags::CPU_MAP_WRITE | grr::MemoryFlags::COHERENT,
+            None,
         )?;
 
         let index_size = 4; // u32
@@ -121,6 +123,7 @@ fn main() -> anyhow::Result<()> {
         let index_data = grr.create_buffer(
             index_data_len,
             grr::MemoryFlags::CPU_MAP_WRITE | grr::MemoryFlags::COHERENT,
+            None,
         )?;
 
         let mut base_index = 0;
@@ -192,6 +195,7 @@ fn main() -> anyhow::Result<()> {
                 },
                 format,
                 num_levels,
+                None,
             )?;
             grr.copy_host_to_image(
                 &img_data,
@@ -227,32 +231,37 @@ fn main() -> anyhow::Result<()> {
         let roughness = load_image_rgba("Textures/Cerberus_R.tga", grr::Format::R8_UNORM)?;
         let occlusion = load_image_rgba("Textures/Raw/Cerberus_AO.tga", grr::Format::R8_UNORM)?;
 
-        let sampler_trilinear = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: Some(grr::Filter::Linear),
-            address: (
-                grr::SamplerAddress::Repeat,
-                grr::SamplerAddress::Repeat,
-                grr::SamplerAddress::Repeat,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..1024.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
+        let sampler_trilinear = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: Some(grr::Filter::Linear),
+                address: (
+                    grr::SamplerAddress::Repeat,
+                    grr::SamplerAddress::Repeat,
+                    grr::SamplerAddress::Repeat,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..1024.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
+        )?;
 
         let pbr_vs = grr.create_shader(
             grr::ShaderStage::Vertex,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/pbr.vs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let pbr_fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/pbr.fs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let pbr_pipeline = grr.create_graphics_pipeline(
@@ -264,28 +273,32 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(pbr_fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
-        let pbr_vertex_array = grr.create_vertex_array(&[
-            grr::VertexAttributeDesc {
-                location: 0,
-                binding: 0,
-                format: grr::VertexFormat::Xyz32Float,
-                offset: 0,
-            },
-            grr::VertexAttributeDesc {
-                location: 1,
-                binding: 0,
-                format: grr::VertexFormat::Xy32Float,
-                offset: 12,
-            },
-            grr::VertexAttributeDesc {
-                location: 2,
-                binding: 0,
-                format: grr::VertexFormat::Xyz32Float,
-                offset: 20,
-            },
-        ])?;
+        let pbr_vertex_array = grr.create_vertex_array(
+            &[
+                grr::VertexAttributeDesc {
+                    location: 0,
+                    binding: 0,
+                    format: grr::VertexFormat::Xyz32Float,
+                    offset: 0,
+                },
+                grr::VertexAttributeDesc {
+                    location: 1,
+                    binding: 0,
+                    format: grr::VertexFormat::Xy32Float,
+                    offset: 12,
+                },
+                grr::VertexAttributeDesc {
+                    location: 2,
+                    binding: 0,
+                    format: grr::VertexFormat::Xyz32Float,
+                    offset: 20,
+                },
+            ],
+            None,
+        )?;
 
         let depth_stencil_state = grr::DepthStencil {
             depth_test: true,
@@ -338,6 +351,7 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R16G16B16_SFLOAT,
             hdr_image_levels,
+            None,
         )?;
 
         println!("Uploading HDR image into GPU memory");
@@ -367,22 +381,25 @@ fn main() -> anyhow::Result<()> {
 
         grr.generate_mipmaps(hdr_texture);
 
-        let hdr_sampler = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: None,
-            address: (
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..10.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
+        let hdr_sampler = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: None,
+                address: (
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..10.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
+        )?;
 
-        let empty_vertex_array = grr.create_vertex_array(&[])?;
+        let empty_vertex_array = grr.create_vertex_array(&[], None)?;
 
         println!("Creating Env Cubemap");
         let env_size = 512;
@@ -395,6 +412,7 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R16G16B16_SFLOAT,
             1,
+            None,
         )?;
 
         let env_cubemap_view = grr.create_image_view(
@@ -405,24 +423,28 @@ fn main() -> anyhow::Result<()> {
                 layers: 0..6,
                 levels: 0..1,
             },
+            None,
         )?;
 
-        let env_cubemap_sampler = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: Some(grr::Filter::Linear),
-            address: (
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..10.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
+        let env_cubemap_sampler = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: Some(grr::Filter::Linear),
+                address: (
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..10.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
+        )?;
 
-        let env_proj_fbo = grr.create_framebuffer()?;
+        let env_proj_fbo = grr.create_framebuffer(None)?;
 
         let env_proj = glm::perspective(1.0, glm::half_pi(), 0.1, 10.0);
         let env_eye = glm::vec3(0.0, 0.0, 0.0);
@@ -498,12 +520,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap.vs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let cubemap_proj_fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap_proj.fs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let cubemap_proj_pipeline = grr.create_graphics_pipeline(
@@ -515,6 +539,7 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(cubemap_proj_fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
         grr.bind_draw_framebuffer(env_proj_fbo);
@@ -553,6 +578,7 @@ fn main() -> anyhow::Result<()> {
                     layers: i..i + 1,
                     levels: 0..1,
                 },
+                None,
             )?;
 
             grr.bind_attachments(
@@ -582,12 +608,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/brdf_integration.vs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let brdf_integration_fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/brdf_integration.fs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let brdf_integration_pipeline = grr.create_graphics_pipeline(
@@ -599,6 +627,7 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(brdf_integration_fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
         let brdf_lut = grr.create_image(
@@ -610,24 +639,28 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R16G16_SFLOAT,
             1,
+            None,
         )?;
 
-        let brdf_lut_sampler = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: None,
-            address: (
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..10.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
+        let brdf_lut_sampler = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: None,
+                address: (
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..10.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
+        )?;
 
-        let brdf_fbo = grr.create_framebuffer()?;
+        let brdf_fbo = grr.create_framebuffer(None)?;
         grr.bind_pipeline(brdf_integration_pipeline);
         grr.bind_draw_framebuffer(brdf_fbo);
         grr.bind_attachments(
@@ -668,12 +701,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap.vs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let env_irradiance_fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap_irradiance.fs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let env_irradiance_pipeline = grr.create_graphics_pipeline(
@@ -685,6 +720,7 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(env_irradiance_fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
         let env_irradiance_size = 32;
@@ -697,6 +733,7 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R16G16B16_SFLOAT,
             1,
+            None,
         )?;
         let env_irradiance_view = grr.create_image_view(
             env_irradiance,
@@ -706,23 +743,27 @@ fn main() -> anyhow::Result<()> {
                 layers: 0..6,
                 levels: 0..1,
             },
+            None,
+        )?;
+        let env_irradiance_sampler = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: None,
+                address: (
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..10.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
         )?;
-        let env_irradiance_sampler = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: None,
-            address: (
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..10.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
 
-        let env_irradiance_fbo = grr.create_framebuffer()?;
+        let env_irradiance_fbo = grr.create_framebuffer(None)?;
         grr.bind_draw_framebuffer(env_irradiance_fbo);
         grr.set_color_attachments(env_irradiance_fbo, &[0]);
         grr.set_viewport(
@@ -758,6 +799,7 @@ fn main() -> anyhow::Result<()> {
                     layers: i..i + 1,
                     levels: 0..1,
                 },
+                None,
             )?;
 
             grr.bind_attachments(
@@ -787,12 +829,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap.vs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let env_prefilter_fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap_specular_filtered.fs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let env_prefilter_pipeline = grr.create_graphics_pipeline(
@@ -804,6 +848,7 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(env_prefilter_fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
         let num_prefiltered_levels = 5;
@@ -817,6 +862,7 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R16G16B16_SFLOAT,
             num_prefiltered_levels,
+            None,
         )?;
         let env_prefiltered_view = grr.create_image_view(
             env_prefiltered,
@@ -826,24 +872,28 @@ fn main() -> anyhow::Result<()> {
                 layers: 0..6,
                 levels: 0..num_prefiltered_levels,
             },
+            None,
         )?;
 
-        let env_prefiltered_sampler = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: Some(grr::Filter::Linear),
-            address: (
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..1024.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
+        let env_prefiltered_sampler = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: Some(grr::Filter::Linear),
+                address: (
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..1024.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
+        )?;
 
-        let env_prefilter_fbo = grr.create_framebuffer()?;
+        let env_prefilter_fbo = grr.create_framebuffer(None)?;
         grr.bind_pipeline(env_prefilter_pipeline);
         grr.bind_image_views(0, &[env_cubemap_view]);
         grr.bind_samplers(0, &[env_cubemap_sampler]);
@@ -903,6 +953,7 @@ fn main() -> anyhow::Result<()> {
                         layers: face..face + 1,
                         levels: mip..mip + 1,
                     },
+                    None,
                 )?;
 
                 grr.bind_attachments(
@@ -925,12 +976,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/cubemap.vs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let skybox_fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             include_bytes!("assets/Shaders/skybox.fs"),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let skybox_pipeline = grr.create_graphics_pipeline(
@@ -942,6 +995,7 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(skybox_fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
         // Scene description
@@ -75,9 +75,9 @@ fn main() -> anyhow::Result<()> {
         let grr = grr::Device::new(
             |symbol| context.get_proc_address(symbol) as *const _,
             grr::Debug::Enable {
-                callback: |_, _, _, _, msg| {
-                    println!("{:?}", msg);
-                },
+                callback: Box::new(|msg| {
+                    println!("{:?}", msg.message);
+                }),
                 flags: grr::DebugReport::FULL,
             },
         );
@@ -87,12 +87,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             VERTEX_SRC.as_bytes(),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             FRAGMENT_SRC.as_bytes(),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let pipeline = grr.create_graphics_pipeline(
@@ -104,27 +106,31 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
-        let vertex_array = grr.create_vertex_array(&[
-            grr::VertexAttributeDesc {
-                location: 0,
-                binding: 0,
-                format: grr::VertexFormat::Xy32Float,
-                offset: 0,
-            },
-            grr::VertexAttributeDesc {
-                location: 1,
-                binding: 0,
-                format: grr::VertexFormat::Xy32Float,
-                offset: (2 * std::mem::size_of::<f32>()) as _,
-            },
-        ])?;
+        let vertex_array = grr.create_vertex_array(
+            &[
+                grr::VertexAttributeDesc {
+                    location: 0,
+                    binding: 0,
+                    format: grr::VertexFormat::Xy32Float,
+                    offset: 0,
+                },
+                grr::VertexAttributeDesc {
+                    location: 1,
+                    binding: 0,
+                    format: grr::VertexFormat::Xy32Float,
+                    offset: (2 * std::mem::size_of::<f32>()) as _,
+                },
+            ],
+            None,
+        )?;
 
         let vertex_buffer =
-            grr.create_buffer_from_host(grr::as_u8_slice(&VERTICES), grr::MemoryFlags::empty())?;
+            grr.create_buffer_from_host(grr::as_bytes(&VERTICES), grr::MemoryFlags::empty(), None)?;
         let index_buffer =
-            grr.create_buffer_from_host(grr::as_u8_slice(&INDICES), grr::MemoryFlags::empty())?;
+            grr.create_buffer_from_host(grr::as_bytes(&INDICES), grr::MemoryFlags::empty(), None)?;
 
         let img = image::open(&Path::new("info/grr_logo.png"))
             .unwrap()
@@ -142,6 +148,7 @@ fn main() -> anyhow::Result<()> {
             },
             grr::Format::R8G8B8A8_SRGB,
             1,
+            None,
         )?;
         grr.object_name(texture, "grr logo");
 
@@ -169,20 +176,23 @@ fn main() -> anyhow::Result<()> {
             },
         );
 
-        let sampler = grr.create_sampler(grr::SamplerDesc {
-            min_filter: grr::Filter::Linear,
-            mag_filter: grr::Filter::Linear,
-            mip_map: None,
-            address: (
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-                grr::SamplerAddress::ClampEdge,
-            ),
-            lod_bias: 0.0,
-            lod: 0.0..10.0,
-            compare: None,
-            border_color: [0.0, 0.0, 0.0, 1.0],
-        })?;
+        let sampler = grr.create_sampler(
+            grr::SamplerDesc {
+                min_filter: grr::Filter::Linear,
+                mag_filter: grr::Filter::Linear,
+                mip_map: None,
+                address: (
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                    grr::SamplerAddress::ClampEdge,
+                ),
+                lod_bias: 0.0,
+                lod: 0.0..10.0,
+                compare: None,
+                border_color: [0.0, 0.0, 0.0, 1.0],
+            },
+            None,
+        )?;
 
         let color_blend = grr::ColorBlend {
             attachments: vec![grr::ColorBlendAttachment {
@@ -66,9 +66,9 @@ fn main() -> anyhow::Result<()> {
         let grr = grr::Device::new(
             |symbol| context.get_proc_address(symbol) as *const _,
             grr::Debug::Enable {
-                callback: |report, _, _, _, msg| {
-                    println!("{:?}: {:?}", report, msg);
-                },
+                callback: Box::new(|msg| {
+                    println!("{:?}: {:?}", msg.severity, msg.message);
+                }),
                 flags: grr::DebugReport::FULL,
             },
         );
@@ -78,12 +78,14 @@ fn main() -> anyhow::Result<()> {
             grr::ShaderSource::Glsl,
             VERTEX_SRC.as_bytes(),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
         let fs = grr.create_shader(
             grr::ShaderStage::Fragment,
             grr::ShaderSource::Glsl,
             FRAGMENT_SRC.as_bytes(),
             grr::ShaderFlags::VERBOSE,
+            None,
         )?;
 
         let pipeline = grr.create_graphics_pipeline(
@@ -95,25 +97,29 @@ fn main() -> anyhow::Result<()> {
                 fragment_shader: Some(fs),
             },
             grr::PipelineFlags::VERBOSE,
+            None,
         )?;
 
-        let vertex_array = grr.create_vertex_array(&[
-            grr::VertexAttributeDesc {
-                location: 0,
-                binding: 0,
-                format: grr::VertexFormat::Xy32Float,
-                offset: 0,
-            },
-            grr::VertexAttributeDesc {
-                location: 1,
-                binding: 0,
-                format: grr::VertexFormat::Xyz32Float,
-                offset: (2 * std::mem::size_of::<f32>()) as _,
-            },
-        ])?;
+        let vertex_array = grr.create_vertex_array(
+            &[
+                grr::VertexAttributeDesc {
+                    location: 0,
+                    binding: 0,
+                    format: grr::VertexFormat::Xy32Float,
+                    offset: 0,
+                },
+                grr::VertexAttributeDesc {
+                    location: 1,
+                    binding: 0,
+                    format: grr::VertexFormat::Xyz32Float,
+                    offset: (2 * std::mem::size_of::<f32>()) as _,
+                },
+            ],
+            None,
+        )?;
 
         let triangle_data =
-            grr.create_buffer_from_host(grr::as_u8_slice(&VERTICES), grr::MemoryFlags::empty())?;
+            grr.create_buffer_from_host(grr::as_bytes(&VERTICES), grr::MemoryFlags::empty(), None)?;
 
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
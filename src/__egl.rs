@@ -0,0 +1,17 @@
+#![allow(clippy::all)]
+#![allow(non_camel_case_types)]
+
+use std::os::raw;
+
+pub type khronos_utime_nanoseconds_t = u64;
+pub type khronos_uint64_t = u64;
+pub type khronos_ssize_t = isize;
+pub type EGLint = i32;
+pub type EGLNativeDisplayType = *const raw::c_void;
+pub type EGLNativePixmapType = *const raw::c_void;
+pub type EGLNativeWindowType = *const raw::c_void;
+pub type NativeDisplayType = EGLNativeDisplayType;
+pub type NativePixmapType = EGLNativePixmapType;
+pub type NativeWindowType = EGLNativeWindowType;
+
+include!(concat!(env!("OUT_DIR"), "/egl_bindings.rs"));
@@ -0,0 +1,653 @@
+//! GL backends.
+//!
+//! All GL entry points used by `grr` are routed through the [`Backend`](trait.Backend.html) trait.
+//! Devices usually run on the function pointers loaded from a GL context (`__gl::Gl`),
+//! but may also be created with a [`RecordingBackend`](struct.RecordingBackend.html) for testing
+//! code built on top of `grr` without a live context.
+
+#![allow(non_snake_case, clippy::too_many_arguments)]
+
+use crate::__gl;
+use crate::__gl::types::*;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::os::raw::c_void;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::{fmt, ptr, slice};
+
+macro_rules! backend {
+    ($(
+        fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)? $(=> |$rec:ident| $fake:expr)?;
+    )*) => {
+        /// GL entry points used by the device.
+        ///
+        /// Each function has the same signature as the corresponding GL function.
+        pub trait Backend {
+            /// Underlying GL context, if the backend is backed by one.
+            fn as_gl(&self) -> Option<&__gl::Gl> {
+                None
+            }
+
+            $(unsafe fn $name(&self, $($arg: $ty),*) $(-> $ret)?;)*
+        }
+
+        impl Backend for __gl::Gl {
+            fn as_gl(&self) -> Option<&__gl::Gl> {
+                Some(self)
+            }
+
+            $(
+                #[inline]
+                unsafe fn $name(&self, $($arg: $ty),*) $(-> $ret)? {
+                    __gl::Gl::$name(self, $($arg),*)
+                }
+            )*
+        }
+
+        impl Backend for RecordingBackend {
+            $(
+                unsafe fn $name(&self, $($arg: $ty),*) $(-> $ret)? {
+                    self.record(stringify!($name), vec![$(Arg::from($arg)),*]);
+                    backend!(@fake self $(, |$rec| $fake)?)
+                }
+            )*
+        }
+    };
+    (@fake $self:ident, |$rec:ident| $fake:expr) => {{
+        let $rec = $self;
+        $fake
+    }};
+    (@fake $self:ident) => {
+        Default::default()
+    };
+}
+
+backend! {
+    fn ActiveTexture(texture: GLenum);
+    fn AttachShader(program: GLuint, shader: GLuint);
+    fn BeginConditionalRender(id: GLuint, mode: GLenum);
+    fn BeginQueryIndexed(target: GLenum, index: GLuint, id: GLuint);
+    fn BindBuffer(target: GLenum, buffer: GLuint);
+    fn BindBufferBase(target: GLenum, index: GLuint, buffer: GLuint);
+    fn BindBufferRange(target: GLenum, index: GLuint, buffer: GLuint, offset: GLintptr, size: GLsizeiptr);
+    fn BindBuffersBase(target: GLenum, first: GLuint, count: GLsizei, buffers: *const GLuint);
+    fn BindBuffersRange(target: GLenum, first: GLuint, count: GLsizei, buffers: *const GLuint, offsets: *const GLintptr, sizes: *const GLsizeiptr);
+    fn BindFramebuffer(target: GLenum, framebuffer: GLuint);
+    fn BindImageTexture(unit: GLuint, texture: GLuint, level: GLint, layered: GLboolean, layer: GLint, access: GLenum, format: GLenum);
+    fn BindImageTextures(first: GLuint, count: GLsizei, textures: *const GLuint);
+    fn BindSamplers(first: GLuint, count: GLsizei, samplers: *const GLuint);
+    fn BindTexture(target: GLenum, texture: GLuint);
+    fn BindTextures(first: GLuint, count: GLsizei, textures: *const GLuint);
+    fn BindVertexArray(array: GLuint);
+    fn BlendEquationSeparatei(buf: GLuint, modeRGB: GLenum, modeAlpha: GLenum);
+    fn BlendFuncSeparatei(buf: GLuint, srcRGB: GLenum, dstRGB: GLenum, srcAlpha: GLenum, dstAlpha: GLenum);
+    fn BlitNamedFramebuffer(readFramebuffer: GLuint, drawFramebuffer: GLuint, srcX0: GLint, srcY0: GLint, srcX1: GLint, srcY1: GLint, dstX0: GLint, dstY0: GLint, dstX1: GLint, dstY1: GLint, mask: GLbitfield, filter: GLenum);
+    fn ClearNamedBufferSubData(buffer: GLuint, internalformat: GLenum, offset: GLintptr, size: GLsizeiptr, format: GLenum, type_: GLenum, data: *const c_void);
+    fn ClearNamedFramebufferfi(framebuffer: GLuint, buffer: GLenum, drawbuffer: GLint, depth: GLfloat, stencil: GLint);
+    fn ClearNamedFramebufferfv(framebuffer: GLuint, buffer: GLenum, drawbuffer: GLint, value: *const GLfloat);
+    fn ClearNamedFramebufferiv(framebuffer: GLuint, buffer: GLenum, drawbuffer: GLint, value: *const GLint);
+    fn ClearNamedFramebufferuiv(framebuffer: GLuint, buffer: GLenum, drawbuffer: GLint, value: *const GLuint);
+    fn ClientWaitSync(sync: GLsync, flags: GLbitfield, timeout: GLuint64) -> GLenum => |rec| rec.lock().wait_status.unwrap_or(__gl::ALREADY_SIGNALED);
+    fn ClipControl(origin: GLenum, depth: GLenum);
+    fn CompileShader(shader: GLuint);
+    fn CopyImageSubData(srcName: GLuint, srcTarget: GLenum, srcLevel: GLint, srcX: GLint, srcY: GLint, srcZ: GLint, dstName: GLuint, dstTarget: GLenum, dstLevel: GLint, dstX: GLint, dstY: GLint, dstZ: GLint, srcWidth: GLsizei, srcHeight: GLsizei, srcDepth: GLsizei);
+    fn CopyNamedBufferSubData(readBuffer: GLuint, writeBuffer: GLuint, readOffset: GLintptr, writeOffset: GLintptr, size: GLsizeiptr);
+    fn CreateBuffers(n: GLsizei, buffers: *mut GLuint) => |rec| rec.gen_names(n, buffers);
+    fn CreateFramebuffers(n: GLsizei, framebuffers: *mut GLuint) => |rec| rec.gen_names(n, framebuffers);
+    fn CreateProgram() -> GLuint => |rec| rec.gen_name();
+    fn CreateQueries(target: GLenum, n: GLsizei, ids: *mut GLuint) => |rec| rec.gen_names(n, ids);
+    fn CreateRenderbuffers(n: GLsizei, renderbuffers: *mut GLuint) => |rec| rec.gen_names(n, renderbuffers);
+    fn CreateSamplers(n: GLsizei, samplers: *mut GLuint) => |rec| rec.gen_names(n, samplers);
+    fn CreateShader(type_: GLenum) -> GLuint => |rec| rec.gen_name();
+    fn CreateTextures(target: GLenum, n: GLsizei, textures: *mut GLuint) => |rec| rec.gen_names(n, textures);
+    fn CreateVertexArrays(n: GLsizei, arrays: *mut GLuint) => |rec| rec.gen_names(n, arrays);
+    fn CullFace(mode: GLenum);
+    fn DebugMessageCallback(callback: GLDEBUGPROC, userParam: *const c_void);
+    fn DebugMessageControl(source: GLenum, type_: GLenum, severity: GLenum, count: GLsizei, ids: *const GLuint, enabled: GLboolean);
+    fn DebugMessageInsert(source: GLenum, type_: GLenum, id: GLuint, severity: GLenum, length: GLsizei, buf: *const GLchar);
+    fn DeleteBuffers(n: GLsizei, buffers: *const GLuint) => |rec| rec.delete_buffers(n, buffers);
+    fn DeleteFramebuffers(n: GLsizei, framebuffers: *const GLuint);
+    fn DeleteProgram(program: GLuint);
+    fn DeleteQueries(n: GLsizei, ids: *const GLuint);
+    fn DeleteRenderbuffers(n: GLsizei, renderbuffers: *const GLuint);
+    fn DeleteSamplers(count: GLsizei, samplers: *const GLuint);
+    fn DeleteShader(shader: GLuint);
+    fn DeleteSync(sync: GLsync);
+    fn DeleteTextures(n: GLsizei, textures: *const GLuint);
+    fn DeleteVertexArrays(n: GLsizei, arrays: *const GLuint);
+    fn DepthFunc(func: GLenum);
+    fn DepthMask(flag: GLboolean);
+    fn DepthRangeArrayv(first: GLuint, count: GLsizei, v: *const GLdouble);
+    fn DetachShader(program: GLuint, shader: GLuint);
+    fn Disable(cap: GLenum);
+    fn Disablei(target: GLenum, index: GLuint);
+    fn DispatchCompute(num_groups_x: GLuint, num_groups_y: GLuint, num_groups_z: GLuint);
+    fn DispatchComputeIndirect(indirect: GLintptr);
+    fn DrawArraysInstancedBaseInstance(mode: GLenum, first: GLint, count: GLsizei, instancecount: GLsizei, baseinstance: GLuint);
+    fn DrawElementsInstancedBaseVertexBaseInstance(mode: GLenum, count: GLsizei, type_: GLenum, indices: *const c_void, instancecount: GLsizei, basevertex: GLint, baseinstance: GLuint);
+    fn DrawMeshTasksNV(first: GLuint, count: GLuint);
+    fn Enable(cap: GLenum);
+    fn EnableVertexArrayAttrib(vaobj: GLuint, index: GLuint);
+    fn Enablei(target: GLenum, index: GLuint);
+    fn EndConditionalRender();
+    fn EndQueryIndexed(target: GLenum, index: GLuint);
+    fn FenceSync(condition: GLenum, flags: GLbitfield) -> GLsync => |rec| rec.gen_name() as usize as GLsync;
+    fn Finish();
+    fn Flush();
+    fn FlushMappedNamedBufferRange(buffer: GLuint, offset: GLintptr, length: GLsizeiptr);
+    fn FrontFace(mode: GLenum);
+    fn GenTextures(n: GLsizei, textures: *mut GLuint) => |rec| rec.gen_names(n, textures);
+    fn GenerateTextureMipmap(texture: GLuint);
+    fn GetBooleanv(pname: GLenum, data: *mut GLboolean);
+    fn GetDebugMessageLog(count: GLuint, bufSize: GLsizei, sources: *mut GLenum, types: *mut GLenum, ids: *mut GLuint, severities: *mut GLenum, lengths: *mut GLsizei, messageLog: *mut GLchar) -> GLuint;
+    fn GetDoublei_v(target: GLenum, index: GLuint, data: *mut GLdouble);
+    fn GetError() -> GLenum => |rec| rec.take_error();
+    fn GetFloati_v(target: GLenum, index: GLuint, data: *mut GLfloat);
+    fn GetFloatv(pname: GLenum, data: *mut GLfloat);
+    fn GetGraphicsResetStatus() -> GLenum;
+    fn GetInteger64v(pname: GLenum, data: *mut GLint64);
+    fn GetInteger64i_v(target: GLenum, index: GLuint, data: *mut GLint64);
+    fn GetIntegeri_v(target: GLenum, index: GLuint, data: *mut GLint);
+    fn GetIntegerv(pname: GLenum, data: *mut GLint) => |_rec| fake_integer(pname, data);
+    fn GetNamedBufferParameteri64v(buffer: GLuint, pname: GLenum, params: *mut GLint64) => |rec| rec.buffer_parameter(buffer, pname, params);
+    fn GetObjectLabel(identifier: GLenum, name: GLuint, bufSize: GLsizei, length: *mut GLsizei, label: *mut GLchar);
+    fn GetObjectPtrLabel(ptr: *const c_void, bufSize: GLsizei, length: *mut GLsizei, label: *mut GLchar);
+    fn GetProgramInfoLog(program: GLuint, bufSize: GLsizei, length: *mut GLsizei, infoLog: *mut GLchar);
+    fn GetProgramiv(program: GLuint, pname: GLenum, params: *mut GLint) => |_rec| fake_object_status(pname, params);
+    fn GetQueryObjectui64v(id: GLuint, pname: GLenum, params: *mut GLuint64);
+    fn GetQueryObjectuiv(id: GLuint, pname: GLenum, params: *mut GLuint);
+    fn GetShaderInfoLog(shader: GLuint, bufSize: GLsizei, length: *mut GLsizei, infoLog: *mut GLchar);
+    fn GetShaderiv(shader: GLuint, pname: GLenum, params: *mut GLint) => |_rec| fake_object_status(pname, params);
+    fn GetString(name: GLenum) -> *const GLubyte => |_rec| fake_string(name);
+    fn GetStringi(name: GLenum, index: GLuint) -> *const GLubyte => |_rec| fake_string(name);
+    fn GetSynciv(sync: GLsync, pname: GLenum, count: GLsizei, length: *mut GLsizei, values: *mut GLint) => |_rec| fake_sync_status(pname, values);
+    fn GetTextureSubImage(texture: GLuint, level: GLint, xoffset: GLint, yoffset: GLint, zoffset: GLint, width: GLsizei, height: GLsizei, depth: GLsizei, format: GLenum, type_: GLenum, bufSize: GLsizei, pixels: *mut c_void);
+    fn InvalidateBufferData(buffer: GLuint);
+    fn InvalidateBufferSubData(buffer: GLuint, offset: GLintptr, length: GLsizeiptr);
+    fn InvalidateNamedFramebufferSubData(framebuffer: GLuint, numAttachments: GLsizei, attachments: *const GLenum, x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+    fn IsEnabled(cap: GLenum) -> GLboolean;
+    fn IsEnabledi(target: GLenum, index: GLuint) -> GLboolean;
+    fn LinkProgram(program: GLuint);
+    fn MapNamedBufferRange(buffer: GLuint, offset: GLintptr, length: GLsizeiptr, access: GLbitfield) -> *mut c_void => |rec| rec.map_buffer(buffer, offset, length);
+    fn MemoryBarrier(barriers: GLbitfield);
+    fn MemoryBarrierByRegion(barriers: GLbitfield);
+    fn MinSampleShading(value: GLfloat);
+    fn MultiDrawArraysIndirect(mode: GLenum, indirect: *const c_void, drawcount: GLsizei, stride: GLsizei);
+    fn MultiDrawElementsIndirect(mode: GLenum, type_: GLenum, indirect: *const c_void, drawcount: GLsizei, stride: GLsizei);
+    fn MultiDrawMeshTasksIndirectCountNV(indirect: GLintptr, drawcount: GLintptr, maxdrawcount: GLsizei, stride: GLsizei);
+    fn MultiDrawMeshTasksIndirectNV(indirect: GLintptr, drawcount: GLsizei, stride: GLsizei);
+    fn NamedBufferStorage(buffer: GLuint, size: GLsizeiptr, data: *const c_void, flags: GLbitfield) => |rec| rec.buffer_storage(buffer, size, data);
+    fn NamedBufferSubData(buffer: GLuint, offset: GLintptr, size: GLsizeiptr, data: *const c_void) => |rec| rec.buffer_sub_data(buffer, offset, size, data);
+    fn NamedFramebufferDrawBuffers(framebuffer: GLuint, n: GLsizei, bufs: *const GLenum);
+    fn NamedFramebufferReadBuffer(framebuffer: GLuint, src: GLenum);
+    fn NamedFramebufferRenderbuffer(framebuffer: GLuint, attachment: GLenum, renderbuffertarget: GLenum, renderbuffer: GLuint);
+    fn NamedFramebufferTexture(framebuffer: GLuint, attachment: GLenum, texture: GLuint, level: GLint);
+    fn NamedRenderbufferStorage(renderbuffer: GLuint, internalformat: GLenum, width: GLsizei, height: GLsizei);
+    fn NamedRenderbufferStorageMultisample(renderbuffer: GLuint, samples: GLsizei, internalformat: GLenum, width: GLsizei, height: GLsizei);
+    fn ObjectLabel(identifier: GLenum, name: GLuint, length: GLsizei, label: *const GLchar);
+    fn ObjectPtrLabel(ptr: *const c_void, length: GLsizei, label: *const GLchar);
+    fn PixelStorei(pname: GLenum, param: GLint);
+    fn PolygonMode(face: GLenum, mode: GLenum);
+    fn PolygonOffset(factor: GLfloat, units: GLfloat);
+    fn PopDebugGroup();
+    fn PrimitiveRestartIndex(index: GLuint);
+    fn ProgramUniform1f(program: GLuint, location: GLint, v0: GLfloat);
+    fn ProgramUniform1i(program: GLuint, location: GLint, v0: GLint);
+    fn ProgramUniform1ui(program: GLuint, location: GLint, v0: GLuint);
+    fn ProgramUniform2f(program: GLuint, location: GLint, v0: GLfloat, v1: GLfloat);
+    fn ProgramUniform2i(program: GLuint, location: GLint, v0: GLint, v1: GLint);
+    fn ProgramUniform2ui(program: GLuint, location: GLint, v0: GLuint, v1: GLuint);
+    fn ProgramUniform3f(program: GLuint, location: GLint, v0: GLfloat, v1: GLfloat, v2: GLfloat);
+    fn ProgramUniform3i(program: GLuint, location: GLint, v0: GLint, v1: GLint, v2: GLint);
+    fn ProgramUniform3ui(program: GLuint, location: GLint, v0: GLuint, v1: GLuint, v2: GLuint);
+    fn ProgramUniform4f(program: GLuint, location: GLint, v0: GLfloat, v1: GLfloat, v2: GLfloat, v3: GLfloat);
+    fn ProgramUniform4i(program: GLuint, location: GLint, v0: GLint, v1: GLint, v2: GLint, v3: GLint);
+    fn ProgramUniform4ui(program: GLuint, location: GLint, v0: GLuint, v1: GLuint, v2: GLuint, v3: GLuint);
+    fn ProgramUniformMatrix2fv(program: GLuint, location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat);
+    fn ProgramUniformMatrix3fv(program: GLuint, location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat);
+    fn ProgramUniformMatrix4fv(program: GLuint, location: GLint, count: GLsizei, transpose: GLboolean, value: *const GLfloat);
+    fn PushDebugGroup(source: GLenum, id: GLuint, length: GLsizei, message: *const GLchar);
+    fn QueryCounter(id: GLuint, target: GLenum);
+    fn ReadnPixels(x: GLint, y: GLint, width: GLsizei, height: GLsizei, format: GLenum, type_: GLenum, bufSize: GLsizei, data: *mut c_void);
+    fn SampleMaski(maskNumber: GLuint, mask: GLbitfield);
+    fn SamplerParameterf(sampler: GLuint, pname: GLenum, param: GLfloat);
+    fn SamplerParameterfv(sampler: GLuint, pname: GLenum, param: *const GLfloat);
+    fn SamplerParameteri(sampler: GLuint, pname: GLenum, param: GLint);
+    fn ScissorArrayv(first: GLuint, count: GLsizei, v: *const GLint);
+    fn ShaderBinary(count: GLsizei, shaders: *const GLuint, binaryformat: GLenum, binary: *const c_void, length: GLsizei);
+    fn ShaderSource(shader: GLuint, count: GLsizei, string: *const *const GLchar, length: *const GLint);
+    fn SpecializeShader(shader: GLuint, pEntryPoint: *const GLchar, numSpecializationConstants: GLuint, pConstantIndex: *const GLuint, pConstantValue: *const GLuint);
+    fn StencilFuncSeparate(face: GLenum, func: GLenum, ref_: GLint, mask: GLuint);
+    fn StencilOpSeparate(face: GLenum, sfail: GLenum, dpfail: GLenum, dppass: GLenum);
+    fn TextureBarrier();
+    fn TextureBufferRange(texture: GLuint, internalformat: GLenum, buffer: GLuint, offset: GLintptr, size: GLsizeiptr);
+    fn TextureStorage1D(texture: GLuint, levels: GLsizei, internalformat: GLenum, width: GLsizei);
+    fn TextureStorage2D(texture: GLuint, levels: GLsizei, internalformat: GLenum, width: GLsizei, height: GLsizei);
+    fn TextureStorage3D(texture: GLuint, levels: GLsizei, internalformat: GLenum, width: GLsizei, height: GLsizei, depth: GLsizei);
+    fn TextureSubImage1D(texture: GLuint, level: GLint, xoffset: GLint, width: GLsizei, format: GLenum, type_: GLenum, pixels: *const c_void);
+    fn TextureSubImage2D(texture: GLuint, level: GLint, xoffset: GLint, yoffset: GLint, width: GLsizei, height: GLsizei, format: GLenum, type_: GLenum, pixels: *const c_void);
+    fn TextureSubImage3D(texture: GLuint, level: GLint, xoffset: GLint, yoffset: GLint, zoffset: GLint, width: GLsizei, height: GLsizei, depth: GLsizei, format: GLenum, type_: GLenum, pixels: *const c_void);
+    fn TextureView(texture: GLuint, target: GLenum, origtexture: GLuint, internalformat: GLenum, minlevel: GLuint, numlevels: GLuint, minlayer: GLuint, numlayers: GLuint);
+    fn UnmapNamedBuffer(buffer: GLuint) -> GLboolean => |_rec| __gl::TRUE;
+    fn UseProgram(program: GLuint);
+    fn VertexArrayAttribBinding(vaobj: GLuint, attribindex: GLuint, bindingindex: GLuint);
+    fn VertexArrayAttribFormat(vaobj: GLuint, attribindex: GLuint, size: GLint, type_: GLenum, normalized: GLboolean, relativeoffset: GLuint);
+    fn VertexArrayAttribIFormat(vaobj: GLuint, attribindex: GLuint, size: GLint, type_: GLenum, relativeoffset: GLuint);
+    fn VertexArrayAttribLFormat(vaobj: GLuint, attribindex: GLuint, size: GLint, type_: GLenum, relativeoffset: GLuint);
+    fn VertexArrayBindingDivisor(vaobj: GLuint, bindingindex: GLuint, divisor: GLuint);
+    fn VertexArrayElementBuffer(vaobj: GLuint, buffer: GLuint);
+    fn VertexArrayVertexBuffers(vaobj: GLuint, first: GLuint, count: GLsizei, buffers: *const GLuint, offsets: *const GLintptr, strides: *const GLsizei);
+    fn ViewportArrayv(first: GLuint, count: GLsizei, v: *const GLfloat);
+    fn WaitSync(sync: GLsync, flags: GLbitfield, timeout: GLuint64);
+}
+
+/// Argument of a recorded call.
+///
+/// Pointers are recorded by address only.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Arg {
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Ptr(usize),
+}
+
+macro_rules! impl_arg {
+    ($($variant:ident($base:ty): $($ty:ty),*;)*) => {
+        $($(
+            impl From<$ty> for Arg {
+                fn from(value: $ty) -> Self {
+                    Arg::$variant(value as $base)
+                }
+            }
+        )*)*
+    };
+}
+
+impl_arg! {
+    Int(i64): i32, i64, isize;
+    Uint(u64): u8, u32, u64;
+    Float(f64): f32, f64;
+}
+
+impl<T> From<*const T> for Arg {
+    fn from(value: *const T) -> Self {
+        Arg::Ptr(value as usize)
+    }
+}
+
+impl<T> From<*mut T> for Arg {
+    fn from(value: *mut T) -> Self {
+        Arg::Ptr(value as usize)
+    }
+}
+
+impl From<GLDEBUGPROC> for Arg {
+    fn from(value: GLDEBUGPROC) -> Self {
+        Arg::Ptr(value.map(|f| f as usize).unwrap_or(0))
+    }
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Arg::Int(v) => write!(fmt, "{}", v),
+            Arg::Uint(v) => write!(fmt, "{}", v),
+            Arg::Float(v) => write!(fmt, "{}", v),
+            Arg::Ptr(v) => write!(fmt, "0x{:x}", v),
+        }
+    }
+}
+
+/// Recorded GL call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    /// GL function name without `gl` prefix (e.g. `Enable`).
+    pub name: &'static str,
+    pub args: Vec<Arg>,
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}(", self.name)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, ", ")?;
+            }
+            write!(fmt, "{}", arg)?;
+        }
+        write!(fmt, ")")
+    }
+}
+
+#[derive(Default)]
+struct Recording {
+    calls: Vec<Call>,
+    next_name: GLuint,
+    buffers: HashMap<GLuint, Vec<u8>>,
+    /// Error flag returned by the next `GetError` call.
+    error: GLenum,
+    /// Result of fence waits, `ALREADY_SIGNALED` if not set.
+    wait_status: Option<GLenum>,
+}
+
+/// Mock backend logging every GL call.
+///
+/// No rendering is performed. Object creation hands out unique fake names,
+/// shader compilation and pipeline linking always succeed and buffers are backed
+/// by host memory to support mapping. Fences are always signaled. Queries return zero
+/// unless required for device creation. Out of range buffer accesses raise `INVALID_VALUE`.
+///
+/// The backend can be cloned to inspect the recorded calls after passing it to a device:
+///
+/// ```
+/// # unsafe {
+/// let recording = grr::RecordingBackend::new();
+/// let grr = grr::Device::with_backend(recording.clone(), grr::Debug::Disable);
+/// recording.clear();
+///
+/// grr.bind_input_assembly_state(grr::InputAssembly {
+///     primitive_restart: None,
+/// });
+/// assert_eq!(recording.calls()[0].name, "Disable");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct RecordingBackend(Arc<Mutex<Recording>>);
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return all calls recorded so far.
+    pub fn calls(&self) -> Vec<Call> {
+        self.lock().calls.clone()
+    }
+
+    /// Return and remove all calls recorded so far.
+    pub fn take_calls(&self) -> Vec<Call> {
+        std::mem::take(&mut self.lock().calls)
+    }
+
+    /// Remove all recorded calls.
+    pub fn clear(&self) {
+        self.lock().calls.clear();
+    }
+
+    /// Override the result of fence waits (e.g. `TIMEOUT_EXPIRED`).
+    #[cfg(test)]
+    pub(crate) fn set_wait_status(&self, status: GLenum) {
+        self.lock().wait_status = Some(status);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Recording> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn record(&self, name: &'static str, args: Vec<Arg>) {
+        self.lock().calls.push(Call { name, args });
+    }
+
+    fn gen_name(&self) -> GLuint {
+        let mut recording = self.lock();
+        recording.next_name += 1;
+        recording.next_name
+    }
+
+    unsafe fn gen_names(&self, n: GLsizei, names: *mut GLuint) {
+        for i in 0..n as usize {
+            *names.add(i) = self.gen_name();
+        }
+    }
+
+    unsafe fn buffer_storage(&self, buffer: GLuint, size: GLsizeiptr, data: *const c_void) {
+        let storage = if data.is_null() {
+            vec![0; size as usize]
+        } else {
+            slice::from_raw_parts(data as *const u8, size as usize).to_vec()
+        };
+        self.lock().buffers.insert(buffer, storage);
+    }
+
+    unsafe fn buffer_sub_data(
+        &self,
+        buffer: GLuint,
+        offset: GLintptr,
+        size: GLsizeiptr,
+        data: *const c_void,
+    ) {
+        let mut recording = self.lock();
+        let storage = match recording.buffers.get_mut(&buffer) {
+            Some(storage) => storage,
+            None => return,
+        };
+        match buffer_range(storage, offset, size) {
+            Some(range) => {
+                let src = slice::from_raw_parts(data as *const u8, range.len());
+                storage[range].copy_from_slice(src);
+            }
+            None => recording.set_error(__gl::INVALID_VALUE),
+        }
+    }
+
+    unsafe fn buffer_parameter(&self, buffer: GLuint, pname: GLenum, params: *mut GLint64) {
+        if pname == __gl::BUFFER_SIZE {
+            if let Some(storage) = self.lock().buffers.get(&buffer) {
+                *params = storage.len() as _;
+            }
+        }
+    }
+
+    unsafe fn map_buffer(
+        &self,
+        buffer: GLuint,
+        offset: GLintptr,
+        length: GLsizeiptr,
+    ) -> *mut c_void {
+        let mut recording = self.lock();
+        let storage = match recording.buffers.get_mut(&buffer) {
+            Some(storage) => storage,
+            None => return ptr::null_mut(),
+        };
+        match buffer_range(storage, offset, length) {
+            // The storage is never reallocated after creation.
+            Some(range) => storage[range].as_mut_ptr() as *mut _,
+            None => {
+                recording.set_error(__gl::INVALID_VALUE);
+                ptr::null_mut()
+            }
+        }
+    }
+
+    fn take_error(&self) -> GLenum {
+        std::mem::replace(&mut self.lock().error, __gl::NO_ERROR)
+    }
+
+    unsafe fn delete_buffers(&self, n: GLsizei, buffers: *const GLuint) {
+        let mut recording = self.lock();
+        for buffer in slice::from_raw_parts(buffers, n as usize) {
+            recording.buffers.remove(buffer);
+        }
+    }
+}
+
+impl Recording {
+    /// Raise an error, keeping the first error until it's queried.
+    fn set_error(&mut self, error: GLenum) {
+        if self.error == __gl::NO_ERROR {
+            self.error = error;
+        }
+    }
+}
+
+/// Byte range of a buffer access, `None` if out of bounds.
+fn buffer_range(storage: &[u8], offset: GLintptr, size: GLsizeiptr) -> Option<Range<usize>> {
+    let start = usize::try_from(offset).ok()?;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    if end <= storage.len() {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+unsafe fn fake_integer(pname: GLenum, data: *mut GLint) {
+    match pname {
+        __gl::MAJOR_VERSION => *data = 4,
+        __gl::MINOR_VERSION => *data = 6,
+        _ => (),
+    }
+}
+
+/// Fences are signaled immediately as no work is executed.
+unsafe fn fake_sync_status(pname: GLenum, values: *mut GLint) {
+    if pname == __gl::SYNC_STATUS {
+        *values = __gl::SIGNALED as _;
+    }
+}
+
+unsafe fn fake_object_status(pname: GLenum, params: *mut GLint) {
+    match pname {
+        __gl::COMPILE_STATUS | __gl::LINK_STATUS => *params = __gl::TRUE as _,
+        __gl::INFO_LOG_LENGTH => *params = 0,
+        _ => (),
+    }
+}
+
+fn fake_string(name: GLenum) -> *const GLubyte {
+    let string: &'static [u8] = match name {
+        __gl::VENDOR => b"grr\0",
+        __gl::RENDERER => b"RecordingBackend\0",
+        __gl::VERSION => b"4.6 (RecordingBackend)\0",
+        __gl::SHADING_LANGUAGE_VERSION => b"4.60\0",
+        _ => b"\0",
+    };
+    string.as_ptr()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::*;
+
+    /// Recorded calls without pointer arguments, which aren't stable across runs.
+    pub(crate) fn calls(recording: &RecordingBackend) -> Vec<(&'static str, Vec<Arg>)> {
+        recording
+            .take_calls()
+            .into_iter()
+            .map(|call| {
+                let args = call
+                    .args
+                    .into_iter()
+                    .filter(|arg| !matches!(arg, Arg::Ptr(_)))
+                    .collect();
+                (call.name, args)
+            })
+            .collect()
+    }
+
+    /// Device over a recording backend, without the calls of device creation.
+    pub(crate) fn device() -> (Device, RecordingBackend) {
+        let recording = RecordingBackend::new();
+        let grr = unsafe { Device::with_backend(recording.clone(), Debug::Disable) };
+        recording.clear();
+        (grr, recording)
+    }
+
+    #[test]
+    fn set_viewport() {
+        let (grr, recording) = device();
+        let viewport = Viewport {
+            x: 0.0,
+            y: 0.0,
+            w: 64.0,
+            h: 32.0,
+            n: 0.0,
+            f: 1.0,
+        };
+        unsafe { grr.set_viewport(1, &[viewport, viewport]) };
+        assert_eq!(
+            calls(&recording),
+            [
+                ("ViewportArrayv", vec![Arg::Uint(1), Arg::Int(2)]),
+                ("DepthRangeArrayv", vec![Arg::Uint(1), Arg::Int(2)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn bind_color_blend_state() {
+        let (grr, recording) = device();
+        let channel = BlendChannel {
+            src_factor: BlendFactor::SrcAlpha,
+            dst_factor: BlendFactor::OneMinusSrcAlpha,
+            blend_op: BlendOp::Add,
+        };
+        let state = ColorBlend {
+            attachments: vec![
+                ColorBlendAttachment {
+                    blend_enable: true,
+                    color: channel,
+                    alpha: channel,
+                },
+                ColorBlendAttachment {
+                    blend_enable: false,
+                    color: channel,
+                    alpha: channel,
+                },
+            ],
+        };
+        unsafe { grr.bind_color_blend_state(&state) };
+
+        let blend = Arg::Uint(__gl::BLEND as _);
+        let (src, dst) = (
+            Arg::Uint(__gl::SRC_ALPHA as _),
+            Arg::Uint(__gl::ONE_MINUS_SRC_ALPHA as _),
+        );
+        assert_eq!(
+            calls(&recording),
+            [
+                ("Enablei", vec![blend, Arg::Uint(0)]),
+                (
+                    "BlendEquationSeparatei",
+                    vec![
+                        Arg::Uint(0),
+                        Arg::Uint(__gl::FUNC_ADD as _),
+                        Arg::Uint(__gl::FUNC_ADD as _)
+                    ]
+                ),
+                ("BlendFuncSeparatei", vec![Arg::Uint(0), src, dst, src, dst]),
+                ("Disablei", vec![blend, Arg::Uint(1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_out_of_range() {
+        let recording = RecordingBackend::new();
+        let data = [1u8; 8];
+        unsafe {
+            recording.NamedBufferStorage(1, 16, ptr::null(), 0);
+            recording.NamedBufferSubData(1, 12, 8, data.as_ptr() as *const _);
+            assert_eq!(recording.GetError(), __gl::INVALID_VALUE);
+            assert_eq!(recording.GetError(), __gl::NO_ERROR);
+
+            assert!(recording
+                .MapNamedBufferRange(1, 8, 16, __gl::MAP_READ_BIT)
+                .is_null());
+            assert_eq!(recording.GetError(), __gl::INVALID_VALUE);
+
+            // The backend is still usable after the errors.
+            recording.NamedBufferSubData(1, 8, 8, data.as_ptr() as *const _);
+            assert_eq!(recording.GetError(), __gl::NO_ERROR);
+            let mapped = recording.MapNamedBufferRange(1, 0, 16, __gl::MAP_READ_BIT);
+            assert_eq!(
+                slice::from_raw_parts(mapped as *const u8, 16),
+                [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1]
+            );
+        }
+    }
+}
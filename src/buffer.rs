@@ -2,18 +2,28 @@
 
 use crate::__gl;
 use crate::__gl::types::{GLbitfield, GLuint};
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 use crate::{
     debug::{Object, ObjectType},
     device::Device,
     error::Result,
+    handle::{Generation, Handle},
+    pod::{as_bytes, Pod, Stride},
+    vertex::{InputRate, VertexBufferView},
     WHOLE_SIZE,
 };
 
-use std::{mem, ops::Range, ptr, slice};
+use std::{fmt, marker::PhantomData, ops::Range, ptr, slice};
 
 ///
-#[derive(Clone, Copy)]
-pub struct Buffer(pub(crate) GLuint, GLbitfield);
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Buffer(
+    pub(crate) GLuint,
+    pub(crate) GLbitfield,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for Buffer {
     const TYPE: ObjectType = ObjectType::Buffer;
@@ -22,9 +32,17 @@ impl Object for Buffer {
     }
 }
 
+impl Handle for Buffer {
+    fn generation(&self) -> Generation {
+        self.2
+    }
+}
+
 /// Buffer Range.
 ///
 /// Specifies a subrange of a buffer resource.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BufferRange {
     pub buffer: Buffer,
     pub offset: u64,
@@ -41,7 +59,118 @@ impl BufferRange {
     }
 }
 
+/// Buffer of elements of type `T`.
+///
+/// Sizes, offsets and ranges of typed buffers are specified in elements.
+/// The raw buffer can be used with all untyped buffer functions.
+///
+/// ```no_run
+/// # unsafe fn typed(grr: &grr::Device, vertex_array: grr::VertexArray) -> grr::Result<()> {
+/// grr::pod! {
+///     struct Vertex {
+///         pos: [f32; 2],
+///         color: [f32; 3],
+///     }
+/// }
+///
+/// let vertices = grr.create_typed_buffer::<Vertex>(3, grr::MemoryFlags::DYNAMIC, None)?;
+/// grr.copy_host_to_typed_buffer(vertices, 1, &[Vertex { pos: [0.0; 2], color: [1.0; 3] }]);
+/// grr.bind_vertex_buffers(vertex_array, 0, &[vertices.vertex_view(grr::InputRate::Vertex)]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TypedBuffer<T> {
+    buffer: Buffer,
+    len: u64,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Clone for TypedBuffer<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedBuffer<T> {}
+
+impl<T> fmt::Debug for TypedBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedBuffer")
+            .field("buffer", &self.buffer)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<T> Object for TypedBuffer<T> {
+    const TYPE: ObjectType = ObjectType::Buffer;
+    fn handle(&self) -> GLuint {
+        self.buffer.0
+    }
+}
+
+impl<T: Pod> TypedBuffer<T> {
+    /// Size in bytes of a single element.
+    ///
+    /// Zero-sized element types fail to compile:
+    ///
+    /// ```compile_fail
+    /// println!("{}", grr::TypedBuffer::<[u32; 0]>::STRIDE);
+    /// ```
+    pub const STRIDE: u64 = Stride::<T>::SIZE as u64;
+
+    /// Untyped buffer handle.
+    pub fn raw(&self) -> Buffer {
+        self.buffer
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns if the buffer has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Buffer range of the elements in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn range(&self, range: Range<u64>) -> BufferRange {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "element range {:?} out of bounds (len {})",
+            range,
+            self.len
+        );
+        BufferRange {
+            buffer: self.buffer,
+            offset: range.start * Self::STRIDE,
+            size: (range.end - range.start) * Self::STRIDE,
+        }
+    }
+
+    /// Buffer range of all elements.
+    pub fn whole(&self) -> BufferRange {
+        self.range(0..self.len)
+    }
+
+    /// Vertex buffer binding of all elements, with the element size as stride.
+    pub fn vertex_view(&self, input_rate: InputRate) -> VertexBufferView {
+        VertexBufferView {
+            buffer: self.buffer,
+            offset: 0,
+            stride: Self::STRIDE as _,
+            input_rate,
+        }
+    }
+}
+
 impl Device {
+    #[track_caller]
     unsafe fn create_buffer_impl(
         &self,
         size: isize,
@@ -77,7 +206,16 @@ impl Device {
             self.get_error()?;
         }
 
-        Ok(Buffer(buffer, flags))
+        let buffer = Buffer(
+            buffer,
+            flags,
+            self.create_handle(ObjectType::Buffer, buffer),
+        );
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Buffer, buffer.0, size as _);
+        #[cfg(feature = "validation")]
+        self.validator().create_buffer(buffer, size as _);
+        Ok(buffer)
     }
 
     /// Create a new empty buffer.
@@ -87,8 +225,24 @@ impl Device {
     /// - `size`: Length in bytes of the associated storage memory.
     /// - `memory`: Properties of the internal memory slice. Indicating the usage
     ///             and locality of the allocation.
-    pub unsafe fn create_buffer(&self, size: u64, memory: MemoryFlags) -> Result<Buffer> {
-        self.create_buffer_impl(size as _, ptr::null(), memory)
+    /// - `label`: Optional debug name of the buffer (see `object_name`).
+    #[track_caller]
+    pub unsafe fn create_buffer(
+        &self,
+        size: u64,
+        memory: MemoryFlags,
+        label: Option<&str>,
+    ) -> Result<Buffer> {
+        let _scope = self.api_scope("create_buffer");
+        let buffer = self.create_buffer_impl(size as _, ptr::null(), memory)?;
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateBuffer {
+            size,
+            memory,
+            buffer,
+        });
+        self.label_object(buffer, label);
+        Ok(buffer)
     }
 
     /// Create a new buffer from host memory data.
@@ -98,12 +252,24 @@ impl Device {
     /// - `data`: Host data, which will copied into the buffer on creation.
     /// - `memory`: Properties of the internal memory slice. Indicating the usage
     ///             and locality of the allocation.
+    /// - `label`: Optional debug name of the buffer (see `object_name`).
+    #[track_caller]
     pub unsafe fn create_buffer_from_host(
         &self,
         data: &[u8],
         memory: MemoryFlags,
+        label: Option<&str>,
     ) -> Result<Buffer> {
-        self.create_buffer_impl(data.len() as _, data.as_ptr() as *const _, memory)
+        let _scope = self.api_scope("create_buffer_from_host");
+        let buffer = self.create_buffer_impl(data.len() as _, data.as_ptr() as *const _, memory)?;
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateBufferFromHost {
+            data: data.to_vec(),
+            memory,
+            buffer,
+        });
+        self.label_object(buffer, label);
+        Ok(buffer)
     }
 
     /// Persistently map memory to host accessible virtual memory.
@@ -114,24 +280,35 @@ impl Device {
     /// - `range.start` must be smaller than `range.end`
     /// - `buffer` must be created with `CPU_MAP_READ` or `CPU_MAP_WRITE` flags.
     /// - `range.end - range.start` must be a multiple of the size of `T`
+    /// - `T` must not be zero-sized (e.g. `[u32; 0]`), checked at compile time.
     /// - If the buffer has not been created with `CPU_MAP_READ` the host should
     ///   not read from the returned slice.
     /// - If the buffer has not been created with `CPU_MAP_WRITE` the host should
     ///   not write to the returned slice.
     /// - A buffer can not be mapped multiple times.
+    /// - `INVALIDATE_RANGE`, `INVALIDATE_BUFFER` and `FLUSH_EXPLICIT` require the buffer
+    ///   to be created with `CPU_MAP_WRITE`.
+    /// - If `mapping` contains `INVALIDATE_RANGE` or `INVALIDATE_BUFFER` the host should
+    ///   not read from the returned slice.
     ///
     /// # Return
     ///
     /// Returns a typed slice of the mapped memory range.
     #[allow(clippy::mut_from_ref)]
-    pub unsafe fn map_buffer<T>(
+    #[track_caller]
+    pub unsafe fn map_buffer<T: Pod>(
         &self,
         buffer: Buffer,
         range: Range<u64>,
         mapping: MappingFlags,
     ) -> &mut [T] {
+        let _scope = self.api_scope("map_buffer");
+        self.check_handle("map_buffer", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().map_buffer(buffer, &range, mapping);
+
         let len = range.end - range.start;
-        let stride = mem::size_of::<T>();
+        let stride = Stride::<T>::SIZE;
         assert_eq!(len % stride as u64, 0);
 
         let mut flags = 0;
@@ -139,23 +316,106 @@ impl Device {
         if mapping.contains(MappingFlags::UNSYNCHRONIZED) {
             flags |= __gl::MAP_UNSYNCHRONIZED_BIT;
         }
+        if mapping.contains(MappingFlags::FLUSH_EXPLICIT) {
+            flags |= __gl::MAP_FLUSH_EXPLICIT_BIT;
+        }
         flags |= buffer.1
             & (__gl::MAP_COHERENT_BIT
                 | __gl::MAP_PERSISTENT_BIT
                 | __gl::MAP_READ_BIT
                 | __gl::MAP_WRITE_BIT);
-
-        let stride = mem::size_of::<T>();
+        // Invalidation is only allowed for write-only mappings.
+        if mapping.intersects(MappingFlags::INVALIDATE_RANGE | MappingFlags::INVALIDATE_BUFFER) {
+            flags &= !__gl::MAP_READ_BIT;
+            if mapping.contains(MappingFlags::INVALIDATE_RANGE) {
+                flags |= __gl::MAP_INVALIDATE_RANGE_BIT;
+            }
+            if mapping.contains(MappingFlags::INVALIDATE_BUFFER) {
+                flags |= __gl::MAP_INVALIDATE_BUFFER_BIT;
+            }
+        }
 
         let ptr = {
             self.0
                 .MapNamedBufferRange(buffer.0, range.start as _, len as _, flags)
                 as *mut _
         };
+        #[cfg(feature = "capture")]
+        self.capture_mapping(buffer, range, ptr as *mut u8, flags);
 
         slice::from_raw_parts_mut(ptr, len as usize / stride)
     }
 
+    /// Flush host writes to a subrange of a mapped buffer.
+    ///
+    /// Required for mappings with `FLUSH_EXPLICIT`, writes to the mapping are only
+    /// visible to the device after flushing the written ranges.
+    ///
+    /// # Parameters
+    ///
+    /// - `range`: Flushed range in bytes, relative to the start of the mapped range.
+    ///
+    /// # Valid usage
+    ///
+    /// - The buffer must be currently mapped with `FLUSH_EXPLICIT`.
+    /// - `range.start` must be smaller than `range.end`.
+    /// - `range.end` may not be larger than the size of the mapped range.
+    #[track_caller]
+    pub unsafe fn flush_mapped_buffer_range(&self, buffer: Buffer, range: Range<u64>) {
+        let _scope = self.api_scope("flush_mapped_buffer_range");
+        #[cfg(feature = "capture")]
+        self.capture_flush(buffer, range.clone());
+        self.check_handle("flush_mapped_buffer_range", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().flush_mapped_buffer_range(buffer, &range);
+        self.0.FlushMappedNamedBufferRange(
+            buffer.0,
+            range.start as _,
+            (range.end - range.start) as _,
+        );
+    }
+
+    /// Invalidate the content of a buffer.
+    ///
+    /// The content becomes undefined, allowing the driver to orphan the current memory
+    /// instead of waiting for pending commands using it.
+    ///
+    /// # Valid usage
+    ///
+    /// - The buffer must not be mapped, except persistently.
+    #[track_caller]
+    pub unsafe fn invalidate_buffer(&self, buffer: Buffer) {
+        let _scope = self.api_scope("invalidate_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::InvalidateBuffer { buffer });
+        self.check_handle("invalidate_buffer", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().invalidate_buffer(buffer, None);
+        self.0.InvalidateBufferData(buffer.0);
+    }
+
+    /// Invalidate the content of a subrange of a buffer.
+    ///
+    /// # Valid usage
+    ///
+    /// - `range.start` must be smaller than `range.end`.
+    /// - `range.end` may not be larger than the size of the buffer.
+    /// - The range must not be mapped, except persistently.
+    #[track_caller]
+    pub unsafe fn invalidate_buffer_range(&self, buffer: Buffer, range: Range<u64>) {
+        let _scope = self.api_scope("invalidate_buffer_range");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::InvalidateBufferRange {
+            buffer,
+            range: range.clone(),
+        });
+        self.check_handle("invalidate_buffer_range", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().invalidate_buffer(buffer, Some(&range));
+        self.0
+            .InvalidateBufferSubData(buffer.0, range.start as _, (range.end - range.start) as _);
+    }
+
     /// Unmap a buffer from virtual host memory.
     ///
     /// # Valid usage
@@ -165,24 +425,51 @@ impl Device {
     /// # Return
     ///
     /// Returns if the unmapping operation was successfull.
+    #[track_caller]
     pub unsafe fn unmap_buffer(&self, buffer: Buffer) -> bool {
+        let _scope = self.api_scope("unmap_buffer");
+        #[cfg(feature = "capture")]
+        self.capture_unmap(buffer);
+        self.check_handle("unmap_buffer", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().unmap_buffer(buffer);
         self.0.UnmapNamedBuffer(buffer.0) != 0
     }
 
     /// Delete a buffer.
+    #[track_caller]
     pub unsafe fn delete_buffer(&self, buffer: Buffer) {
+        let _scope = self.api_scope("delete_buffer");
         self.delete_buffers(&[buffer]);
     }
 
     /// Delete multiple buffers.
+    #[track_caller]
     pub unsafe fn delete_buffers(&self, buffers: &[Buffer]) {
+        let _scope = self.api_scope("delete_buffers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteBuffers {
+            buffers: buffers.to_vec(),
+        });
+        self.delete_handles("delete_buffers", buffers);
+        #[cfg(feature = "validation")]
+        self.validator().delete_buffers(buffers);
         let buffers = buffers.iter().map(|buffer| buffer.0).collect::<Vec<_>>();
 
         self.0.DeleteBuffers(buffers.len() as _, buffers.as_ptr());
     }
 
     /// Copy memory from the host into the buffer memory.
+    #[track_caller]
     pub unsafe fn copy_host_to_buffer(&self, buffer: Buffer, offset: isize, data: &[u8]) {
+        let _scope = self.api_scope("copy_host_to_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyHostToBuffer {
+            buffer,
+            offset,
+            data: data.to_vec(),
+        });
+        self.check_handle("copy_host_to_buffer", buffer);
         self.0
             .NamedBufferSubData(buffer.0, offset, data.len() as _, data.as_ptr() as *const _);
     }
@@ -190,7 +477,17 @@ impl Device {
     /// Bind buffer ranges as uniform buffers.
     ///
     /// Shader can access the buffer memory as readonly.
+    #[track_caller]
     pub unsafe fn bind_uniform_buffers(&self, first: u32, ranges: &[BufferRange]) {
+        let _scope = self.api_scope("bind_uniform_buffers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindUniformBuffers {
+            first,
+            ranges: ranges.to_vec(),
+        });
+        for range in ranges {
+            self.check_handle("bind_uniform_buffers", range.buffer);
+        }
         let buffers = ranges.iter().map(|view| view.buffer.0).collect::<Vec<_>>();
         let offsets = ranges
             .iter()
@@ -220,7 +517,17 @@ impl Device {
     /// Bind buffer ranges as shader storage buffers.
     ///
     /// Shaders can access the buffer memory as readwrite.
+    #[track_caller]
     pub unsafe fn bind_storage_buffers(&self, first: u32, ranges: &[BufferRange]) {
+        let _scope = self.api_scope("bind_storage_buffers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindStorageBuffers {
+            first,
+            ranges: ranges.to_vec(),
+        });
+        for range in ranges {
+            self.check_handle("bind_storage_buffers", range.buffer);
+        }
         let buffers = ranges.iter().map(|view| view.buffer.0).collect::<Vec<_>>();
         let offsets = ranges
             .iter()
@@ -248,22 +555,48 @@ impl Device {
     }
 
     /// Bind indirect buffer for draw commands.
+    #[track_caller]
     pub unsafe fn bind_draw_indirect_buffer(&self, buffer: Buffer) {
+        let _scope = self.api_scope("bind_draw_indirect_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindDrawIndirectBuffer { buffer });
+        self.check_handle("bind_draw_indirect_buffer", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().bind_draw_indirect_buffer(Some(buffer));
         self.0.BindBuffer(__gl::DRAW_INDIRECT_BUFFER, buffer.0);
     }
 
     /// Unbind indirect buffer for draw commands.
+    #[track_caller]
     pub unsafe fn unbind_draw_indirect_buffer(&self) {
+        let _scope = self.api_scope("unbind_draw_indirect_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::UnbindDrawIndirectBuffer);
+        #[cfg(feature = "validation")]
+        self.validator().bind_draw_indirect_buffer(None);
         self.0.BindBuffer(__gl::DRAW_INDIRECT_BUFFER, 0);
     }
 
     /// Bind indirect buffer for dispatch commands.
+    #[track_caller]
     pub unsafe fn bind_dispatch_indirect_buffer(&self, buffer: Buffer) {
+        let _scope = self.api_scope("bind_dispatch_indirect_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindDispatchIndirectBuffer { buffer });
+        self.check_handle("bind_dispatch_indirect_buffer", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().bind_dispatch_indirect_buffer(Some(buffer));
         self.0.BindBuffer(__gl::DISPATCH_INDIRECT_BUFFER, buffer.0);
     }
 
     /// Unbind indirect buffer for draw commands.
+    #[track_caller]
     pub unsafe fn unbind_dispatch_indirect_buffer(&self) {
+        let _scope = self.api_scope("unbind_dispatch_indirect_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::UnbindDispatchIndirectBuffer);
+        #[cfg(feature = "validation")]
+        self.validator().bind_dispatch_indirect_buffer(None);
         self.0.BindBuffer(__gl::DISPATCH_INDIRECT_BUFFER, 0);
     }
 
@@ -289,8 +622,13 @@ impl Device {
 
     /// Bind parameter buffer for indirect commands.
     ///
-    /// Requires GL 4.6
+    /// Requires GL 4.6 (`DeviceFeatures::draw_indirect_count`).
+    #[track_caller]
     pub unsafe fn bind_parameter_buffer(&self, buffer: Buffer) {
+        let _scope = self.api_scope("bind_parameter_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindParameterBuffer { buffer });
+        self.check_handle("bind_parameter_buffer", buffer);
         self.0.BindBuffer(__gl::PARAMETER_BUFFER, buffer.0);
     }
 
@@ -302,8 +640,121 @@ impl Device {
     }
 }
 
+impl Device {
+    /// Create a new empty buffer of `len` elements.
+    ///
+    /// See `create_buffer`.
+    #[track_caller]
+    pub unsafe fn create_typed_buffer<T: Pod>(
+        &self,
+        len: u64,
+        memory: MemoryFlags,
+        label: Option<&str>,
+    ) -> Result<TypedBuffer<T>> {
+        let _scope = self.api_scope("create_typed_buffer");
+        let buffer = self.create_buffer(len * TypedBuffer::<T>::STRIDE, memory, label)?;
+        Ok(TypedBuffer {
+            buffer,
+            len,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Create a new buffer from host elements.
+    ///
+    /// See `create_buffer_from_host`.
+    #[track_caller]
+    pub unsafe fn create_typed_buffer_from_host<T: Pod>(
+        &self,
+        data: &[T],
+        memory: MemoryFlags,
+        label: Option<&str>,
+    ) -> Result<TypedBuffer<T>> {
+        let _scope = self.api_scope("create_typed_buffer_from_host");
+        let buffer = self.create_buffer_from_host(as_bytes(data), memory, label)?;
+        Ok(TypedBuffer {
+            buffer,
+            len: data.len() as _,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Delete a typed buffer.
+    #[track_caller]
+    pub unsafe fn delete_typed_buffer<T: Pod>(&self, buffer: TypedBuffer<T>) {
+        let _scope = self.api_scope("delete_typed_buffer");
+        self.delete_buffer(buffer.buffer);
+    }
+
+    /// Copy elements from the host into the buffer, starting at element `first`.
+    ///
+    /// # Valid usage
+    ///
+    /// - The buffer must be created with `DYNAMIC` memory.
+    /// - `first + data.len()` must not be larger than the length of the buffer.
+    #[track_caller]
+    pub unsafe fn copy_host_to_typed_buffer<T: Pod>(
+        &self,
+        buffer: TypedBuffer<T>,
+        first: u64,
+        data: &[T],
+    ) {
+        let _scope = self.api_scope("copy_host_to_typed_buffer");
+        let range = buffer.range(first..first + data.len() as u64);
+        self.copy_host_to_buffer(buffer.buffer, range.offset as _, as_bytes(data));
+    }
+
+    /// Copy `len` elements between two typed buffers.
+    ///
+    /// See `copy_buffer`.
+    #[track_caller]
+    pub unsafe fn copy_typed_buffer<T: Pod>(
+        &self,
+        src_buffer: TypedBuffer<T>,
+        src_first: u64,
+        dst_buffer: TypedBuffer<T>,
+        dst_first: u64,
+        len: u64,
+    ) {
+        let _scope = self.api_scope("copy_typed_buffer");
+        let src = src_buffer.range(src_first..src_first + len);
+        let dst = dst_buffer.range(dst_first..dst_first + len);
+        self.copy_buffer(src.buffer, src.offset, dst.buffer, dst.offset, src.size);
+    }
+
+    /// Persistently map the elements in `range` to host accessible virtual memory.
+    ///
+    /// See `map_buffer`.
+    #[allow(clippy::mut_from_ref)]
+    #[track_caller]
+    pub unsafe fn map_typed_buffer<T: Pod>(
+        &self,
+        buffer: TypedBuffer<T>,
+        range: Range<u64>,
+        mapping: MappingFlags,
+    ) -> &mut [T] {
+        let _scope = self.api_scope("map_typed_buffer");
+        let range = buffer.range(range);
+        self.map_buffer(
+            buffer.buffer,
+            range.offset..range.offset + range.size,
+            mapping,
+        )
+    }
+
+    /// Unmap a typed buffer from virtual host memory.
+    ///
+    /// See `unmap_buffer`.
+    #[track_caller]
+    pub unsafe fn unmap_typed_buffer<T: Pod>(&self, buffer: TypedBuffer<T>) -> bool {
+        let _scope = self.api_scope("unmap_typed_buffer");
+        self.unmap_buffer(buffer.buffer)
+    }
+}
+
 bitflags!(
     /// Memory property flags.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct MemoryFlags: u8 {
         /// Device local memory on the GPU.
         const DEVICE_LOCAL = 0x1;
@@ -327,7 +778,22 @@ bitflags!(
     pub struct MappingFlags: u8 {
         /// Driver won't synchronize memory access.
         ///
-        /// The user needs to manually synchonize access via fences.
+        /// The user needs to manually synchronize access via fences (`Device::create_fence`).
         const UNSYNCHRONIZED = 0x1;
+
+        /// Previous content of the mapped range may be discarded.
+        ///
+        /// The mapping is write-only.
+        const INVALIDATE_RANGE = 0x2;
+
+        /// Previous content of the whole buffer may be discarded.
+        ///
+        /// Allows the driver to orphan the buffer memory for streaming.
+        /// The mapping is write-only.
+        const INVALIDATE_BUFFER = 0x4;
+
+        /// Host writes are only visible to the device after flushing them
+        /// via `Device::flush_mapped_buffer_range`.
+        const FLUSH_EXPLICIT = 0x8;
     }
 );
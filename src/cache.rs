@@ -0,0 +1,208 @@
+//! Redundant state elimination.
+//!
+//! Devices created with `DeviceDesc::state_cache` keep a shadow copy of the context state
+//! set by pipeline state, binding and capability calls. GL calls setting state which is already
+//! current are skipped.
+//!
+//! The cache assumes exclusive ownership of the context state. After modifying the state
+//! through the raw context, the cache needs to be invalidated via
+//! [`Device::invalidate_state_cache`](struct.Device.html#method.invalidate_state_cache).
+
+use crate::__gl::types::{GLenum, GLuint};
+use crate::device::Device;
+
+use std::collections::HashMap;
+
+/// Context state slot, set by a single GL call.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum StateKey {
+    Capability(GLenum),
+    CapabilityIndexed(GLenum, GLuint),
+    Program,
+    VertexArray,
+    Framebuffer(GLenum),
+    PrimitiveRestartIndex,
+    BlendEquation(GLuint),
+    BlendFunc(GLuint),
+    DepthMask,
+    DepthFunc,
+    StencilFunc(GLenum),
+    StencilOp(GLenum),
+    PolygonMode,
+    PolygonOffset,
+    FrontFace,
+    CullFace,
+    MinSampleShading,
+    SampleMask(GLuint),
+    ClipControl,
+}
+
+/// Statistics of the state cache.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct StateCacheStats {
+    /// Number of state changes issued to the context.
+    pub issued: u64,
+    /// Number of redundant state changes skipped as the state was already current.
+    pub elided: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct StateCache {
+    state: HashMap<StateKey, [u32; 4]>,
+    stats: StateCacheStats,
+}
+
+impl Device {
+    /// Check if a state needs to be set, updating the shadow state.
+    ///
+    /// Always true if the device has no state cache.
+    pub(crate) fn state_changed(&self, key: StateKey, value: &[u32]) -> bool {
+        let mut cache = self.1.state_cache.borrow_mut();
+        let cache = match *cache {
+            Some(ref mut cache) => cache,
+            None => return true,
+        };
+
+        let mut entry = [0; 4];
+        entry[..value.len()].copy_from_slice(value);
+        match cache.state.insert(key, entry) {
+            Some(current) if current == entry => {
+                cache.stats.elided += 1;
+                false
+            }
+            _ => {
+                cache.stats.issued += 1;
+                true
+            }
+        }
+    }
+
+    /// Remove a state from the shadow state, the next call will set it.
+    pub(crate) fn forget_state(&self, key: StateKey) {
+        if let Some(ref mut cache) = *self.1.state_cache.borrow_mut() {
+            cache.state.remove(&key);
+        }
+    }
+
+    pub(crate) unsafe fn set_capability(&self, cap: GLenum, enable: bool) {
+        if self.state_changed(StateKey::Capability(cap), &[enable as _]) {
+            if enable {
+                self.0.Enable(cap);
+            } else {
+                self.0.Disable(cap);
+            }
+        }
+    }
+
+    pub(crate) unsafe fn set_capability_indexed(&self, cap: GLenum, index: GLuint, enable: bool) {
+        if self.state_changed(StateKey::CapabilityIndexed(cap, index), &[enable as _]) {
+            if enable {
+                self.0.Enablei(cap, index);
+            } else {
+                self.0.Disablei(cap, index);
+            }
+        }
+    }
+
+    /// Invalidate the state cache.
+    ///
+    /// Required after modifying context state through the raw context (`Device::context`),
+    /// the next calls will set all state again.
+    pub fn invalidate_state_cache(&self) {
+        if let Some(ref mut cache) = *self.1.state_cache.borrow_mut() {
+            cache.state.clear();
+        }
+    }
+
+    /// Statistics of the state cache.
+    ///
+    /// Returns `None` if the device was created without state cache.
+    pub fn state_cache_stats(&self) -> Option<StateCacheStats> {
+        self.1
+            .state_cache
+            .borrow()
+            .as_ref()
+            .map(|cache| cache.stats)
+    }
+
+    /// Reset the statistics of the state cache.
+    pub fn reset_state_cache_stats(&self) {
+        if let Some(ref mut cache) = *self.1.state_cache.borrow_mut() {
+            cache.stats = StateCacheStats::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::__gl;
+    use crate::backend::tests::calls;
+    use crate::backend::Arg::{Int, Uint};
+    use crate::*;
+
+    #[test]
+    fn depth_stencil_state() {
+        let recording = RecordingBackend::new();
+        let grr = unsafe {
+            Device::with_backend_desc(recording.clone(), DeviceDesc::default().state_cache(true))
+        };
+        recording.clear();
+
+        let stencil = StencilFace {
+            fail: StencilOp::Zero,
+            pass: StencilOp::Replace,
+            depth_fail: StencilOp::Keep,
+            compare_op: Compare::Equal,
+            compare_mask: 0xFF,
+            reference: 1,
+        };
+        let state = DepthStencil {
+            depth_test: true,
+            depth_write: false,
+            depth_compare_op: Compare::LessEqual,
+            stencil_test: true,
+            stencil_front: stencil,
+            stencil_back: stencil,
+        };
+        let stencil_calls = |face| {
+            [
+                (
+                    "StencilFuncSeparate",
+                    vec![Uint(face as _), Uint(__gl::EQUAL as _), Int(1), Uint(0xFF)],
+                ),
+                (
+                    "StencilOpSeparate",
+                    vec![
+                        Uint(face as _),
+                        Uint(__gl::ZERO as _),
+                        Uint(__gl::KEEP as _),
+                        Uint(__gl::REPLACE as _),
+                    ],
+                ),
+            ]
+        };
+
+        unsafe {
+            grr.bind_depth_stencil_state(&state);
+            let mut expected = vec![
+                ("Enable", vec![Uint(__gl::DEPTH_TEST as _)]),
+                ("DepthMask", vec![Uint(__gl::FALSE as _)]),
+                ("DepthFunc", vec![Uint(__gl::LEQUAL as _)]),
+                ("Enable", vec![Uint(__gl::STENCIL_TEST as _)]),
+            ];
+            expected.extend(stencil_calls(__gl::FRONT));
+            expected.extend(stencil_calls(__gl::BACK));
+            assert_eq!(calls(&recording), expected);
+
+            grr.bind_depth_stencil_state(&state);
+            assert_eq!(calls(&recording), []);
+        }
+        assert_eq!(
+            grr.state_cache_stats(),
+            Some(StateCacheStats {
+                issued: 8,
+                elided: 8,
+            })
+        );
+    }
+}
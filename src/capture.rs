@@ -0,0 +1,1631 @@
+//! API capture and replay.
+//!
+//! A capture records every state changing `grr` call issued on a device, including host
+//! payloads (e.g. buffer or image uploads) and the handles of created objects.
+//! Captures can be replayed on another device, for example on a different driver or
+//! a [`RecordingBackend`](struct.RecordingBackend.html) to compare the issued GL calls.
+//!
+//! ## File format
+//!
+//! A capture starts with the magic bytes `GRRC`, followed by the format version as
+//! little-endian `u32`. The remaining stream is a sequence of [`Command`](enum.Command.html)s
+//! encoded with `bincode`.
+//!
+//! Object handles are stored with the GL names assigned at capture time. The replayer
+//! maps these names to the objects created during replay.
+//!
+//! Requires the `capture` feature.
+
+use crate::__gl;
+use crate::__gl::types::{GLbitfield, GLuint};
+use crate::handle::Generation;
+use crate::{
+    Attachment, AttachmentView, Barrier, Buffer, BufferImageCopy, BufferRange, ClearAttachment,
+    ClipDepth, ClipOrigin, ColorBlend, ConditionalMode, Constant, DebugReport, DebugSource,
+    DebugType, DepthStencil, Device, DrawIndexedIndirectCmd, DrawIndirectCmd, Error, Fence, Filter,
+    Format, Framebuffer, HostImageCopy, Image, ImageCopy, ImageType, ImageView, ImageViewType,
+    IndexTy, InputAssembly, MappingFlags, MemoryFlags, MemoryLayout, Multisample, ObjectType,
+    Pipeline, PipelineFlags, Primitive, Query, QueryType, Rasterization, Region, RegionBarrier,
+    Renderbuffer, Result, Sampler, SamplerDesc, Shader, ShaderFlags, ShaderSource, ShaderStage,
+    StateSnapshot, SubresourceRange, VertexArray, VertexAttributeDesc, VertexBufferView, Viewport,
+};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+use std::{fmt, ptr};
+
+const MAGIC: [u8; 4] = *b"GRRC";
+
+/// Version of the capture file format.
+///
+/// Bumped on every change of the [`Command`](enum.Command.html) encoding.
+pub const CAPTURE_VERSION: u32 = 2;
+
+/// Captured API call.
+///
+/// Each variant corresponds to a `Device` method of the same name.
+/// Handles of created objects are stored in the last field of the creating command.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Command {
+    CreateBuffer {
+        size: u64,
+        memory: MemoryFlags,
+        buffer: Buffer,
+    },
+    CreateBufferFromHost {
+        data: Vec<u8>,
+        memory: MemoryFlags,
+        buffer: Buffer,
+    },
+    /// Host writes to a mapped buffer range, recorded on `unmap_buffer`,
+    /// `flush_mapped_buffer_range` and before commands reading persistently mapped memory.
+    WriteMappedBuffer {
+        buffer: Buffer,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    DeleteBuffers {
+        buffers: Vec<Buffer>,
+    },
+    CopyHostToBuffer {
+        buffer: Buffer,
+        offset: isize,
+        data: Vec<u8>,
+    },
+    InvalidateBuffer {
+        buffer: Buffer,
+    },
+    InvalidateBufferRange {
+        buffer: Buffer,
+        range: Range<u64>,
+    },
+    BindUniformBuffers {
+        first: u32,
+        ranges: Vec<BufferRange>,
+    },
+    BindStorageBuffers {
+        first: u32,
+        ranges: Vec<BufferRange>,
+    },
+    BindDrawIndirectBuffer {
+        buffer: Buffer,
+    },
+    UnbindDrawIndirectBuffer,
+    BindDispatchIndirectBuffer {
+        buffer: Buffer,
+    },
+    UnbindDispatchIndirectBuffer,
+    BindParameterBuffer {
+        buffer: Buffer,
+    },
+
+    BindUniformConstants {
+        pipeline: Pipeline,
+        first: u32,
+        constants: Vec<Constant>,
+    },
+    SetViewport {
+        first: u32,
+        viewports: Vec<Viewport>,
+    },
+    SetScissor {
+        first: u32,
+        scissors: Vec<Region>,
+    },
+    SetClipControl {
+        origin: ClipOrigin,
+        depth: ClipDepth,
+    },
+    SetDepthBias {
+        constant_factor: f32,
+        slope_factor: f32,
+    },
+    Draw {
+        primitive: Primitive,
+        vertices: Range<u32>,
+        instance: Range<u32>,
+    },
+    DrawIndexed {
+        primitive: Primitive,
+        index_ty: IndexTy,
+        indices: Range<u32>,
+        instance: Range<u32>,
+        base_vertex: i32,
+    },
+    DrawIndirect {
+        primitive: Primitive,
+        offset: u64,
+        count: u32,
+        stride: u32,
+    },
+    DrawIndirectFromHost {
+        primitive: Primitive,
+        data: Vec<DrawIndirectCmd>,
+    },
+    DrawIndexedIndirect {
+        primitive: Primitive,
+        index_ty: IndexTy,
+        offset: u64,
+        count: u32,
+        stride: u32,
+    },
+    DrawIndexedIndirectFromHost {
+        primitive: Primitive,
+        index_ty: IndexTy,
+        data: Vec<DrawIndexedIndirectCmd>,
+    },
+    Dispatch {
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+    DispatchIndirect {
+        offset: u64,
+    },
+    Blit {
+        src: Framebuffer,
+        src_region: Region,
+        dst: Framebuffer,
+        dst_region: Region,
+        filter: Filter,
+    },
+    DrawMeshTasksNv {
+        task_count: u32,
+        first_task: u32,
+    },
+    DrawMeshTasksIndirectNv {
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    DrawMeshTasksIndirectCountNv {
+        offset: u64,
+        count_buffer_offset: u64,
+        max_draw_count: u32,
+        stride: u32,
+    },
+
+    CreateFramebuffer {
+        framebuffer: Framebuffer,
+    },
+    DeleteFramebuffers {
+        framebuffers: Vec<Framebuffer>,
+    },
+    CreateRenderbuffer {
+        format: Format,
+        width: u32,
+        height: u32,
+        samples: u32,
+        renderbuffer: Renderbuffer,
+    },
+    DeleteRenderbuffers {
+        renderbuffers: Vec<Renderbuffer>,
+    },
+    ClearAttachment {
+        framebuffer: Framebuffer,
+        clear: ClearAttachment,
+    },
+    InvalidateAttachments {
+        framebuffer: Framebuffer,
+        attachments: Vec<Attachment>,
+        region: Region,
+    },
+    BindFramebuffer {
+        framebuffer: Framebuffer,
+    },
+    BindDrawFramebuffer {
+        framebuffer: Framebuffer,
+    },
+    BindReadFramebuffer {
+        framebuffer: Framebuffer,
+    },
+    BindAttachments {
+        framebuffer: Framebuffer,
+        attachments: Vec<(Attachment, AttachmentView)>,
+    },
+    SetColorAttachments {
+        framebuffer: Framebuffer,
+        attachments: Vec<u32>,
+    },
+    SetReadAttachment {
+        framebuffer: Framebuffer,
+        attachment: u32,
+    },
+    SetFramebufferSrgb {
+        enable: bool,
+    },
+
+    CreateImage {
+        ty: ImageType,
+        format: Format,
+        levels: u32,
+        image: Image,
+    },
+    CreateTexelBuffer {
+        buffer: BufferRange,
+        format: Format,
+        image: Image,
+    },
+    DeleteImages {
+        images: Vec<Image>,
+    },
+    CreateImageView {
+        image: Image,
+        ty: ImageViewType,
+        format: Format,
+        range: SubresourceRange,
+        view: ImageView,
+    },
+    DeleteImageViews {
+        views: Vec<ImageView>,
+    },
+    BindImageViews {
+        first: u32,
+        views: Vec<ImageView>,
+    },
+    BindStorageImageViews {
+        first: u32,
+        views: Vec<ImageView>,
+    },
+    GenerateMipmaps {
+        image: Image,
+    },
+
+    CreateShader {
+        stage: ShaderStage,
+        /// SPIR-V entry point, `None` for GLSL shaders.
+        entrypoint: Option<String>,
+        source: Vec<u8>,
+        flags: ShaderFlags,
+        shader: Shader,
+    },
+    DeleteShaders {
+        shaders: Vec<Shader>,
+    },
+    CreatePipeline {
+        shaders: Vec<Shader>,
+        flags: PipelineFlags,
+        pipeline: Pipeline,
+    },
+    DeletePipelines {
+        pipelines: Vec<Pipeline>,
+    },
+    BindInputAssemblyState {
+        state: InputAssembly,
+    },
+    BindColorBlendState {
+        state: ColorBlend,
+    },
+    BindDepthStencilState {
+        state: DepthStencil,
+    },
+    BindRasterizationState {
+        state: Rasterization,
+    },
+    BindMultisampleState {
+        state: Option<Multisample>,
+    },
+    BindPipeline {
+        pipeline: Pipeline,
+    },
+
+    CreateQuery {
+        ty: QueryType,
+        query: Query,
+    },
+    DeleteQueries {
+        queries: Vec<Query>,
+    },
+    BeginQuery {
+        query: Query,
+    },
+    EndQuery {
+        query: Query,
+    },
+    WriteTimestamp {
+        query: Query,
+    },
+    BeginConditionalRendering {
+        query: Query,
+        mode: ConditionalMode,
+    },
+    EndConditionalRendering,
+
+    CreateSampler {
+        desc: SamplerDesc,
+        sampler: Sampler,
+    },
+    BindSamplers {
+        first: u32,
+        samplers: Vec<Sampler>,
+    },
+    DeleteSamplers {
+        samplers: Vec<Sampler>,
+    },
+
+    CreateVertexArray {
+        attributes: Vec<VertexAttributeDesc>,
+        vertex_array: VertexArray,
+    },
+    DeleteVertexArrays {
+        vertex_arrays: Vec<VertexArray>,
+    },
+    BindVertexArray {
+        vertex_array: VertexArray,
+    },
+    BindVertexBuffers {
+        vertex_array: VertexArray,
+        first: u32,
+        views: Vec<VertexBufferView>,
+    },
+    BindIndexBuffer {
+        vertex_array: VertexArray,
+        buffer: Buffer,
+    },
+
+    MemoryBarrier {
+        flags: Barrier,
+    },
+    MemoryBarrierByRegion {
+        flags: RegionBarrier,
+    },
+    CreateFence {
+        fence: u64,
+    },
+    DeleteFence {
+        fence: u64,
+    },
+    ServerWaitFence {
+        fence: u64,
+    },
+
+    CopyHostToImage {
+        data: Vec<u8>,
+        image: Image,
+        region: HostImageCopy,
+    },
+    CopyBufferToImage {
+        buffer: Buffer,
+        image: Image,
+        region: BufferImageCopy,
+    },
+    /// Readback into host memory of `size` bytes, the data itself isn't recorded.
+    CopyImageToHost {
+        image: Image,
+        size: usize,
+        region: HostImageCopy,
+    },
+    CopyImageToBuffer {
+        image: Image,
+        buffer: Buffer,
+        region: BufferImageCopy,
+    },
+    /// Readback into host memory of `size` bytes, the data itself isn't recorded.
+    CopyAttachmentToHost {
+        region: Region,
+        layout: MemoryLayout,
+        size: usize,
+    },
+    CopyAttachmentToBuffer {
+        region: Region,
+        layout: MemoryLayout,
+        buffer: BufferRange,
+    },
+    CopyImage {
+        src: Image,
+        dst: Image,
+        region: ImageCopy,
+    },
+    CopyBuffer {
+        src: Buffer,
+        src_offset: u64,
+        dst: Buffer,
+        dst_offset: u64,
+        size: u64,
+    },
+    FillBuffer {
+        buffer: BufferRange,
+        value: u32,
+    },
+    /// Restored context state, objects not created by the device are unbound on replay.
+    RestoreState {
+        snapshot: StateSnapshot,
+    },
+
+    ObjectName {
+        ty: ObjectType,
+        object: GLuint,
+        name: String,
+    },
+    BeginDebugMarker {
+        src: DebugSource,
+        id: u32,
+        msg: String,
+    },
+    EndDebugMarker,
+    InsertDebugMessage {
+        src: DebugSource,
+        ty: DebugType,
+        id: u32,
+        severity: DebugReport,
+        msg: String,
+    },
+}
+
+/// Active capture of a device.
+pub(crate) struct Capture {
+    writer: Box<dyn Write + Send>,
+    /// First error while writing, reported on `end_capture`.
+    error: Option<Error>,
+    /// Currently mapped buffer ranges by buffer name.
+    mappings: BTreeMap<GLuint, Mapping>,
+}
+
+/// Mapped buffer range.
+struct Mapping {
+    buffer: Buffer,
+    /// Address of the mapped range.
+    ptr: usize,
+    range: Range<u64>,
+    recorded: Recorded,
+}
+
+/// Host writes of a mapping recorded so far.
+enum Recorded {
+    /// Writes are only recorded on unmap and explicit flushes.
+    OnFlush,
+    /// Nothing recorded, the initial contents are undefined (invalidated range).
+    Nothing,
+    /// Contents of the mapping as of the last recorded write.
+    Contents(Vec<u8>),
+}
+
+impl Capture {
+    fn write(&mut self, command: &Command) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(err) = bincode::serialize_into(&mut self.writer, command) {
+            self.error = Some(capture_error(err));
+        }
+    }
+
+    /// Record host writes to persistent mappings since the last record.
+    unsafe fn write_mapped_changes(&mut self) {
+        let mut commands = Vec::new();
+        for mapping in self.mappings.values_mut() {
+            commands.extend(mapping.changes());
+        }
+        for command in commands {
+            self.write(&command);
+        }
+    }
+}
+
+impl Mapping {
+    unsafe fn data(&self) -> &[u8] {
+        let len = (self.range.end - self.range.start) as usize;
+        std::slice::from_raw_parts(self.ptr as *const u8, len)
+    }
+
+    /// Write of the changed part of the mapping, updating the recorded contents.
+    unsafe fn changes(&mut self) -> Option<Command> {
+        let data = self.data();
+        let (start, end) = match self.recorded {
+            Recorded::OnFlush => return None,
+            Recorded::Nothing => (0, data.len()),
+            Recorded::Contents(ref contents) => {
+                let changed = |(a, b): (&u8, &u8)| a != b;
+                let start = data.iter().zip(contents).position(changed)?;
+                let end = data.len()
+                    - data
+                        .iter()
+                        .rev()
+                        .zip(contents.iter().rev())
+                        .position(changed)?;
+                (start, end)
+            }
+        };
+        let command = Command::WriteMappedBuffer {
+            buffer: self.buffer,
+            offset: self.range.start + start as u64,
+            data: data[start..end].to_vec(),
+        };
+        self.recorded = Recorded::Contents(data.to_vec());
+        Some(command)
+    }
+}
+
+impl Command {
+    /// Check if the command may read buffer memory on the device.
+    fn reads_buffers(&self) -> bool {
+        matches!(
+            *self,
+            Command::Draw { .. }
+                | Command::DrawIndexed { .. }
+                | Command::DrawIndirect { .. }
+                | Command::DrawIndirectFromHost { .. }
+                | Command::DrawIndexedIndirect { .. }
+                | Command::DrawIndexedIndirectFromHost { .. }
+                | Command::Dispatch { .. }
+                | Command::DispatchIndirect { .. }
+                | Command::DrawMeshTasksNv { .. }
+                | Command::DrawMeshTasksIndirectNv { .. }
+                | Command::DrawMeshTasksIndirectCountNv { .. }
+                | Command::CopyBuffer { .. }
+                | Command::CopyBufferToImage { .. }
+        )
+    }
+}
+
+impl Device {
+    /// Start capturing all API calls into `writer`.
+    ///
+    /// The capture should be started directly after device creation, as objects created
+    /// beforehand are unknown to the replayer. Write errors are reported by `end_capture`.
+    ///
+    /// Host writes to mapped buffers are recorded when unmapping the buffer or flushing
+    /// the written range. Changes to mappings without `MappingFlags::FLUSH_EXPLICIT` are
+    /// additionally recorded before draws, dispatches and copies from buffers, which
+    /// covers persistently mapped buffers (e.g. `FrameRing`).
+    /// Calls querying data (e.g. `get_query_result_u32`, `limits`) aren't recorded.
+    pub fn begin_capture<W>(&self, writer: W) -> Result<()>
+    where
+        W: Write + Send + 'static,
+    {
+        let mut capture = self.1.capture.borrow_mut();
+        if capture.is_some() {
+            return Err(Error::Capture("capture already active".into()));
+        }
+
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(&MAGIC).map_err(capture_error)?;
+        writer
+            .write_all(&CAPTURE_VERSION.to_le_bytes())
+            .map_err(capture_error)?;
+
+        *capture = Some(Capture {
+            writer: Box::new(writer),
+            error: None,
+            mappings: BTreeMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Stop the active capture and flush all commands to the writer.
+    pub fn end_capture(&self) -> Result<()> {
+        let capture = self.1.capture.borrow_mut().take();
+        match capture {
+            Some(Capture {
+                mut writer, error, ..
+            }) => match error {
+                Some(err) => Err(err),
+                None => writer.flush().map_err(capture_error),
+            },
+            None => Err(Error::Capture("no active capture".into())),
+        }
+    }
+
+    /// Check if API calls are currently captured.
+    pub fn is_capturing(&self) -> bool {
+        self.1.capture.borrow().is_some()
+    }
+
+    pub(crate) fn capture<F>(&self, command: F)
+    where
+        F: FnOnce() -> Command,
+    {
+        if let Some(capture) = self.1.capture.borrow_mut().as_mut() {
+            let command = command();
+            if command.reads_buffers() {
+                // Mappings are valid until unmapped or deleted, which removes them.
+                unsafe { capture.write_mapped_changes() };
+            }
+            capture.write(&command);
+            if let Command::DeleteBuffers { ref buffers } = command {
+                for buffer in buffers {
+                    capture.mappings.remove(&buffer.0);
+                }
+            }
+        }
+    }
+
+    /// Track a new mapping of `buffer` with the GL mapping `flags`.
+    pub(crate) unsafe fn capture_mapping(
+        &self,
+        buffer: Buffer,
+        range: Range<u64>,
+        ptr: *mut u8,
+        flags: GLbitfield,
+    ) {
+        if ptr.is_null() {
+            return;
+        }
+        if let Some(capture) = self.1.capture.borrow_mut().as_mut() {
+            let write = flags & __gl::MAP_WRITE_BIT != 0;
+            let explicit = flags & __gl::MAP_FLUSH_EXPLICIT_BIT != 0;
+            let invalidate =
+                flags & (__gl::MAP_INVALIDATE_RANGE_BIT | __gl::MAP_INVALIDATE_BUFFER_BIT) != 0;
+            let recorded = if !write || explicit {
+                Recorded::OnFlush
+            } else if invalidate {
+                Recorded::Nothing
+            } else {
+                // Previous contents are already part of the capture.
+                let len = (range.end - range.start) as usize;
+                Recorded::Contents(std::slice::from_raw_parts(ptr, len).to_vec())
+            };
+            capture.mappings.insert(
+                buffer.0,
+                Mapping {
+                    buffer,
+                    ptr: ptr as usize,
+                    range,
+                    recorded,
+                },
+            );
+        }
+    }
+
+    pub(crate) unsafe fn capture_flush(&self, buffer: Buffer, range: Range<u64>) {
+        if let Some(capture) = self.1.capture.borrow_mut().as_mut() {
+            let command = match capture.mappings.get_mut(&buffer.0) {
+                Some(mapping) => {
+                    // Invalid ranges are reported by the device, only record the mapped part.
+                    let end = range.end.min(mapping.range.end - mapping.range.start);
+                    if range.start >= end {
+                        return;
+                    }
+                    let (start, end) = (range.start as usize, end as usize);
+                    let data = mapping.data()[start..end].to_vec();
+                    if let Recorded::Contents(ref mut contents) = mapping.recorded {
+                        contents[start..end].copy_from_slice(&data);
+                    }
+                    Command::WriteMappedBuffer {
+                        buffer,
+                        offset: mapping.range.start + start as u64,
+                        data,
+                    }
+                }
+                None => return,
+            };
+            capture.write(&command);
+        }
+    }
+
+    pub(crate) unsafe fn capture_unmap(&self, buffer: Buffer) {
+        if let Some(capture) = self.1.capture.borrow_mut().as_mut() {
+            if let Some(mut mapping) = capture.mappings.remove(&buffer.0) {
+                if buffer.1 & __gl::MAP_WRITE_BIT != 0 {
+                    let command = match mapping.recorded {
+                        Recorded::OnFlush => Some(Command::WriteMappedBuffer {
+                            buffer,
+                            offset: mapping.range.start,
+                            data: mapping.data().to_vec(),
+                        }),
+                        _ => mapping.changes(),
+                    };
+                    if let Some(command) = command {
+                        capture.write(&command);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reader for captured command streams.
+///
+/// Iterates over all commands of a capture, e.g. for inspecting or diffing captures.
+pub struct CaptureReader<R> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Open a capture stream, validating the header.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut reader = BufReader::new(reader);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).map_err(capture_error)?;
+        if magic != MAGIC {
+            return Err(Error::Capture("not a grr capture".into()));
+        }
+
+        let mut version = [0; 4];
+        reader.read_exact(&mut version).map_err(capture_error)?;
+        let version = u32::from_le_bytes(version);
+        if version != CAPTURE_VERSION {
+            return Err(Error::Capture(format!(
+                "unsupported capture version {} (expected {})",
+                version, CAPTURE_VERSION
+            )));
+        }
+
+        Ok(CaptureReader { reader })
+    }
+
+    fn read_command(&mut self) -> Result<Option<Command>> {
+        if self.reader.fill_buf().map_err(capture_error)?.is_empty() {
+            return Ok(None);
+        }
+        bincode::deserialize_from(&mut self.reader)
+            .map(Some)
+            .map_err(capture_error)
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<Command>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_command().transpose()
+    }
+}
+
+/// Replays captured commands on a device.
+///
+/// Objects are recreated on the target device, handles of subsequent commands are
+/// remapped to the newly created objects.
+///
+/// ## Example
+///
+/// ```no_run
+/// # unsafe fn replay(grr: &grr::Device) -> grr::Result<()> {
+/// let file = std::fs::File::open("frame.grrc").unwrap();
+/// let mut replayer = grr::Replayer::new();
+/// replayer.replay(grr, grr::CaptureReader::new(file)?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Replayer {
+    buffers: HashMap<GLuint, GLuint>,
+    textures: HashMap<GLuint, GLuint>,
+    samplers: HashMap<GLuint, GLuint>,
+    framebuffers: HashMap<GLuint, GLuint>,
+    renderbuffers: HashMap<GLuint, GLuint>,
+    vertex_arrays: HashMap<GLuint, GLuint>,
+    shaders: HashMap<GLuint, GLuint>,
+    pipelines: HashMap<GLuint, GLuint>,
+    queries: HashMap<GLuint, GLuint>,
+    fences: HashMap<u64, Fence>,
+}
+
+impl Replayer {
+    pub fn new() -> Self {
+        Replayer::default()
+    }
+
+    /// Execute all commands of a capture on `device`.
+    pub unsafe fn replay<R: Read>(
+        &mut self,
+        device: &Device,
+        reader: CaptureReader<R>,
+    ) -> Result<()> {
+        for command in reader {
+            self.execute(device, &command?)?;
+        }
+        Ok(())
+    }
+
+    /// Execute a single command on `device`.
+    pub unsafe fn execute(&mut self, device: &Device, command: &Command) -> Result<()> {
+        match *command {
+            Command::CreateBuffer {
+                size,
+                memory,
+                buffer,
+            } => {
+                let new = device.create_buffer(size, memory, None)?;
+                self.buffers.insert(buffer.0, new.0);
+            }
+            Command::CreateBufferFromHost {
+                ref data,
+                memory,
+                buffer,
+            } => {
+                let new = device.create_buffer_from_host(data, memory, None)?;
+                self.buffers.insert(buffer.0, new.0);
+            }
+            Command::WriteMappedBuffer {
+                buffer,
+                offset,
+                ref data,
+            } => {
+                let buffer = self.buffer(buffer)?;
+                let range = offset..offset + data.len() as u64;
+                let mapping = device.map_buffer::<u8>(buffer, range, MappingFlags::empty());
+                if !mapping.is_empty() {
+                    ptr::copy_nonoverlapping(data.as_ptr(), mapping.as_mut_ptr(), data.len());
+                }
+                device.unmap_buffer(buffer);
+            }
+            Command::DeleteBuffers { ref buffers } => {
+                let buffers = self.map_all(buffers, Self::buffer)?;
+                device.delete_buffers(&buffers);
+            }
+            Command::CopyHostToBuffer {
+                buffer,
+                offset,
+                ref data,
+            } => device.copy_host_to_buffer(self.buffer(buffer)?, offset, data),
+            Command::InvalidateBuffer { buffer } => device.invalidate_buffer(self.buffer(buffer)?),
+            Command::InvalidateBufferRange { buffer, ref range } => {
+                device.invalidate_buffer_range(self.buffer(buffer)?, range.clone())
+            }
+            Command::BindUniformBuffers { first, ref ranges } => {
+                let ranges = self.map_all(ranges, Self::buffer_range)?;
+                device.bind_uniform_buffers(first, &ranges);
+            }
+            Command::BindStorageBuffers { first, ref ranges } => {
+                let ranges = self.map_all(ranges, Self::buffer_range)?;
+                device.bind_storage_buffers(first, &ranges);
+            }
+            Command::BindDrawIndirectBuffer { buffer } => {
+                device.bind_draw_indirect_buffer(self.buffer(buffer)?)
+            }
+            Command::UnbindDrawIndirectBuffer => device.unbind_draw_indirect_buffer(),
+            Command::BindDispatchIndirectBuffer { buffer } => {
+                device.bind_dispatch_indirect_buffer(self.buffer(buffer)?)
+            }
+            Command::UnbindDispatchIndirectBuffer => device.unbind_dispatch_indirect_buffer(),
+            Command::BindParameterBuffer { buffer } => {
+                device.bind_parameter_buffer(self.buffer(buffer)?)
+            }
+
+            Command::BindUniformConstants {
+                pipeline,
+                first,
+                ref constants,
+            } => device.bind_uniform_constants(self.pipeline(pipeline)?, first, constants),
+            Command::SetViewport {
+                first,
+                ref viewports,
+            } => device.set_viewport(first, viewports),
+            Command::SetScissor {
+                first,
+                ref scissors,
+            } => device.set_scissor(first, scissors),
+            Command::SetClipControl { origin, depth } => device.set_clip_control(origin, depth),
+            Command::SetDepthBias {
+                constant_factor,
+                slope_factor,
+            } => device.set_depth_bias(constant_factor, slope_factor),
+            Command::Draw {
+                primitive,
+                ref vertices,
+                ref instance,
+            } => device.draw(primitive, vertices.clone(), instance.clone()),
+            Command::DrawIndexed {
+                primitive,
+                index_ty,
+                ref indices,
+                ref instance,
+                base_vertex,
+            } => device.draw_indexed(
+                primitive,
+                index_ty,
+                indices.clone(),
+                instance.clone(),
+                base_vertex,
+            ),
+            Command::DrawIndirect {
+                primitive,
+                offset,
+                count,
+                stride,
+            } => device.draw_indirect(primitive, offset, count, stride),
+            Command::DrawIndirectFromHost {
+                primitive,
+                ref data,
+            } => device.draw_indirect_from_host(primitive, data),
+            Command::DrawIndexedIndirect {
+                primitive,
+                index_ty,
+                offset,
+                count,
+                stride,
+            } => device.draw_indexed_indirect(primitive, index_ty, offset, count, stride),
+            Command::DrawIndexedIndirectFromHost {
+                primitive,
+                index_ty,
+                ref data,
+            } => device.draw_indexed_indirect_from_host(primitive, index_ty, data),
+            Command::Dispatch { x, y, z } => device.dispatch(x, y, z),
+            Command::DispatchIndirect { offset } => device.dispatch_indirect(offset),
+            Command::Blit {
+                src,
+                src_region,
+                dst,
+                dst_region,
+                filter,
+            } => device.blit(
+                self.framebuffer(src)?,
+                src_region,
+                self.framebuffer(dst)?,
+                dst_region,
+                filter,
+            ),
+            Command::DrawMeshTasksNv {
+                task_count,
+                first_task,
+            } => device.draw_mesh_tasks_nv(task_count, first_task),
+            Command::DrawMeshTasksIndirectNv {
+                offset,
+                draw_count,
+                stride,
+            } => device.draw_mesh_tasks_indirect_nv(offset, draw_count, stride),
+            Command::DrawMeshTasksIndirectCountNv {
+                offset,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            } => device.draw_mesh_tasks_indirect_count_nv(
+                offset,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            ),
+
+            Command::CreateFramebuffer { framebuffer } => {
+                let new = device.create_framebuffer(None)?;
+                self.framebuffers.insert(framebuffer.0, new.0);
+            }
+            Command::DeleteFramebuffers { ref framebuffers } => {
+                let framebuffers = self.map_all(framebuffers, Self::framebuffer)?;
+                device.delete_framebuffers(&framebuffers);
+            }
+            Command::CreateRenderbuffer {
+                format,
+                width,
+                height,
+                samples,
+                renderbuffer,
+            } => {
+                let new = device.create_renderbuffer(format, width, height, samples, None)?;
+                self.renderbuffers.insert(renderbuffer.0, new.0);
+            }
+            Command::DeleteRenderbuffers { ref renderbuffers } => {
+                let renderbuffers = self.map_all(renderbuffers, Self::renderbuffer)?;
+                device.delete_renderbuffers(&renderbuffers);
+            }
+            Command::ClearAttachment { framebuffer, clear } => {
+                device.clear_attachment(self.framebuffer(framebuffer)?, clear)
+            }
+            Command::InvalidateAttachments {
+                framebuffer,
+                ref attachments,
+                region,
+            } => device.invalidate_attachments(self.framebuffer(framebuffer)?, attachments, region),
+            Command::BindFramebuffer { framebuffer } => {
+                device.bind_framebuffer(self.framebuffer(framebuffer)?)
+            }
+            Command::BindDrawFramebuffer { framebuffer } => {
+                device.bind_draw_framebuffer(self.framebuffer(framebuffer)?)
+            }
+            Command::BindReadFramebuffer { framebuffer } => {
+                device.bind_read_framebuffer(self.framebuffer(framebuffer)?)
+            }
+            Command::BindAttachments {
+                framebuffer,
+                ref attachments,
+            } => {
+                let attachments = self.map_all(attachments, |replayer, (attachment, view)| {
+                    Ok((attachment, replayer.attachment_view(view)?))
+                })?;
+                device.bind_attachments(self.framebuffer(framebuffer)?, &attachments);
+            }
+            Command::SetColorAttachments {
+                framebuffer,
+                ref attachments,
+            } => device.set_color_attachments(self.framebuffer(framebuffer)?, attachments),
+            Command::SetReadAttachment {
+                framebuffer,
+                attachment,
+            } => device.set_read_attachment(self.framebuffer(framebuffer)?, attachment),
+            Command::SetFramebufferSrgb { enable } => device.set_framebuffer_srgb(enable),
+
+            Command::CreateImage {
+                ty,
+                format,
+                levels,
+                image,
+            } => {
+                let new = device.create_image(ty, format, levels, None)?;
+                self.textures.insert(image.raw, new.raw);
+            }
+            Command::CreateTexelBuffer {
+                buffer,
+                format,
+                image,
+            } => {
+                let new = device.create_texel_buffer(self.buffer_range(buffer)?, format, None)?;
+                self.textures.insert(image.raw, new.raw);
+            }
+            Command::DeleteImages { ref images } => {
+                let images = self.map_all(images, Self::image)?;
+                device.delete_images(&images);
+            }
+            Command::CreateImageView {
+                image,
+                ty,
+                format,
+                ref range,
+                view,
+            } => {
+                let new = device.create_image_view(
+                    self.image(image)?,
+                    ty,
+                    format,
+                    range.clone(),
+                    None,
+                )?;
+                self.textures.insert(view.0, new.0);
+            }
+            Command::DeleteImageViews { ref views } => {
+                let views = self.map_all(views, Self::image_view)?;
+                device.delete_image_views(&views);
+            }
+            Command::BindImageViews { first, ref views } => {
+                let views = self.map_all(views, Self::image_view)?;
+                device.bind_image_views(first, &views);
+            }
+            Command::BindStorageImageViews { first, ref views } => {
+                let views = self.map_all(views, Self::image_view)?;
+                device.bind_storage_image_views(first, &views);
+            }
+            Command::GenerateMipmaps { image } => device.generate_mipmaps(self.image(image)?),
+
+            Command::CreateShader {
+                stage,
+                ref entrypoint,
+                ref source,
+                flags,
+                shader,
+            } => {
+                let ty = match entrypoint {
+                    Some(entrypoint) => ShaderSource::Spirv { entrypoint },
+                    None => ShaderSource::Glsl,
+                };
+                let new = device.create_shader(stage, ty, source, flags, None)?;
+                self.shaders.insert(shader.0, new.0);
+            }
+            Command::DeleteShaders { ref shaders } => {
+                let shaders = self.map_all(shaders, Self::shader)?;
+                device.delete_shaders(&shaders);
+            }
+            Command::CreatePipeline {
+                ref shaders,
+                flags,
+                pipeline,
+            } => {
+                let shaders = self.map_all(shaders, Self::shader)?;
+                let new = device.create_pipeline(&shaders, flags, None)?;
+                self.pipelines.insert(pipeline.0, new.0);
+            }
+            Command::DeletePipelines { ref pipelines } => {
+                let pipelines = self.map_all(pipelines, Self::pipeline)?;
+                device.delete_pipelines(&pipelines);
+            }
+            Command::BindInputAssemblyState { state } => device.bind_input_assembly_state(state),
+            Command::BindColorBlendState { ref state } => device.bind_color_blend_state(state),
+            Command::BindDepthStencilState { ref state } => device.bind_depth_stencil_state(state),
+            Command::BindRasterizationState { ref state } => device.bind_rasterization_state(state),
+            Command::BindMultisampleState { ref state } => {
+                device.bind_multisample_state(state.as_ref())
+            }
+            Command::BindPipeline { pipeline } => device.bind_pipeline(self.pipeline(pipeline)?),
+
+            Command::CreateQuery { ty, query } => {
+                let new = device.create_query(ty, None);
+                self.queries.insert(query.raw, new.raw);
+            }
+            Command::DeleteQueries { ref queries } => {
+                let queries = self.map_all(queries, Self::query)?;
+                device.delete_queries(&queries);
+            }
+            Command::BeginQuery { query } => device.begin_query(self.query(query)?),
+            Command::EndQuery { query } => device.end_query(self.query(query)?),
+            Command::WriteTimestamp { query } => device.write_timestamp(self.query(query)?),
+            Command::BeginConditionalRendering { query, mode } => {
+                device.begin_conditional_rendering(self.query(query)?, mode)
+            }
+            Command::EndConditionalRendering => device.end_conditional_rendering(),
+
+            Command::CreateSampler { ref desc, sampler } => {
+                let new = device.create_sampler(desc.clone(), None)?;
+                self.samplers.insert(sampler.0, new.0);
+            }
+            Command::BindSamplers {
+                first,
+                ref samplers,
+            } => {
+                let samplers = self.map_all(samplers, Self::sampler)?;
+                device.bind_samplers(first, &samplers);
+            }
+            Command::DeleteSamplers { ref samplers } => {
+                let samplers = self.map_all(samplers, Self::sampler)?;
+                device.delete_samplers(&samplers);
+            }
+
+            Command::CreateVertexArray {
+                ref attributes,
+                vertex_array,
+            } => {
+                let new = device.create_vertex_array(attributes, None)?;
+                self.vertex_arrays.insert(vertex_array.0, new.0);
+            }
+            Command::DeleteVertexArrays { ref vertex_arrays } => {
+                let vertex_arrays = self.map_all(vertex_arrays, Self::vertex_array)?;
+                device.delete_vertex_arrays(&vertex_arrays);
+            }
+            Command::BindVertexArray { vertex_array } => {
+                device.bind_vertex_array(self.vertex_array(vertex_array)?)
+            }
+            Command::BindVertexBuffers {
+                vertex_array,
+                first,
+                ref views,
+            } => {
+                let views = views
+                    .iter()
+                    .map(|view| {
+                        Ok(VertexBufferView {
+                            buffer: self.buffer(view.buffer)?,
+                            ..view.clone()
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                device.bind_vertex_buffers(self.vertex_array(vertex_array)?, first, &views);
+            }
+            Command::BindIndexBuffer {
+                vertex_array,
+                buffer,
+            } => device.bind_index_buffer(self.vertex_array(vertex_array)?, self.buffer(buffer)?),
+
+            Command::MemoryBarrier { flags } => device.memory_barrier(flags),
+            Command::MemoryBarrierByRegion { flags } => device.memory_barrier_by_region(flags),
+            Command::CreateFence { fence } => {
+                let new = device.create_fence();
+                self.fences.insert(fence, new);
+            }
+            Command::DeleteFence { fence } => {
+                device.delete_fence(self.fence(fence)?);
+                self.fences.remove(&fence);
+            }
+            Command::ServerWaitFence { fence } => device.server_wait_fence(self.fence(fence)?),
+
+            Command::CopyHostToImage {
+                ref data,
+                image,
+                ref region,
+            } => device.copy_host_to_image(data, self.image(image)?, region.clone()),
+            Command::CopyBufferToImage {
+                buffer,
+                image,
+                ref region,
+            } => device.copy_buffer_to_image(
+                self.buffer(buffer)?,
+                self.image(image)?,
+                region.clone(),
+            ),
+            Command::CopyImageToHost {
+                image,
+                size,
+                ref region,
+            } => {
+                let mut data = vec![0u8; size];
+                device.copy_image_to_host(self.image(image)?, &mut data, region.clone());
+            }
+            Command::CopyImageToBuffer {
+                image,
+                buffer,
+                ref region,
+            } => device.copy_image_to_buffer(
+                self.image(image)?,
+                self.buffer(buffer)?,
+                region.clone(),
+            ),
+            Command::CopyAttachmentToHost {
+                region,
+                layout,
+                size,
+            } => {
+                let mut data = vec![0u8; size];
+                device.copy_attachment_to_host(region, layout, &mut data);
+            }
+            Command::CopyAttachmentToBuffer {
+                region,
+                layout,
+                buffer,
+            } => device.copy_attachment_to_buffer(region, layout, self.buffer_range(buffer)?),
+            Command::CopyImage {
+                src,
+                dst,
+                ref region,
+            } => device.copy_image(self.image(src)?, self.image(dst)?, region.clone()),
+            Command::CopyBuffer {
+                src,
+                src_offset,
+                dst,
+                dst_offset,
+                size,
+            } => device.copy_buffer(
+                self.buffer(src)?,
+                src_offset,
+                self.buffer(dst)?,
+                dst_offset,
+                size,
+            ),
+            Command::FillBuffer { buffer, value } => {
+                device.fill_buffer(self.buffer_range(buffer)?, value)
+            }
+            Command::RestoreState { ref snapshot } => device.restore_state(
+                &snapshot.map_names(|ty, object| self.object(ty, object).unwrap_or(0)),
+            ),
+
+            Command::ObjectName {
+                ty,
+                object,
+                ref name,
+            } => {
+                let object = self.object(ty, object)?;
+                device
+                    .0
+                    .ObjectLabel(ty as _, object, name.len() as _, name.as_ptr() as *const _);
+            }
+            Command::BeginDebugMarker { src, id, ref msg } => {
+                device.begin_debug_marker(src, id, msg)
+            }
+            Command::EndDebugMarker => device.end_debug_marker(),
+            Command::InsertDebugMessage {
+                src,
+                ty,
+                id,
+                severity,
+                ref msg,
+            } => device.insert_debug_message(src, ty, id, severity, msg),
+        }
+
+        Ok(())
+    }
+
+    fn map_all<T: Copy, F>(&self, objects: &[T], f: F) -> Result<Vec<T>>
+    where
+        F: Fn(&Self, T) -> Result<T>,
+    {
+        objects.iter().map(|&object| f(self, object)).collect()
+    }
+
+    fn object(&self, ty: ObjectType, object: GLuint) -> Result<GLuint> {
+        match ty {
+            ObjectType::Buffer => lookup(&self.buffers, "buffer", object),
+            ObjectType::Shader => lookup(&self.shaders, "shader", object),
+            ObjectType::Image => lookup(&self.textures, "image", object),
+            ObjectType::VertexArray => lookup(&self.vertex_arrays, "vertex array", object),
+            ObjectType::Pipeline => lookup(&self.pipelines, "pipeline", object),
+            ObjectType::Framebuffer => lookup(&self.framebuffers, "framebuffer", object),
+            ObjectType::Renderbuffer => lookup(&self.renderbuffers, "renderbuffer", object),
+            ObjectType::Sampler => lookup(&self.samplers, "sampler", object),
+            ObjectType::Query => lookup(&self.queries, "query", object),
+        }
+    }
+
+    fn buffer(&self, buffer: Buffer) -> Result<Buffer> {
+        Ok(Buffer(
+            lookup(&self.buffers, "buffer", buffer.0)?,
+            buffer.1,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn buffer_range(&self, range: BufferRange) -> Result<BufferRange> {
+        Ok(BufferRange {
+            buffer: self.buffer(range.buffer)?,
+            ..range
+        })
+    }
+
+    fn image(&self, image: Image) -> Result<Image> {
+        Ok(Image {
+            raw: lookup(&self.textures, "image", image.raw)?,
+            target: image.target,
+            generation: Generation::UNTRACKED,
+        })
+    }
+
+    fn image_view(&self, view: ImageView) -> Result<ImageView> {
+        Ok(ImageView(
+            lookup(&self.textures, "image view", view.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn sampler(&self, sampler: Sampler) -> Result<Sampler> {
+        Ok(Sampler(
+            lookup(&self.samplers, "sampler", sampler.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn framebuffer(&self, framebuffer: Framebuffer) -> Result<Framebuffer> {
+        Ok(Framebuffer(
+            lookup(&self.framebuffers, "framebuffer", framebuffer.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn renderbuffer(&self, renderbuffer: Renderbuffer) -> Result<Renderbuffer> {
+        Ok(Renderbuffer(
+            lookup(&self.renderbuffers, "renderbuffer", renderbuffer.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn attachment_view(&self, view: AttachmentView) -> Result<AttachmentView> {
+        Ok(match view {
+            AttachmentView::Image(view) => AttachmentView::Image(self.image_view(view)?),
+            AttachmentView::Renderbuffer(renderbuffer) => {
+                AttachmentView::Renderbuffer(self.renderbuffer(renderbuffer)?)
+            }
+        })
+    }
+
+    fn vertex_array(&self, vertex_array: VertexArray) -> Result<VertexArray> {
+        Ok(VertexArray(
+            lookup(&self.vertex_arrays, "vertex array", vertex_array.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn shader(&self, shader: Shader) -> Result<Shader> {
+        Ok(Shader(
+            lookup(&self.shaders, "shader", shader.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn pipeline(&self, pipeline: Pipeline) -> Result<Pipeline> {
+        Ok(Pipeline(
+            lookup(&self.pipelines, "pipeline", pipeline.0)?,
+            Generation::UNTRACKED,
+        ))
+    }
+
+    fn fence(&self, fence: u64) -> Result<Fence> {
+        self.fences
+            .get(&fence)
+            .copied()
+            .ok_or_else(|| Error::Capture(format!("unknown fence handle 0x{:x}", fence)))
+    }
+
+    fn query(&self, query: Query) -> Result<Query> {
+        Ok(Query {
+            raw: lookup(&self.queries, "query", query.raw)?,
+            ty: query.ty,
+            generation: Generation::UNTRACKED,
+        })
+    }
+}
+
+/// Map a captured GL name to the replayed object, the null object maps to itself.
+fn lookup(names: &HashMap<GLuint, GLuint>, kind: &str, name: GLuint) -> Result<GLuint> {
+    if name == 0 {
+        return Ok(0);
+    }
+    names
+        .get(&name)
+        .copied()
+        .ok_or_else(|| Error::Capture(format!("unknown {} handle {}", kind, name)))
+}
+
+fn capture_error<E: fmt::Display>(err: E) -> Error {
+    Error::Capture(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::tests::{calls, device};
+    use crate::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Capture target readable after ending the capture.
+    #[derive(Clone, Default)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedWriter {
+        fn data(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn commands(&self) -> Vec<Command> {
+            let data = self.data();
+            CaptureReader::new(&data[..])
+                .unwrap()
+                .collect::<Result<_>>()
+                .unwrap()
+        }
+    }
+
+    unsafe fn replay(capture: &SharedWriter) -> RecordingBackend {
+        let (grr, recording) = device();
+        let data = capture.data();
+        Replayer::new()
+            .replay(&grr, CaptureReader::new(&data[..]).unwrap())
+            .unwrap();
+        recording
+    }
+
+    #[test]
+    fn replay_round_trip() {
+        let (grr, recording) = device();
+        let capture = SharedWriter::default();
+        grr.begin_capture(capture.clone()).unwrap();
+        unsafe {
+            let shader = |stage| {
+                grr.create_shader(
+                    stage,
+                    ShaderSource::Glsl,
+                    b"void main() {}",
+                    ShaderFlags::empty(),
+                    None,
+                )
+                .unwrap()
+            };
+            let vs = shader(ShaderStage::Vertex);
+            let fs = shader(ShaderStage::Fragment);
+            let pipeline = grr
+                .create_graphics_pipeline(
+                    VertexPipelineDesc {
+                        vertex_shader: vs,
+                        tessellation_control_shader: None,
+                        tessellation_evaluation_shader: None,
+                        geometry_shader: None,
+                        fragment_shader: Some(fs),
+                    },
+                    PipelineFlags::empty(),
+                    None,
+                )
+                .unwrap();
+            let buffer = grr
+                .create_buffer_from_host(&[0; 36], MemoryFlags::empty(), None)
+                .unwrap();
+            let vertex_array = grr
+                .create_vertex_array(
+                    &[VertexAttributeDesc {
+                        location: 0,
+                        binding: 0,
+                        format: VertexFormat::Xyz32Float,
+                        offset: 0,
+                    }],
+                    None,
+                )
+                .unwrap();
+            grr.bind_vertex_buffers(
+                vertex_array,
+                0,
+                &[VertexBufferView {
+                    buffer,
+                    offset: 0,
+                    stride: 12,
+                    input_rate: InputRate::Vertex,
+                }],
+            );
+
+            grr.bind_pipeline(pipeline);
+            grr.bind_vertex_array(vertex_array);
+            grr.set_viewport(
+                0,
+                &[Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    w: 64.0,
+                    h: 64.0,
+                    n: 0.0,
+                    f: 1.0,
+                }],
+            );
+            grr.set_scissor(
+                0,
+                &[Region {
+                    x: 0,
+                    y: 0,
+                    w: 64,
+                    h: 64,
+                }],
+            );
+            grr.draw(Primitive::Triangles, 0..3, 0..1);
+
+            grr.delete_vertex_array(vertex_array);
+            grr.delete_buffer(buffer);
+            grr.delete_pipeline(pipeline);
+            grr.delete_shaders(&[vs, fs]);
+        }
+        grr.end_capture().unwrap();
+
+        let captured = calls(&recording);
+        assert!(captured
+            .iter()
+            .any(|&(name, _)| name == "DrawArraysInstancedBaseInstance"));
+        let replayed = unsafe { replay(&capture) };
+        assert_eq!(calls(&replayed), captured);
+    }
+
+    #[test]
+    fn restore_state() {
+        let (grr, recording) = device();
+        let capture = SharedWriter::default();
+        grr.begin_capture(capture.clone()).unwrap();
+        unsafe {
+            let snapshot = grr.save_state();
+            recording.clear();
+            grr.restore_state(&snapshot);
+        }
+        grr.end_capture().unwrap();
+
+        let restored = calls(&recording);
+        assert!(!restored.is_empty());
+        let replayed = unsafe { replay(&capture) };
+        assert_eq!(calls(&replayed), restored);
+    }
+
+    #[test]
+    fn persistent_mapping_writes() {
+        let (grr, _recording) = device();
+        let capture = SharedWriter::default();
+        grr.begin_capture(capture.clone()).unwrap();
+        unsafe {
+            let memory = MemoryFlags::CPU_MAP_WRITE | MemoryFlags::COHERENT;
+            let buffer = grr.create_buffer(16, memory, None).unwrap();
+            let dst = grr.create_buffer(16, MemoryFlags::empty(), None).unwrap();
+            let data = grr.map_buffer::<u32>(buffer, 0..16, MappingFlags::empty());
+
+            data[1] = 7;
+            grr.copy_buffer(buffer, 0, dst, 0, 16);
+            // Unchanged contents aren't recorded again.
+            grr.copy_buffer(buffer, 0, dst, 0, 16);
+            data[0] = 1;
+            data[3] = 9;
+            grr.copy_buffer(buffer, 0, dst, 0, 16);
+            grr.unmap_buffer(buffer);
+        }
+        grr.end_capture().unwrap();
+
+        let writes = capture
+            .commands()
+            .into_iter()
+            .filter_map(|command| match command {
+                Command::WriteMappedBuffer { offset, data, .. } => Some((offset, data)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // Only the changed byte span is written.
+        assert_eq!(
+            writes,
+            [(4, vec![7]), (0, as_bytes(&[1u32, 7, 0, 9])[..13].to_vec())]
+        );
+
+        unsafe {
+            let replayed = replay(&capture);
+            let contents = replayed.MapNamedBufferRange(1, 0, 16, __gl::MAP_READ_BIT);
+            assert_eq!(
+                std::slice::from_raw_parts(contents as *const u32, 4),
+                [1, 7, 0, 9]
+            );
+        }
+    }
+}
@@ -1,6 +1,9 @@
 //! Drawing and Dispatching related commands.
 
 use crate::__gl;
+use crate::cache::StateKey;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 use crate::{Device, Filter, Framebuffer, Pipeline, Region};
 use std::{mem, ops::Range};
 
@@ -10,6 +13,7 @@ use std::{mem, ops::Range};
 /// assemble primitives based on the incoming vertex data.
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Primitive {
     /// Independent vertex points.
     ///
@@ -74,6 +78,7 @@ pub enum Primitive {
 /// Specifies the size of indices during indexed draw calls.
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IndexTy {
     /// 8-bit unsigned integer.
     U8 = __gl::UNSIGNED_BYTE,
@@ -103,6 +108,8 @@ impl IndexTy {
 /// During the geometry a primitive will be assigned a viewport index (GLSL: `gl_ViewportIndex`)
 /// either automatically or manually. This index controls which viewport from the bounded
 /// viewports will be selected for applying the transformation for the current **primitive**.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Viewport {
     /// Offset (x).
     pub x: f32,
@@ -118,6 +125,28 @@ pub struct Viewport {
     pub f: f64,
 }
 
+/// Origin of the window coordinate system.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipOrigin {
+    /// Framebuffer coordinate (0, 0) is the lower-left corner (GL default).
+    LowerLeft = __gl::LOWER_LEFT,
+    /// Framebuffer coordinate (0, 0) is the upper-left corner.
+    UpperLeft = __gl::UPPER_LEFT,
+}
+
+/// Depth range of the clip volume.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipDepth {
+    /// Clip space depth in [-1; 1] (GL default).
+    NegativeOneToOne = __gl::NEGATIVE_ONE_TO_ONE,
+    /// Clip space depth in [0; 1].
+    ZeroToOne = __gl::ZERO_TO_ONE,
+}
+
 /// Uniform constant.
 ///
 /// Small values which can be written directly by the API.
@@ -126,6 +155,8 @@ pub struct Viewport {
 /// ## Example
 ///
 /// GLSL: `layout (location = 0) uniform mat4 u_perspective;`
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constant {
     /// 32-bit single precision floating point.
     F32(f32),
@@ -174,6 +205,7 @@ pub enum Constant {
 /// Indirect draw command structure.
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DrawIndirectCmd {
     ///
     pub vertex_count: u32,
@@ -188,6 +220,7 @@ pub struct DrawIndirectCmd {
 /// Indirect (indexed) draw command structure.
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DrawIndexedIndirectCmd {
     ///
     pub index_count: u32,
@@ -204,6 +237,7 @@ pub struct DrawIndexedIndirectCmd {
 /// Indirect dispatch command structure.
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DispatchIndirectCmd {
     /// Number of local workgroups in x dimension.
     pub x: u32,
@@ -215,12 +249,21 @@ pub struct DispatchIndirectCmd {
 
 impl Device {
     /// Set uniform constants for a pipeline.
+    #[track_caller]
     pub unsafe fn bind_uniform_constants(
         &self,
         pipeline: Pipeline,
         first: u32,
         constants: &[Constant],
     ) {
+        let _scope = self.api_scope("bind_uniform_constants");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindUniformConstants {
+            pipeline,
+            first,
+            constants: constants.to_vec(),
+        });
+        self.check_handle("bind_uniform_constants", pipeline);
         for (i, constant) in constants.iter().enumerate() {
             let location = first as i32 + i as i32;
             match constant {
@@ -326,7 +369,16 @@ impl Device {
     ///
     /// See [Viewport](../command/struct.Viewport.html) for more information
     /// about the viewport transformation.
+    #[track_caller]
     pub unsafe fn set_viewport(&self, first: u32, viewports: &[Viewport]) {
+        let _scope = self.api_scope("set_viewport");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetViewport {
+            first,
+            viewports: viewports.to_vec(),
+        });
+        #[cfg(feature = "validation")]
+        self.validator().set_viewport(first, viewports.len());
         let rects = viewports
             .iter()
             .flat_map(|viewport| vec![viewport.x, viewport.y, viewport.w, viewport.h])
@@ -344,12 +396,35 @@ impl Device {
             .DepthRangeArrayv(first, viewports.len() as _, depth_ranges.as_ptr());
     }
 
+    /// Set the clip control parameters.
+    ///
+    /// Controls the window coordinate origin and the clip space depth range
+    /// used by the viewport transformation.
+    #[track_caller]
+    pub unsafe fn set_clip_control(&self, origin: ClipOrigin, depth: ClipDepth) {
+        let _scope = self.api_scope("set_clip_control");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetClipControl { origin, depth });
+        if self.state_changed(StateKey::ClipControl, &[origin as _, depth as _]) {
+            self.0.ClipControl(origin as _, depth as _);
+        }
+    }
+
     /// Set scissor rectangles for viewports.
     ///
     /// # Valid usage
     ///
     /// - Every active viewport needs an associated scissor.
+    #[track_caller]
     pub unsafe fn set_scissor(&self, first: u32, scissors: &[Region]) {
+        let _scope = self.api_scope("set_scissor");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetScissor {
+            first,
+            scissors: scissors.to_vec(),
+        });
+        #[cfg(feature = "validation")]
+        self.validator().set_scissor(first, scissors.len());
         let scissors_raw = scissors
             .iter()
             .flat_map(|scissor| vec![scissor.x, scissor.y, scissor.w, scissor.h])
@@ -360,8 +435,20 @@ impl Device {
     }
 
     /// Set depth bias factors.
+    #[track_caller]
     pub unsafe fn set_depth_bias(&self, constant_factor: f32, slope_factor: f32) {
-        self.0.PolygonOffset(slope_factor, constant_factor);
+        let _scope = self.api_scope("set_depth_bias");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetDepthBias {
+            constant_factor,
+            slope_factor,
+        });
+        if self.state_changed(
+            StateKey::PolygonOffset,
+            &[slope_factor.to_bits(), constant_factor.to_bits()],
+        ) {
+            self.0.PolygonOffset(slope_factor, constant_factor);
+        }
     }
 
     /// Submit a (non-indexed) draw call.
@@ -378,7 +465,17 @@ impl Device {
     /// - `vertices.end` must be larger than `vertices.start`.
     /// - `vertices.end - vertices.start` must be allow assembling complete primitives.
     /// - `instances.end` must be larger than `instances.start`.
+    #[track_caller]
     pub unsafe fn draw(&self, primitive: Primitive, vertices: Range<u32>, instance: Range<u32>) {
+        let _scope = self.api_scope("draw");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::Draw {
+            primitive,
+            vertices: vertices.clone(),
+            instance: instance.clone(),
+        });
+        #[cfg(feature = "validation")]
+        self.validator().draw(&vertices, &instance);
         self.0.DrawArraysInstancedBaseInstance(
             primitive as _,
             vertices.start as _,
@@ -402,6 +499,7 @@ impl Device {
     /// - `indices.end` must be larger than `indices.start`.
     /// - `indices.end - indices.start` must allow to assemble complete primitives.
     /// - `instances.end` must be larger than `instances.start`.
+    #[track_caller]
     pub unsafe fn draw_indexed(
         &self,
         primitive: Primitive,
@@ -410,6 +508,17 @@ impl Device {
         instance: Range<u32>,
         base_vertex: i32,
     ) {
+        let _scope = self.api_scope("draw_indexed");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawIndexed {
+            primitive,
+            index_ty,
+            indices: indices.clone(),
+            instance: instance.clone(),
+            base_vertex,
+        });
+        #[cfg(feature = "validation")]
+        self.validator().draw_indexed(&indices, &instance);
         self.0.DrawElementsInstancedBaseVertexBaseInstance(
             primitive as _,
             (indices.end - indices.start) as _,
@@ -427,13 +536,35 @@ impl Device {
     ///
     /// - There must be a valid graphics pipeline currently bound.
     /// - There must be a valid draw indirect buffer currently bound.
+    #[track_caller]
     pub unsafe fn draw_indirect(&self, primitive: Primitive, offset: u64, count: u32, stride: u32) {
+        let _scope = self.api_scope("draw_indirect");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawIndirect {
+            primitive,
+            offset,
+            count,
+            stride,
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_indirect("draw_indirect", false, false);
         self.0
             .MultiDrawArraysIndirect(primitive as _, offset as _, count as _, stride as _);
     }
 
     /// Submit an indirect draw call.
+    #[track_caller]
     pub unsafe fn draw_indirect_from_host(&self, primitive: Primitive, data: &[DrawIndirectCmd]) {
+        let _scope = self.api_scope("draw_indirect_from_host");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawIndirectFromHost {
+            primitive,
+            data: data.to_vec(),
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_indirect("draw_indirect_from_host", false, true);
         self.0.MultiDrawArraysIndirect(
             primitive as _,
             data.as_ptr() as *const _,
@@ -443,6 +574,7 @@ impl Device {
     }
 
     /// Indirect draw call.
+    #[track_caller]
     pub unsafe fn draw_indexed_indirect(
         &self,
         primitive: Primitive,
@@ -451,6 +583,18 @@ impl Device {
         count: u32,
         stride: u32,
     ) {
+        let _scope = self.api_scope("draw_indexed_indirect");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawIndexedIndirect {
+            primitive,
+            index_ty,
+            offset,
+            count,
+            stride,
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_indirect("draw_indexed_indirect", true, false);
         self.0.MultiDrawElementsIndirect(
             primitive as _,
             index_ty as _,
@@ -461,12 +605,23 @@ impl Device {
     }
 
     /// Indirect (indexed) draw call.
+    #[track_caller]
     pub unsafe fn draw_indexed_indirect_from_host(
         &self,
         primitive: Primitive,
         index_ty: IndexTy,
         data: &[DrawIndexedIndirectCmd],
     ) {
+        let _scope = self.api_scope("draw_indexed_indirect_from_host");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawIndexedIndirectFromHost {
+            primitive,
+            index_ty,
+            data: data.to_vec(),
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_indirect("draw_indexed_indirect_from_host", true, true);
         self.0.MultiDrawElementsIndirect(
             primitive as _,
             index_ty as _,
@@ -481,16 +636,29 @@ impl Device {
     /// # Valid usage
     ///
     /// - There must be a valid compute shader currently bound.
+    #[track_caller]
     pub unsafe fn dispatch(&self, x: u32, y: u32, z: u32) {
+        let _scope = self.api_scope("dispatch");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::Dispatch { x, y, z });
+        #[cfg(feature = "validation")]
+        self.validator().dispatch("dispatch", false);
         self.0.DispatchCompute(x, y, z);
     }
 
     ///
+    #[track_caller]
     pub unsafe fn dispatch_indirect(&self, offset: u64) {
+        let _scope = self.api_scope("dispatch_indirect");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DispatchIndirect { offset });
+        #[cfg(feature = "validation")]
+        self.validator().dispatch("dispatch_indirect", true);
         self.0.DispatchComputeIndirect(offset as _);
     }
 
     ///
+    #[track_caller]
     pub unsafe fn blit(
         &self,
         src: Framebuffer,
@@ -499,6 +667,17 @@ impl Device {
         dst_region: Region,
         filter: Filter,
     ) {
+        let _scope = self.api_scope("blit");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::Blit {
+            src,
+            src_region,
+            dst,
+            dst_region,
+            filter,
+        });
+        self.check_handle("blit", src);
+        self.check_handle("blit", dst);
         self.0.BlitNamedFramebuffer(
             src.0,
             dst.0,
@@ -515,18 +694,42 @@ impl Device {
         );
     }
 
+    /// Submit a mesh task draw call.
     ///
+    /// Requires `DeviceFeatures::mesh_shader_nv`.
+    #[track_caller]
     pub unsafe fn draw_mesh_tasks_nv(&self, task_count: u32, first_task: u32) {
+        let _scope = self.api_scope("draw_mesh_tasks_nv");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawMeshTasksNv {
+            task_count,
+            first_task,
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_mesh_tasks("draw_mesh_tasks_nv", false);
         self.0.DrawMeshTasksNV(first_task, task_count);
     }
 
     ///
+    #[track_caller]
     pub unsafe fn draw_mesh_tasks_indirect_nv(&self, offset: u64, draw_count: u32, stride: u32) {
+        let _scope = self.api_scope("draw_mesh_tasks_indirect_nv");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawMeshTasksIndirectNv {
+            offset,
+            draw_count,
+            stride,
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_mesh_tasks("draw_mesh_tasks_indirect_nv", true);
         self.0
             .MultiDrawMeshTasksIndirectNV(offset as _, draw_count as _, stride as _);
     }
 
     ///
+    #[track_caller]
     pub unsafe fn draw_mesh_tasks_indirect_count_nv(
         &self,
         offset: u64,
@@ -534,6 +737,17 @@ impl Device {
         max_draw_count: u32,
         stride: u32,
     ) {
+        let _scope = self.api_scope("draw_mesh_tasks_indirect_count_nv");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DrawMeshTasksIndirectCountNv {
+            offset,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .draw_mesh_tasks("draw_mesh_tasks_indirect_count_nv", true);
         self.0.MultiDrawMeshTasksIndirectCountNV(
             offset as _,
             count_buffer_offset as _,
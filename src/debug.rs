@@ -1,7 +1,22 @@
 use crate::__gl;
-use crate::__gl::types::{GLenum, GLuint};
+use crate::__gl::types::{GLchar, GLenum, GLsizei, GLsync, GLuint};
+use crate::backend::Backend;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 use crate::device::Device;
 
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CStr;
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use std::{ptr, slice};
+
 /// Message filter.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MsgFilter<T> {
@@ -33,6 +48,7 @@ bitflags!(
     /// Debug report flags.
     ///
     /// Denotes which events will trigger a debug report.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct DebugReport: GLenum {
         const NOTIFICATION = __gl::DEBUG_SEVERITY_NOTIFICATION;
         const WARNING = __gl::DEBUG_SEVERITY_MEDIUM;
@@ -44,7 +60,8 @@ bitflags!(
 
 /// Debug message source.
 #[repr(u32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DebugSource {
     Api = __gl::DEBUG_SOURCE_API,
     ShaderCompiler = __gl::DEBUG_SOURCE_SHADER_COMPILER,
@@ -56,7 +73,8 @@ pub enum DebugSource {
 
 /// Debug message type.
 #[repr(u32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DebugType {
     Error = __gl::DEBUG_TYPE_ERROR,
     Deprecated = __gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR,
@@ -69,12 +87,313 @@ pub enum DebugType {
     Other = __gl::DEBUG_TYPE_OTHER,
 }
 
+impl DebugSource {
+    fn from_raw(source: GLenum) -> Self {
+        match source {
+            __gl::DEBUG_SOURCE_API => DebugSource::Api,
+            __gl::DEBUG_SOURCE_SHADER_COMPILER => DebugSource::ShaderCompiler,
+            __gl::DEBUG_SOURCE_WINDOW_SYSTEM => DebugSource::Wsi,
+            __gl::DEBUG_SOURCE_THIRD_PARTY => DebugSource::ThirdParty,
+            __gl::DEBUG_SOURCE_APPLICATION => DebugSource::Application,
+            _ => DebugSource::Other,
+        }
+    }
+}
+
+impl DebugType {
+    fn from_raw(ty: GLenum) -> Self {
+        match ty {
+            __gl::DEBUG_TYPE_ERROR => DebugType::Error,
+            __gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => DebugType::Deprecated,
+            __gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => DebugType::UndefinedBehavior,
+            __gl::DEBUG_TYPE_PERFORMANCE => DebugType::Performance,
+            __gl::DEBUG_TYPE_PORTABILITY => DebugType::Portability,
+            __gl::DEBUG_TYPE_MARKER => DebugType::Marker,
+            __gl::DEBUG_TYPE_PUSH_GROUP => DebugType::PushGroup,
+            __gl::DEBUG_TYPE_POP_GROUP => DebugType::PopGroup,
+            _ => DebugType::Other,
+        }
+    }
+}
+
+/// Debug message reported by the context or the validation layer.
+#[derive(Debug, Copy, Clone)]
+pub struct DebugMessage<'a> {
+    /// Severity of the message, a single flag of `DebugReport`.
+    pub severity: DebugReport,
+    pub source: DebugSource,
+    pub ty: DebugType,
+    /// Implementation specific message id.
+    pub id: u32,
+    /// Message text, invalid UTF-8 sequences are replaced.
+    pub message: &'a str,
+}
+
+/// Debug message callback.
+///
+/// The callback may be called from any thread if the driver reports messages asynchronously.
+pub type DebugCallback = Box<dyn Fn(DebugMessage) + Send + Sync>;
+
+/// Filtering and logging of debug messages.
+///
+/// Messages are identified by their source, type and id. Messages without id (e.g. messages
+/// of the validation layer) are additionally identified by their text. Suppressed messages
+/// are not forwarded to the debug callback, but are still recorded in the debug log.
+///
+/// ```
+/// let desc = grr::DeviceDesc::default().debug_filter(grr::DebugFilter {
+///     max_repeats: Some(4),
+///     rate_limit: Some(100),
+///     log_capacity: 256,
+/// });
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DebugFilter {
+    /// Maximum number of forwarded messages per message id (or text, for messages without id).
+    pub max_repeats: Option<u32>,
+    /// Maximum number of forwarded messages per second.
+    pub rate_limit: Option<u32>,
+    /// Number of recent messages kept in the debug log (`Device::debug_log`).
+    pub log_capacity: usize,
+}
+
+/// Debug message recorded in the debug log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugLogEntry {
+    pub severity: DebugReport,
+    pub source: DebugSource,
+    pub ty: DebugType,
+    pub id: u32,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct FilterState {
+    /// Number of received messages per message key (see `message_key`).
+    repeats: HashMap<(DebugSource, DebugType, u32, u64), u32>,
+    /// Start of the current rate limiting interval.
+    interval: Option<Instant>,
+    /// Number of forwarded messages in the current interval.
+    forwarded: u32,
+    suppressed: u64,
+    log: VecDeque<DebugLogEntry>,
+}
+
+/// Debug callback registered with the context.
+pub(crate) struct DebugHandler {
+    callback: Option<DebugCallback>,
+    filter: DebugFilter,
+    state: Mutex<FilterState>,
+    /// Panic of the callback caught at the FFI boundary.
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+    panicked: AtomicBool,
+}
+
+impl DebugHandler {
+    pub(crate) fn new(callback: Option<DebugCallback>, filter: DebugFilter) -> Self {
+        DebugHandler {
+            callback,
+            filter,
+            state: Mutex::default(),
+            panic: Mutex::new(None),
+            panicked: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn has_callback(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    pub(crate) fn report(&self, message: DebugMessage) {
+        if !self.filter_message(&message) {
+            return;
+        }
+        if let Some(ref callback) = self.callback {
+            callback(message);
+        }
+    }
+
+    fn report_raw(&self, source: GLenum, ty: GLenum, id: GLuint, severity: GLenum, message: &[u8]) {
+        self.report(DebugMessage {
+            severity: DebugReport::from_bits_truncate(severity),
+            source: DebugSource::from_raw(source),
+            ty: DebugType::from_raw(ty),
+            id,
+            message: &String::from_utf8_lossy(message),
+        });
+    }
+
+    /// Record the message in the log, returns if the message should be forwarded.
+    fn filter_message(&self, message: &DebugMessage) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = &mut *state;
+
+        if self.filter.log_capacity > 0 {
+            if state.log.len() >= self.filter.log_capacity {
+                state.log.pop_front();
+            }
+            state.log.push_back(DebugLogEntry {
+                severity: message.severity,
+                source: message.source,
+                ty: message.ty,
+                id: message.id,
+                message: message.message.to_string(),
+            });
+        }
+
+        if let Some(max_repeats) = self.filter.max_repeats {
+            let repeats = state.repeats.entry(message_key(message)).or_insert(0);
+            *repeats = repeats.saturating_add(1);
+            if *repeats > max_repeats {
+                state.suppressed += 1;
+                return false;
+            }
+        }
+
+        if let Some(rate_limit) = self.filter.rate_limit {
+            let now = Instant::now();
+            match state.interval {
+                Some(start) if now.duration_since(start) < Duration::from_secs(1) => {}
+                _ => {
+                    state.interval = Some(now);
+                    state.forwarded = 0;
+                }
+            }
+            if state.forwarded >= rate_limit {
+                state.suppressed += 1;
+                return false;
+            }
+            state.forwarded += 1;
+        }
+
+        true
+    }
+}
+
+/// Key identifying repeated messages.
 ///
-pub type DebugCallback = fn(DebugReport, DebugSource, DebugType, u32, &str);
+/// Messages with id `0` are not distinguishable by their id and are keyed by a hash of their text.
+fn message_key(message: &DebugMessage) -> (DebugSource, DebugType, u32, u64) {
+    let text = if message.id == 0 {
+        let mut hasher = DefaultHasher::new();
+        message.message.hash(&mut hasher);
+        hasher.finish()
+    } else {
+        0
+    };
+    (message.source, message.ty, message.id, text)
+}
+
+/// Entry point for debug messages of the context, `user_param` points to the `DebugHandler`.
+///
+/// Panics must not unwind into the driver. The panic is stored and resumed
+/// by the next device call instead.
+pub(crate) extern "system" fn callback_ffi(
+    source: GLenum,
+    ty: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    user_param: *mut c_void,
+) {
+    let handler = unsafe { &*(user_param as *const DebugHandler) };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        // Application messages may not be null-terminated (e.g. on Mesa).
+        let message = if message.is_null() {
+            &[]
+        } else if length >= 0 {
+            unsafe { slice::from_raw_parts(message as *const u8, length as usize) }
+        } else {
+            unsafe { CStr::from_ptr(message) }.to_bytes()
+        };
+        handler.report_raw(source, ty, id, severity, message);
+    }));
+
+    if let Err(payload) = result {
+        let mut panic = handler.panic.lock().unwrap_or_else(PoisonError::into_inner);
+        // Keep the first panic, following messages are likely caused by it.
+        if panic.is_none() {
+            *panic = Some(payload);
+            handler.panicked.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Map the severity of a message to a `log` level.
+///
+/// Severities are GL enum values and overlap bitwise, they need to be compared by equality.
+#[cfg(feature = "log")]
+fn log_level(severity: DebugReport) -> log::Level {
+    if severity == DebugReport::ERROR {
+        log::Level::Error
+    } else if severity == DebugReport::WARNING {
+        log::Level::Warn
+    } else if severity == DebugReport::PERFORMANCE_WARNING {
+        log::Level::Info
+    } else {
+        log::Level::Debug
+    }
+}
+
+/// Debug callback forwarding messages to the `log` crate with target `grr`.
+///
+/// Errors are logged as `Error`, warnings as `Warn`, performance warnings as `Info`
+/// and notifications as `Debug`.
+#[cfg(feature = "log")]
+pub fn log_callback() -> DebugCallback {
+    Box::new(|msg| {
+        log::log!(
+            target: "grr",
+            log_level(msg.severity),
+            "{:?} {:?} ({}): {}",
+            msg.source,
+            msg.ty,
+            msg.id,
+            msg.message
+        )
+    })
+}
+
+/// Debug callback forwarding messages as `tracing` events with target `grr`.
+///
+/// Errors are reported as `ERROR`, warnings as `WARN`, performance warnings as `INFO`
+/// and notifications as `DEBUG` events.
+#[cfg(feature = "tracing")]
+pub fn tracing_callback() -> DebugCallback {
+    Box::new(|msg| {
+        // Event levels need to be constant.
+        macro_rules! event {
+            ($level:expr) => {
+                tracing::event!(
+                    target: "grr",
+                    $level,
+                    source = ?msg.source,
+                    ty = ?msg.ty,
+                    id = msg.id,
+                    "{}",
+                    msg.message
+                )
+            };
+        }
+
+        // Severities are GL enum values and overlap bitwise.
+        if msg.severity == DebugReport::ERROR {
+            event!(tracing::Level::ERROR)
+        } else if msg.severity == DebugReport::WARNING {
+            event!(tracing::Level::WARN)
+        } else if msg.severity == DebugReport::PERFORMANCE_WARNING {
+            event!(tracing::Level::INFO)
+        } else {
+            event!(tracing::Level::DEBUG)
+        }
+    })
+}
 
 ///
 #[repr(u32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ObjectType {
     Buffer = __gl::BUFFER,
     Shader = __gl::SHADER,
@@ -84,6 +403,19 @@ pub enum ObjectType {
     Framebuffer = __gl::FRAMEBUFFER,
     Renderbuffer = __gl::RENDERBUFFER,
     Sampler = __gl::SAMPLER,
+    Query = __gl::QUERY,
+}
+
+impl ObjectType {
+    /// Check if objects of this type are shared between the contexts of a share group.
+    ///
+    /// Container objects (vertex arrays, framebuffers and queries) are not shared.
+    pub fn is_shared(self) -> bool {
+        !matches!(
+            self,
+            ObjectType::VertexArray | ObjectType::Framebuffer | ObjectType::Query
+        )
+    }
 }
 
 pub trait Object: Copy {
@@ -92,8 +424,32 @@ pub trait Object: Copy {
     fn handle(&self) -> GLuint;
 }
 
+/// Object identified by a pointer instead of a name, i.e. fence sync objects.
+pub trait PtrObject: Copy {
+    fn ptr(&self) -> GLsync;
+}
+
+impl PtrObject for GLsync {
+    fn ptr(&self) -> GLsync {
+        *self
+    }
+}
+
+/// Debug group, popped when dropped.
+///
+/// Created by [`Device::debug_scope`](struct.Device.html#method.debug_scope).
+pub struct DebugScope<'a> {
+    device: &'a Device,
+}
+
+impl Drop for DebugScope<'_> {
+    fn drop(&mut self) {
+        unsafe { self.device.end_debug_marker() }
+    }
+}
+
 pub(crate) unsafe fn set_debug_message_control(
-    ctxt: &__gl::Gl,
+    ctxt: &dyn Backend,
     enable: bool,
     src: MsgFilter<DebugSource>,
     ty: MsgFilter<DebugType>,
@@ -148,8 +504,130 @@ pub(crate) unsafe fn set_debug_message_control(
 }
 
 impl Device {
+    /// Resume a panic of the debug callback caught at the FFI boundary.
+    pub(crate) fn resume_debug_panic(&self) {
+        let handler = match self.1.debug_callback {
+            Some(ref handler) => handler,
+            None => return,
+        };
+        if handler.panicked.swap(false, Ordering::Acquire) {
+            let payload = handler
+                .panic
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            if let Some(payload) = payload {
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+
+    /// Recent debug messages, oldest first.
+    ///
+    /// Requires a non-zero `DebugFilter::log_capacity`. Without debug callback,
+    /// pending messages are retrieved from the context log first. The context only
+    /// keeps a limited number of messages (`GL_MAX_DEBUG_LOGGED_MESSAGES`) between calls.
+    ///
+    /// ```no_run
+    /// # unsafe {
+    /// # let grr = grr::Device::new(|_| panic!(), grr::Debug::Disable);
+    /// let errors = grr
+    ///     .debug_log()
+    ///     .iter()
+    ///     .filter(|msg| msg.ty == grr::DebugType::Error)
+    ///     .count();
+    /// assert_eq!(errors, 0);
+    /// # }
+    /// ```
+    #[track_caller]
+    pub unsafe fn debug_log(&self) -> Vec<DebugLogEntry> {
+        let _scope = self.api_scope("debug_log");
+        let handler = match self.1.debug_callback {
+            Some(ref handler) => handler,
+            None => return Vec::new(),
+        };
+
+        if !handler.has_callback() {
+            loop {
+                let mut len = 0;
+                self.0
+                    .GetIntegerv(__gl::DEBUG_NEXT_LOGGED_MESSAGE_LENGTH, &mut len);
+                if len <= 0 {
+                    break;
+                }
+
+                let mut message = vec![0u8; len as usize];
+                let (mut source, mut ty, mut id, mut severity, mut length) = (0, 0, 0, 0, 0);
+                let count = self.0.GetDebugMessageLog(
+                    1,
+                    len,
+                    &mut source,
+                    &mut ty,
+                    &mut id,
+                    &mut severity,
+                    &mut length,
+                    message.as_mut_ptr() as *mut _,
+                );
+                if count == 0 {
+                    break;
+                }
+                // The length includes the null terminator.
+                message.truncate((length.max(1) - 1) as usize);
+                handler.report_raw(source, ty, id, severity, &message);
+            }
+        }
+
+        let state = handler.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.log.iter().cloned().collect()
+    }
+
+    /// Clear the debug log.
+    pub fn clear_debug_log(&self) {
+        if let Some(ref handler) = self.1.debug_callback {
+            handler
+                .state
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .log
+                .clear();
+        }
+    }
+
+    /// Number of debug messages suppressed by the debug filter.
+    pub fn suppressed_debug_messages(&self) -> u64 {
+        match self.1.debug_callback {
+            Some(ref handler) => {
+                handler
+                    .state
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .suppressed
+            }
+            None => 0,
+        }
+    }
+
+    /// Name a newly created object, if a label is passed.
+    pub(crate) unsafe fn label_object<T: Object>(&self, object: T, label: Option<&str>) {
+        if let Some(label) = label {
+            self.object_name(object, label);
+        }
+    }
+
     /// Associate a name with an object.
+    ///
+    /// Objects can also be named on creation via the `label` parameter of the `create_*` calls.
+    #[track_caller]
     pub unsafe fn object_name<T: Object>(&self, object: T, name: &str) {
+        let _scope = self.api_scope("object_name");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::ObjectName {
+            ty: T::TYPE,
+            object: object.handle(),
+            name: name.to_string(),
+        });
+        #[cfg(feature = "tracking")]
+        self.label_resource(T::TYPE, object.handle(), name);
         let label = name.as_bytes();
         self.0.ObjectLabel(
             T::TYPE as _,
@@ -159,6 +637,90 @@ impl Device {
         );
     }
 
+    /// Retrieve the name of an object, empty if the object has no name.
+    #[track_caller]
+    pub unsafe fn get_object_name<T: Object>(&self, object: T) -> String {
+        let _scope = self.api_scope("get_object_name");
+        let mut len = 0;
+        self.0
+            .GetObjectLabel(T::TYPE as _, object.handle(), 0, &mut len, ptr::null_mut());
+        let mut label = vec![0u8; len as usize + 1];
+        self.0.GetObjectLabel(
+            T::TYPE as _,
+            object.handle(),
+            label.len() as _,
+            &mut len,
+            label.as_mut_ptr() as *mut _,
+        );
+        label.truncate(len as usize);
+        String::from_utf8_lossy(&label).into_owned()
+    }
+
+    /// Associate a name with a pointer object (e.g. a fence).
+    #[track_caller]
+    pub unsafe fn object_ptr_name<T: PtrObject>(&self, object: T, name: &str) {
+        let _scope = self.api_scope("object_ptr_name");
+        let label = name.as_bytes();
+        self.0.ObjectPtrLabel(
+            object.ptr() as *const _,
+            label.len() as _,
+            label.as_ptr() as *const _,
+        );
+    }
+
+    /// Retrieve the name of a pointer object, empty if the object has no name.
+    #[track_caller]
+    pub unsafe fn get_object_ptr_name<T: PtrObject>(&self, object: T) -> String {
+        let _scope = self.api_scope("get_object_ptr_name");
+        let mut len = 0;
+        self.0
+            .GetObjectPtrLabel(object.ptr() as *const _, 0, &mut len, ptr::null_mut());
+        let mut label = vec![0u8; len as usize + 1];
+        self.0.GetObjectPtrLabel(
+            object.ptr() as *const _,
+            label.len() as _,
+            &mut len,
+            label.as_mut_ptr() as *mut _,
+        );
+        label.truncate(len as usize);
+        String::from_utf8_lossy(&label).into_owned()
+    }
+
+    /// Insert a message into the debug output.
+    ///
+    /// # Valid usage
+    ///
+    /// - `src` must be `DebugSource::Application` or `DebugSource::ThirdParty`.
+    /// - `severity` must be a single flag.
+    #[track_caller]
+    pub unsafe fn insert_debug_message(
+        &self,
+        src: DebugSource,
+        ty: DebugType,
+        id: u32,
+        severity: DebugReport,
+        msg: &str,
+    ) {
+        let _scope = self.api_scope("insert_debug_message");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::InsertDebugMessage {
+            src,
+            ty,
+            id,
+            severity,
+            msg: msg.to_string(),
+        });
+        self.0.DebugMessageInsert(
+            src as _,
+            ty as _,
+            id,
+            severity.bits(),
+            msg.len() as _,
+            msg.as_ptr() as *const _,
+        );
+    }
+
+    #[track_caller]
     pub unsafe fn enable_debug_message(
         &self,
         src: MsgFilter<DebugSource>,
@@ -166,9 +728,11 @@ impl Device {
         flags: DebugReport,
         ids: Option<&[u32]>,
     ) {
-        set_debug_message_control(&self.0, true, src, ty, flags, ids);
+        let _scope = self.api_scope("enable_debug_message");
+        set_debug_message_control(&*self.0, true, src, ty, flags, ids);
     }
 
+    #[track_caller]
     pub unsafe fn disable_debug_message(
         &self,
         src: MsgFilter<DebugSource>,
@@ -176,15 +740,151 @@ impl Device {
         flags: DebugReport,
         ids: Option<&[u32]>,
     ) {
-        set_debug_message_control(&self.0, false, src, ty, flags, ids);
+        let _scope = self.api_scope("disable_debug_message");
+        set_debug_message_control(&*self.0, false, src, ty, flags, ids);
     }
 
+    #[track_caller]
     pub unsafe fn begin_debug_marker(&self, src: DebugSource, id: u32, msg: &str) {
+        let _scope = self.api_scope("begin_debug_marker");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BeginDebugMarker {
+            src,
+            id,
+            msg: msg.to_string(),
+        });
         self.0
             .PushDebugGroup(src as _, id, msg.len() as _, msg.as_ptr() as *const _);
     }
 
+    #[track_caller]
     pub unsafe fn end_debug_marker(&self) {
+        let _scope = self.api_scope("end_debug_marker");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::EndDebugMarker);
         self.0.PopDebugGroup();
     }
+
+    /// Push a debug group, which is popped when the returned scope is dropped.
+    ///
+    /// See [`begin_debug_marker`](#method.begin_debug_marker).
+    #[track_caller]
+    pub unsafe fn debug_scope(&self, src: DebugSource, id: u32, msg: &str) -> DebugScope<'_> {
+        self.begin_debug_marker(src, id, msg);
+        DebugScope { device: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Handler recording the ids of all forwarded messages.
+    fn handler(filter: DebugFilter) -> (DebugHandler, Arc<Mutex<Vec<u32>>>) {
+        let forwarded = Arc::new(Mutex::new(Vec::new()));
+        let sink = forwarded.clone();
+        let callback: DebugCallback = Box::new(move |msg| sink.lock().unwrap().push(msg.id));
+        (DebugHandler::new(Some(callback), filter), forwarded)
+    }
+
+    fn report(handler: &DebugHandler, id: u32, message: &str) {
+        handler.report(DebugMessage {
+            severity: DebugReport::WARNING,
+            source: DebugSource::Api,
+            ty: DebugType::Other,
+            id,
+            message,
+        });
+    }
+
+    fn suppressed(handler: &DebugHandler) -> u64 {
+        handler.state.lock().unwrap().suppressed
+    }
+
+    #[test]
+    fn repeat_cap_per_id() {
+        let (handler, forwarded) = handler(DebugFilter {
+            max_repeats: Some(2),
+            ..DebugFilter::default()
+        });
+        for _ in 0..4 {
+            report(&handler, 1, "a");
+        }
+        report(&handler, 2, "b");
+        report(&handler, 1, "c");
+
+        assert_eq!(*forwarded.lock().unwrap(), [1, 1, 2]);
+        assert_eq!(suppressed(&handler), 3);
+    }
+
+    #[test]
+    fn messages_without_id_are_keyed_by_text() {
+        let (handler, forwarded) = handler(DebugFilter {
+            max_repeats: Some(1),
+            ..DebugFilter::default()
+        });
+        report(&handler, 0, "map_buffer: buffer 1 is already mapped");
+        report(&handler, 0, "map_buffer: buffer 1 is already mapped");
+        report(&handler, 0, "unmap_buffer: buffer 2 is not mapped");
+
+        assert_eq!(forwarded.lock().unwrap().len(), 2);
+        assert_eq!(suppressed(&handler), 1);
+    }
+
+    #[test]
+    fn rate_limit_window_reset() {
+        let (handler, forwarded) = handler(DebugFilter {
+            rate_limit: Some(2),
+            ..DebugFilter::default()
+        });
+        for id in 1..=3 {
+            report(&handler, id, "a");
+        }
+        assert_eq!(*forwarded.lock().unwrap(), [1, 2]);
+        assert_eq!(suppressed(&handler), 1);
+
+        // Move the start of the current interval into the past.
+        handler.state.lock().unwrap().interval = Instant::now().checked_sub(Duration::from_secs(2));
+        for id in 4..=6 {
+            report(&handler, id, "a");
+        }
+        assert_eq!(*forwarded.lock().unwrap(), [1, 2, 4, 5]);
+        assert_eq!(suppressed(&handler), 2);
+    }
+
+    #[test]
+    fn log_evicts_oldest_entries() {
+        let (handler, forwarded) = handler(DebugFilter {
+            max_repeats: Some(1),
+            log_capacity: 3,
+            ..DebugFilter::default()
+        });
+        for (id, message) in [(1, "a"), (2, "b"), (1, "c"), (3, "d"), (4, "e")] {
+            report(&handler, id, message);
+        }
+
+        // Suppressed messages are still logged.
+        let state = handler.state.lock().unwrap();
+        let log = state
+            .log
+            .iter()
+            .map(|entry| (entry.id, entry.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(log, [(1, "c"), (3, "d"), (4, "e")]);
+        assert_eq!(*forwarded.lock().unwrap(), [1, 2, 3, 4]);
+        assert_eq!(state.suppressed, 1);
+    }
+
+    #[test]
+    fn no_filter_forwards_everything() {
+        let (handler, forwarded) = handler(DebugFilter::default());
+        for _ in 0..10 {
+            report(&handler, 1, "a");
+        }
+
+        assert_eq!(forwarded.lock().unwrap().len(), 10);
+        assert_eq!(suppressed(&handler), 0);
+        assert!(handler.state.lock().unwrap().log.is_empty());
+    }
 }
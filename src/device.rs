@@ -1,16 +1,70 @@
 use crate::__gl;
-use crate::__gl::types::{GLchar, GLenum, GLsizei, GLuint};
+use crate::__gl::types::GLenum;
 
+use std::cell::{Cell, RefCell};
+use std::ffi;
 use std::os::raw::c_void;
-use std::{ffi, mem};
+#[cfg(any(debug_assertions, feature = "tracking", feature = "validation"))]
+use std::sync::{Arc, Mutex};
 
-use crate::debug::{self, DebugCallback, DebugReport};
+use crate::backend::Backend;
+use crate::cache::StateCache;
+#[cfg(feature = "capture")]
+use crate::capture::Capture;
+use crate::command::{ClipDepth, ClipOrigin};
+use crate::debug::{self, DebugCallback, DebugFilter, DebugHandler, DebugReport};
+use crate::error::ApiCall;
+#[cfg(feature = "headless")]
+use crate::error::Result;
+use crate::group;
+#[cfg(debug_assertions)]
+use crate::handle;
+#[cfg(feature = "headless")]
+use crate::headless;
+#[cfg(feature = "tracking")]
+use crate::tracking;
+#[cfg(feature = "validation")]
+use crate::validation;
 
 /// Logical device, representation one or multiple physical devices (hardware or software).
 ///
 /// This wraps an existing GL context and acts as the main API interface.
 /// It's the responsibility of the user to keep the context alive.
-pub struct Device(pub(crate) __gl::Gl, Option<Box<DebugCallback>>);
+pub struct Device(pub(crate) Box<dyn Backend + Send>, pub(crate) DeviceInner);
+
+/// Device owned data, which is not part of the GL context.
+pub(crate) struct DeviceInner {
+    /// Context owned by the device, destroyed together with the device.
+    #[cfg(feature = "headless")]
+    headless: Option<headless::Context>,
+    /// Needs to outlive the context as the driver may still report messages on destruction.
+    pub(crate) debug_callback: Option<Box<DebugHandler>>,
+    /// First non-ok reset status, the context stays lost after a reset.
+    pub(crate) status: Cell<DeviceStatus>,
+    /// Shadow state for redundant state elimination.
+    pub(crate) state_cache: RefCell<Option<StateCache>>,
+    /// Check for API errors after every call.
+    pub(crate) error_checks: bool,
+    /// Outermost call in flight with enabled error checks.
+    pub(crate) api_call: Cell<Option<ApiCall>>,
+    /// Active API capture.
+    #[cfg(feature = "capture")]
+    pub(crate) capture: RefCell<Option<Capture>>,
+    /// Tracked state for validation of valid usage rules.
+    #[cfg(feature = "validation")]
+    pub(crate) validation: RefCell<validation::State>,
+    /// Tracked shared objects for validation, shared with the device group.
+    #[cfg(feature = "validation")]
+    pub(crate) validation_objects: Arc<Mutex<validation::Objects>>,
+    /// Generations of live objects for detecting stale handles, shared with the device group.
+    #[cfg(debug_assertions)]
+    pub(crate) handles: Arc<Mutex<handle::Registry>>,
+    /// Live objects with their creation site and estimated size, shared with the device group.
+    #[cfg(feature = "tracking")]
+    pub(crate) tracking: Arc<Mutex<tracking::Registry>>,
+    /// Membership in a device group over shared contexts.
+    pub(crate) group: Option<group::Member>,
+}
 
 /// Device debug control.
 pub enum Debug<F> {
@@ -18,46 +72,243 @@ pub enum Debug<F> {
     Disable,
 }
 
+/// Device Descriptor.
+///
+/// Configures the context state set up on device creation
+/// (see [`Device::new_with_desc`](struct.Device.html#method.new_with_desc)) and
+/// devices which own their context (e.g. [`Device::new_headless`](struct.Device.html#method.new_headless)).
+///
+/// The defaults match `Device::new`: sRGB framebuffer writes, scissor testing, seamless cube maps,
+/// program point size and sample mask are enabled, with a lower-left origin and [0; 1] depth range.
+///
+/// ```
+/// let desc = grr::DeviceDesc::default()
+///     .clip_control(grr::ClipOrigin::UpperLeft, grr::ClipDepth::ZeroToOne)
+///     .framebuffer_srgb(false);
+/// ```
+pub struct DeviceDesc {
+    /// Debug output of the context.
+    ///
+    /// Enabling debug output will also request a debug context.
+    pub debug: Debug<DebugCallback>,
+
+    /// Deduplication, rate limiting and logging of debug messages.
+    ///
+    /// A non-zero `DebugFilter::log_capacity` enables debug output even without callback.
+    pub debug_filter: DebugFilter,
+
+    /// Request a robust context, which reports device resets.
+    ///
+    /// The context is lost on reset, detectable via [`Device::status`](struct.Device.html#method.status).
+    /// Contexts created by the user need to be requested with robust access and
+    /// `LOSE_CONTEXT_ON_RESET` notification strategy for reset detection.
+    pub robust: bool,
+
+    /// Skip GL calls setting state which is already current.
+    ///
+    /// The device needs to be informed about state changes by foreign GL code,
+    /// see [`Device::invalidate_state_cache`](struct.Device.html#method.invalidate_state_cache).
+    pub state_cache: bool,
+
+    /// Check for API errors after every call.
+    ///
+    /// Calls returning a `Result` report errors as `Error::Api`, other calls panic.
+    /// Errors are attributed to the call site of the device function, including errors
+    /// raised by foreign GL code before the call. Intended for debugging on drivers
+    /// without debug output, as every call waits for the error state of the context.
+    pub error_checks: bool,
+
+    /// Linear to sRGB conversion on writes to sRGB framebuffer attachments.
+    pub framebuffer_srgb: bool,
+    /// Scissor testing against the scissor rectangles.
+    pub scissor_test: bool,
+    /// Filtering across cube map faces.
+    pub seamless_cube_map: bool,
+    /// Point size specified by the shader (GLSL: `gl_PointSize`).
+    pub program_point_size: bool,
+    /// Sample mask testing for multisampled rasterization.
+    pub sample_mask: bool,
+    /// Origin of the window coordinate system.
+    pub clip_origin: ClipOrigin,
+    /// Depth range of the clip volume.
+    pub clip_depth: ClipDepth,
+}
+
+impl Default for DeviceDesc {
+    fn default() -> Self {
+        DeviceDesc {
+            debug: Debug::Disable,
+            debug_filter: DebugFilter::default(),
+            robust: false,
+            state_cache: false,
+            error_checks: false,
+            framebuffer_srgb: true,
+            scissor_test: true,
+            seamless_cube_map: true,
+            program_point_size: true,
+            sample_mask: true,
+            clip_origin: ClipOrigin::LowerLeft,
+            clip_depth: ClipDepth::ZeroToOne,
+        }
+    }
+}
+
+impl DeviceDesc {
+    /// Set the debug output of the context.
+    pub fn debug(mut self, debug: Debug<DebugCallback>) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Set the filtering and logging of debug messages.
+    pub fn debug_filter(mut self, filter: DebugFilter) -> Self {
+        self.debug_filter = filter;
+        self
+    }
+
+    /// Request a robust context.
+    pub fn robust(mut self, robust: bool) -> Self {
+        self.robust = robust;
+        self
+    }
+
+    /// Enable or disable redundant state elimination.
+    pub fn state_cache(mut self, enable: bool) -> Self {
+        self.state_cache = enable;
+        self
+    }
+
+    /// Enable or disable API error checks after every call.
+    pub fn error_checks(mut self, enable: bool) -> Self {
+        self.error_checks = enable;
+        self
+    }
+
+    /// Enable or disable sRGB conversion on framebuffer writes.
+    pub fn framebuffer_srgb(mut self, enable: bool) -> Self {
+        self.framebuffer_srgb = enable;
+        self
+    }
+
+    /// Enable or disable scissor testing.
+    pub fn scissor_test(mut self, enable: bool) -> Self {
+        self.scissor_test = enable;
+        self
+    }
+
+    /// Enable or disable seamless cube map filtering.
+    pub fn seamless_cube_map(mut self, enable: bool) -> Self {
+        self.seamless_cube_map = enable;
+        self
+    }
+
+    /// Enable or disable shader specified point sizes.
+    pub fn program_point_size(mut self, enable: bool) -> Self {
+        self.program_point_size = enable;
+        self
+    }
+
+    /// Enable or disable sample mask testing.
+    pub fn sample_mask(mut self, enable: bool) -> Self {
+        self.sample_mask = enable;
+        self
+    }
+
+    /// Set the window coordinate origin and clip space depth range.
+    pub fn clip_control(mut self, origin: ClipOrigin, depth: ClipDepth) -> Self {
+        self.clip_origin = origin;
+        self.clip_depth = depth;
+        self
+    }
+}
+
+/// Reset status of a device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DeviceStatus {
+    /// No reset occurred, the device is operational.
+    Ok,
+    /// The context was lost by a reset caused by this device.
+    GuiltyReset,
+    /// The context was lost by a reset caused by another context.
+    InnocentReset,
+    /// The context was lost by a reset with unknown cause.
+    UnknownReset,
+}
+
 impl Device {
     /// Create a new device from an existing context.
     ///
     /// The context must be initialized with GL 4.5+ core profile.
     /// The passed `loader` is used to obtain the function pointers from the context.
+    ///
+    /// The context state is initialized with the defaults of `DeviceDesc`.
     pub unsafe fn new<F>(loader: F, debug: Debug<DebugCallback>) -> Self
     where
         F: FnMut(&str) -> *const c_void,
     {
-        let ctxt = __gl::Gl::load_with(loader);
+        Device::new_with_desc(
+            loader,
+            DeviceDesc {
+                debug,
+                ..Default::default()
+            },
+        )
+    }
 
-        let cb = match debug {
-            Debug::Enable { callback, flags } => {
-                extern "system" fn callback_ffi(
-                    source: GLenum,
-                    gltype: GLenum,
-                    id: GLuint,
-                    severity: GLenum,
-                    _length: GLsizei,
-                    message: *const GLchar,
-                    user_param: *mut c_void,
-                ) {
-                    unsafe {
-                        let cb = Box::from_raw(user_param as *mut DebugCallback);
-                        let msg = ffi::CStr::from_ptr(message).to_str().unwrap();
-                        cb(
-                            mem::transmute(severity),
-                            mem::transmute(source),
-                            mem::transmute(gltype),
-                            id,
-                            msg,
-                        );
-                        Box::into_raw(cb);
-                    }
-                }
+    /// Create a new device from an existing context, initializing the context state from `desc`.
+    ///
+    /// The context must be initialized with GL 4.5+ core profile.
+    /// `DeviceDesc::robust` only describes the passed context, which needs to be created accordingly.
+    pub unsafe fn new_with_desc<F>(loader: F, desc: DeviceDesc) -> Self
+    where
+        F: FnMut(&str) -> *const c_void,
+    {
+        Device::with_backend_desc(__gl::Gl::load_with(loader), desc)
+    }
+
+    /// Create a new device issuing all GL calls to a custom backend.
+    ///
+    /// See [`RecordingBackend`](struct.RecordingBackend.html) for testing without a GL context.
+    pub unsafe fn with_backend<B>(backend: B, debug: Debug<DebugCallback>) -> Self
+    where
+        B: Backend + Send + 'static,
+    {
+        Device::with_backend_desc(
+            backend,
+            DeviceDesc {
+                debug,
+                ..Default::default()
+            },
+        )
+    }
 
-                let cb = Box::new(callback);
-                let cb_raw = Box::into_raw(cb);
+    /// Create a new device issuing all GL calls to a custom backend,
+    /// initializing the context state from `desc`.
+    pub unsafe fn with_backend_desc<B>(backend: B, desc: DeviceDesc) -> Self
+    where
+        B: Backend + Send + 'static,
+    {
+        let ctxt: Box<dyn Backend + Send> = Box::new(backend);
+
+        let debug = match desc.debug {
+            Debug::Enable { callback, flags } => Some((Some(callback), flags)),
+            // Messages are retrieved from the context log by `Device::debug_log`.
+            Debug::Disable if desc.debug_filter.log_capacity > 0 => Some((None, DebugReport::FULL)),
+            Debug::Disable => None,
+        };
+
+        let cb = match debug {
+            Some((callback, flags)) => {
+                let has_callback = callback.is_some();
+                // Boxed for a stable address, referenced by the context.
+                let handler = Box::new(DebugHandler::new(callback, desc.debug_filter));
                 ctxt.Enable(__gl::DEBUG_OUTPUT);
-                ctxt.DebugMessageCallback(Some(callback_ffi), cb_raw as *mut _);
+                if has_callback {
+                    ctxt.DebugMessageCallback(
+                        Some(debug::callback_ffi),
+                        &*handler as *const DebugHandler as *mut _,
+                    );
+                }
                 ctxt.DebugMessageControl(
                     __gl::DONT_CARE,
                     __gl::DONT_CARE,
@@ -67,40 +318,129 @@ impl Device {
                     __gl::FALSE,
                 );
                 debug::set_debug_message_control(
-                    &ctxt,
+                    &*ctxt,
                     true,
                     debug::MsgFilter::All,
                     debug::MsgFilter::All,
                     flags,
                     None,
                 );
-                Some(Box::from_raw(cb_raw))
+                Some(handler)
             }
-            Debug::Disable => {
+            None => {
                 ctxt.Disable(__gl::DEBUG_OUTPUT);
                 None
             }
         };
 
-        // Enforce sRGB frmaebuffer handling
-        ctxt.Enable(__gl::FRAMEBUFFER_SRGB);
-        // Enforce lower-left window coordinate system with [0; 1] depth range
-        ctxt.ClipControl(__gl::LOWER_LEFT, __gl::ZERO_TO_ONE);
-        // Always enable scissor testing
-        ctxt.Enable(__gl::SCISSOR_TEST);
-        ctxt.Enable(__gl::TEXTURE_CUBE_MAP_SEAMLESS);
-        ctxt.Enable(__gl::PROGRAM_POINT_SIZE);
-        ctxt.Enable(__gl::SAMPLE_MASK);
+        // Explicitly set all state, external contexts may deviate from the GL defaults.
+        let set_capability = |cap, enable| {
+            if enable {
+                ctxt.Enable(cap);
+            } else {
+                ctxt.Disable(cap);
+            }
+        };
+        set_capability(__gl::FRAMEBUFFER_SRGB, desc.framebuffer_srgb);
+        set_capability(__gl::SCISSOR_TEST, desc.scissor_test);
+        set_capability(__gl::TEXTURE_CUBE_MAP_SEAMLESS, desc.seamless_cube_map);
+        set_capability(__gl::PROGRAM_POINT_SIZE, desc.program_point_size);
+        set_capability(__gl::SAMPLE_MASK, desc.sample_mask);
+        ctxt.ClipControl(desc.clip_origin as _, desc.clip_depth as _);
+
+        let device = Device(
+            ctxt,
+            DeviceInner {
+                #[cfg(feature = "headless")]
+                headless: None,
+                debug_callback: cb,
+                status: Cell::new(DeviceStatus::Ok),
+                error_checks: desc.error_checks,
+                api_call: Cell::new(None),
+                state_cache: RefCell::new(if desc.state_cache {
+                    Some(StateCache::default())
+                } else {
+                    None
+                }),
+                #[cfg(feature = "capture")]
+                capture: RefCell::new(None),
+                #[cfg(feature = "validation")]
+                validation: RefCell::default(),
+                #[cfg(feature = "validation")]
+                validation_objects: Arc::default(),
+                #[cfg(debug_assertions)]
+                handles: Arc::default(),
+                #[cfg(feature = "tracking")]
+                tracking: Arc::default(),
+                group: None,
+            },
+        );
+        #[cfg(feature = "validation")]
+        device.validator().set_scissor_test(desc.scissor_test);
+        device
+    }
+
+    /// Create a new device without any window system.
+    ///
+    /// The device owns a surfaceless (or pbuffer-backed) GL 4.5 core context created via EGL,
+    /// e.g. running on Mesa's software rasterizer when no GPU is available.
+    /// The context is made current on the calling thread and destroyed when the device is dropped.
+    ///
+    /// Rendering is only possible into framebuffers created by the user, the default
+    /// framebuffer has no backing memory. Initial viewport and scissor rectangles are empty.
+    ///
+    /// Requires the `headless` feature and a loadable `libEGL`.
+    #[cfg(feature = "headless")]
+    pub unsafe fn new_headless(desc: DeviceDesc) -> Result<Self> {
+        let debug = match desc.debug {
+            Debug::Enable { .. } => true,
+            Debug::Disable => desc.debug_filter.log_capacity > 0,
+        };
+        let context = headless::Context::new(debug, desc.robust)?;
+        Ok(Device::with_headless_context(context, desc))
+    }
 
-        Device(ctxt, cb)
+    #[cfg(feature = "headless")]
+    pub(crate) unsafe fn with_headless_context(
+        context: headless::Context,
+        desc: DeviceDesc,
+    ) -> Self {
+        let mut device = Device::new_with_desc(|symbol| context.get_proc_address(symbol), desc);
+        device.1.headless = Some(context);
+        device
+    }
+
+    /// Make the context owned by the device current on the calling thread.
+    ///
+    /// Required when switching between multiple headless devices (e.g. of a `DeviceGroup`)
+    /// or after moving a headless device to another thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device doesn't own its context.
+    #[cfg(feature = "headless")]
+    pub unsafe fn make_current(&self) -> Result<()> {
+        self.1
+            .headless
+            .as_ref()
+            .expect("Device doesn't own its context")
+            .make_current()
     }
 
     /// Return the underlying context for the device
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device wasn't created from a GL context (e.g. `RecordingBackend`).
     pub unsafe fn context(&self) -> &__gl::Gl {
-        &self.0
+        self.0
+            .as_gl()
+            .expect("Device is not backed by a GL context")
     }
 
+    #[track_caller]
     pub unsafe fn limits(&self) -> DeviceLimits {
+        let _scope = self.api_scope("limits");
         DeviceLimits {
             max_compute_work_group_invocations: self
                 .get_u32(__gl::MAX_COMPUTE_WORK_GROUP_INVOCATIONS, None),
@@ -123,39 +463,192 @@ impl Device {
             max_framebuffer_height: self.get_u32(__gl::MAX_FRAMEBUFFER_HEIGHT, None),
             max_framebuffer_layers: self.get_u32(__gl::MAX_FRAMEBUFFER_LAYERS, None),
             max_color_attachments: self.get_u32(__gl::MAX_COLOR_ATTACHMENTS, None),
-            max_viewport_dimensions: [
-                self.get_u32(__gl::MAX_VIEWPORT_DIMS, Some(0)),
-                self.get_u32(__gl::MAX_VIEWPORT_DIMS, Some(1)),
-            ],
+            max_viewport_dimensions: {
+                let mut dims = [0; 2];
+                self.0
+                    .GetIntegerv(__gl::MAX_VIEWPORT_DIMS, dims.as_mut_ptr());
+                [dims[0] as _, dims[1] as _]
+            },
             max_vertex_input_attributes: self.get_u32(__gl::MAX_VERTEX_ATTRIBS, None),
             max_vertex_input_bindings: self.get_u32(__gl::MAX_VERTEX_ATTRIB_BINDINGS, None),
             max_vertex_input_attribute_offset: self
                 .get_u32(__gl::MAX_VERTEX_ATTRIB_RELATIVE_OFFSET, None),
             max_vertex_input_binding_stride: self.get_u32(__gl::MAX_VERTEX_ATTRIB_STRIDE, None),
             max_vertex_output_components: self.get_u32(__gl::MAX_VERTEX_OUTPUT_COMPONENTS, None),
+            uniform_buffer_offset_alignment: self
+                .get_u32(__gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT, None),
+            storage_buffer_offset_alignment: self
+                .get_u32(__gl::SHADER_STORAGE_BUFFER_OFFSET_ALIGNMENT, None),
+            max_uniform_block_size: self.get_u32(__gl::MAX_UNIFORM_BLOCK_SIZE, None),
+            max_storage_block_size: self.get_u64(__gl::MAX_SHADER_STORAGE_BLOCK_SIZE),
+            max_uniform_buffer_bindings: self.get_u32(__gl::MAX_UNIFORM_BUFFER_BINDINGS, None),
+            max_storage_buffer_bindings: self
+                .get_u32(__gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS, None),
+            max_image_dimension_2d: self.get_u32(__gl::MAX_TEXTURE_SIZE, None),
+            max_image_dimension_3d: self.get_u32(__gl::MAX_3D_TEXTURE_SIZE, None),
+            max_image_dimension_cube: self.get_u32(__gl::MAX_CUBE_MAP_TEXTURE_SIZE, None),
+            max_image_array_layers: self.get_u32(__gl::MAX_ARRAY_TEXTURE_LAYERS, None),
+            max_texel_buffer_elements: self.get_u32(__gl::MAX_TEXTURE_BUFFER_SIZE, None),
+            max_texture_units: StageLimits {
+                vertex: self.get_u32(__gl::MAX_VERTEX_TEXTURE_IMAGE_UNITS, None),
+                tessellation_control: self
+                    .get_u32(__gl::MAX_TESS_CONTROL_TEXTURE_IMAGE_UNITS, None),
+                tessellation_evaluation: self
+                    .get_u32(__gl::MAX_TESS_EVALUATION_TEXTURE_IMAGE_UNITS, None),
+                geometry: self.get_u32(__gl::MAX_GEOMETRY_TEXTURE_IMAGE_UNITS, None),
+                fragment: self.get_u32(__gl::MAX_TEXTURE_IMAGE_UNITS, None),
+                compute: self.get_u32(__gl::MAX_COMPUTE_TEXTURE_IMAGE_UNITS, None),
+            },
+            max_combined_texture_units: self.get_u32(__gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, None),
+            max_storage_image_units: StageLimits {
+                vertex: self.get_u32(__gl::MAX_VERTEX_IMAGE_UNIFORMS, None),
+                tessellation_control: self.get_u32(__gl::MAX_TESS_CONTROL_IMAGE_UNIFORMS, None),
+                tessellation_evaluation: self
+                    .get_u32(__gl::MAX_TESS_EVALUATION_IMAGE_UNIFORMS, None),
+                geometry: self.get_u32(__gl::MAX_GEOMETRY_IMAGE_UNIFORMS, None),
+                fragment: self.get_u32(__gl::MAX_FRAGMENT_IMAGE_UNIFORMS, None),
+                compute: self.get_u32(__gl::MAX_COMPUTE_IMAGE_UNIFORMS, None),
+            },
+            max_combined_storage_image_units: self.get_u32(__gl::MAX_IMAGE_UNITS, None),
+            max_samples: self.get_u32(__gl::MAX_SAMPLES, None),
+            max_color_samples: self.get_u32(__gl::MAX_COLOR_TEXTURE_SAMPLES, None),
+            max_depth_samples: self.get_u32(__gl::MAX_DEPTH_TEXTURE_SAMPLES, None),
+            max_integer_samples: self.get_u32(__gl::MAX_INTEGER_SAMPLES, None),
+            max_sampler_anisotropy: if self.version() >= (Version { major: 4, minor: 6 })
+                || self.has_extension(&[
+                    "GL_ARB_texture_filter_anisotropic",
+                    "GL_EXT_texture_filter_anisotropic",
+                ]) {
+                self.get_f32(__gl::MAX_TEXTURE_MAX_ANISOTROPY)
+            } else {
+                1.0
+            },
+            max_patch_vertices: self.get_u32(__gl::MAX_PATCH_VERTICES, None),
+            max_transform_feedback_buffers: self
+                .get_u32(__gl::MAX_TRANSFORM_FEEDBACK_BUFFERS, None),
         }
     }
 
+    /// Query the supported features of the device.
+    ///
+    /// Optional functionality (e.g `ShaderSource::Spirv` or mesh shaders) should be
+    /// checked against the returned features before usage.
+    #[track_caller]
     pub unsafe fn features(&self) -> DeviceFeatures {
-        DeviceFeatures {}
+        let _scope = self.api_scope("features");
+        let version = self.version();
+        let shading_language_version =
+            Version::parse(&self.get_string(__gl::SHADING_LANGUAGE_VERSION)).unwrap_or_default();
+
+        let num_extensions = self.get_u32(__gl::NUM_EXTENSIONS, None);
+        let extensions = (0..num_extensions)
+            .map(|i| {
+                let name = self.0.GetStringi(__gl::EXTENSIONS, i);
+                ffi::CStr::from_ptr(name as *const _)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>();
+
+        let gl46 = version >= Version { major: 4, minor: 6 };
+        let has_extension = |name: &str| extensions.iter().any(|ext| ext == name);
+
+        DeviceFeatures {
+            spirv: gl46 || has_extension("GL_ARB_gl_spirv"),
+            draw_indirect_count: gl46 || has_extension("GL_ARB_indirect_parameters"),
+            mesh_shader_nv: has_extension("GL_NV_mesh_shader"),
+            pipeline_statistics_query: gl46 || has_extension("GL_ARB_pipeline_statistics_query"),
+            sampler_anisotropy: gl46
+                || has_extension("GL_ARB_texture_filter_anisotropic")
+                || has_extension("GL_EXT_texture_filter_anisotropic"),
+            polygon_offset_clamp: gl46
+                || has_extension("GL_ARB_polygon_offset_clamp")
+                || has_extension("GL_EXT_polygon_offset_clamp"),
+            version,
+            shading_language_version,
+            vendor: self.get_string(__gl::VENDOR),
+            renderer: self.get_string(__gl::RENDERER),
+            extensions,
+        }
     }
 
     /// Submit all pending operations for device execution.
     ///
     /// This function may return before all operations have finished executing.
+    #[track_caller]
     pub unsafe fn submit(&self) {
+        let _scope = self.api_scope("submit");
         self.0.Flush();
     }
 
     /// Wait on the host for execution of all outstanding device operations.
+    #[track_caller]
     pub unsafe fn wait_idle(&self) {
+        let _scope = self.api_scope("wait_idle");
         self.0.Finish();
     }
 
+    /// Query the reset status of the device.
+    ///
+    /// Once a reset has been detected, the device stays lost and all resource creation
+    /// calls return `Error::DeviceLost`. Resets are only reported for robust contexts
+    /// (see `DeviceDesc::robust`), otherwise the status is always `DeviceStatus::Ok`.
+    pub unsafe fn status(&self) -> DeviceStatus {
+        if self.1.status.get() == DeviceStatus::Ok {
+            let status = match self.0.GetGraphicsResetStatus() {
+                __gl::GUILTY_CONTEXT_RESET => DeviceStatus::GuiltyReset,
+                __gl::INNOCENT_CONTEXT_RESET => DeviceStatus::InnocentReset,
+                __gl::UNKNOWN_CONTEXT_RESET => DeviceStatus::UnknownReset,
+                _ => DeviceStatus::Ok,
+            };
+            self.1.status.set(status);
+        }
+        self.1.status.get()
+    }
+
+    pub(crate) unsafe fn version(&self) -> Version {
+        Version {
+            major: self.get_u32(__gl::MAJOR_VERSION, None),
+            minor: self.get_u32(__gl::MINOR_VERSION, None),
+        }
+    }
+
+    /// Check if any of the extensions is supported, without querying all extension names.
+    pub(crate) unsafe fn has_extension(&self, names: &[&str]) -> bool {
+        let num_extensions = self.get_u32(__gl::NUM_EXTENSIONS, None);
+        (0..num_extensions).any(|i| {
+            let name = self.0.GetStringi(__gl::EXTENSIONS, i);
+            let name = ffi::CStr::from_ptr(name as *const _).to_bytes();
+            names.iter().any(|ext| ext.as_bytes() == name)
+        })
+    }
+
+    unsafe fn get_u64(&self, target: GLenum) -> u64 {
+        let mut value = 0;
+        self.0.GetInteger64v(target, &mut value);
+        value as _
+    }
+
+    unsafe fn get_f32(&self, target: GLenum) -> f32 {
+        let mut value = 0.0;
+        self.0.GetFloatv(target, &mut value);
+        value
+    }
+
     unsafe fn get_u32(&self, target: GLenum, index: Option<usize>) -> u32 {
         self.get_i32(target, index) as _
     }
 
+    unsafe fn get_string(&self, name: GLenum) -> String {
+        let string = self.0.GetString(name);
+        if string.is_null() {
+            return String::new();
+        }
+        ffi::CStr::from_ptr(string as *const _)
+            .to_string_lossy()
+            .into_owned()
+    }
+
     unsafe fn get_i32(&self, target: GLenum, index: Option<usize>) -> i32 {
         let mut value = 0;
         match index {
@@ -166,7 +659,8 @@ impl Device {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceLimits {
     /// Maximum number of total invocations in a single workgroup.
     pub max_compute_work_group_invocations: u32,
@@ -206,10 +700,146 @@ pub struct DeviceLimits {
     pub max_vertex_input_binding_stride: u32,
 
     pub max_vertex_output_components: u32,
+
+    /// Required alignment in bytes for offsets of uniform buffer ranges.
+    pub uniform_buffer_offset_alignment: u32,
+    /// Required alignment in bytes for offsets of storage buffer ranges.
+    pub storage_buffer_offset_alignment: u32,
+    /// Maximum size in bytes of a uniform block.
+    ///
+    /// Minimum value: 16384
+    pub max_uniform_block_size: u32,
+    /// Maximum size in bytes of a shader storage block.
+    pub max_storage_block_size: u64,
+    /// Maximum number of uniform buffer binding points.
+    pub max_uniform_buffer_bindings: u32,
+    /// Maximum number of storage buffer binding points.
+    pub max_storage_buffer_bindings: u32,
+
+    /// Maximum width and height of 1D and 2D images.
+    pub max_image_dimension_2d: u32,
+    /// Maximum width, height and depth of 3D images.
+    pub max_image_dimension_3d: u32,
+    /// Maximum width and height of cube map images.
+    pub max_image_dimension_cube: u32,
+    /// Maximum number of layers of array images.
+    pub max_image_array_layers: u32,
+    /// Maximum number of texels of texel buffers.
+    pub max_texel_buffer_elements: u32,
+
+    /// Maximum number of texture units accessible in each shader stage.
+    pub max_texture_units: StageLimits,
+    /// Maximum number of texture units accessible over all shader stages.
+    pub max_combined_texture_units: u32,
+    /// Maximum number of storage images accessible in each shader stage.
+    pub max_storage_image_units: StageLimits,
+    /// Maximum number of storage image units.
+    pub max_combined_storage_image_units: u32,
+
+    /// Maximum number of samples for renderbuffers.
+    pub max_samples: u32,
+    /// Maximum number of samples for images with color formats.
+    pub max_color_samples: u32,
+    /// Maximum number of samples for images with depth or stencil formats.
+    pub max_depth_samples: u32,
+    /// Maximum number of samples for integer formats.
+    pub max_integer_samples: u32,
+
+    /// Maximum degree of anisotropy for sampling.
+    ///
+    /// `1.0` if anisotropic filtering is not supported.
+    pub max_sampler_anisotropy: f32,
+    /// Maximum number of vertices in a tessellation patch.
+    pub max_patch_vertices: u32,
+    /// Maximum number of transform feedback buffers.
+    pub max_transform_feedback_buffers: u32,
+}
+
+/// Limits for each shader stage.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageLimits {
+    pub vertex: u32,
+    pub tessellation_control: u32,
+    pub tessellation_evaluation: u32,
+    pub geometry: u32,
+    pub fragment: u32,
+    pub compute: u32,
+}
+
+/// API version number.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Version {
+    /// Parse the leading `<major>.<minor>` of a version string.
+    ///
+    /// Vendor specific information following the version number is ignored
+    /// (e.g `4.60 NVIDIA`, `4.50 - Build 27.20`).
+    fn parse(version: &str) -> Option<Version> {
+        let number = version.split_whitespace().next()?;
+        let mut parts = number.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts
+            .next()
+            .map(|minor| minor.trim_end_matches(|c: char| !c.is_ascii_digit()))
+            .unwrap_or("0")
+            .parse()
+            .ok()?;
+        Some(Version { major, minor })
+    }
 }
 
 /// Additional features supported by the device.
 ///
 /// These may correspond to OpenGL extensions or feature of higher OpenGL versions.
 #[derive(Clone, Debug)]
-pub struct DeviceFeatures {}
+pub struct DeviceFeatures {
+    /// GL version of the context.
+    pub version: Version,
+    /// Highest supported GLSL version.
+    ///
+    /// The minor version follows the GLSL convention (e.g. `4.60` has minor version `60`).
+    pub shading_language_version: Version,
+    /// Name of the company responsible for the implementation.
+    pub vendor: String,
+    /// Name of the renderer, specific to a hardware platform.
+    pub renderer: String,
+    /// All supported extension names.
+    pub extensions: Vec<String>,
+
+    /// SPIR-V shaders (`ShaderSource::Spirv`).
+    ///
+    /// GL 4.6 or `GL_ARB_gl_spirv`.
+    pub spirv: bool,
+    /// Indirect draw calls with a draw count sourced from the parameter buffer (`bind_parameter_buffer`).
+    ///
+    /// GL 4.6 or `GL_ARB_indirect_parameters`.
+    pub draw_indirect_count: bool,
+    /// Mesh and task shaders (`ShaderStage::MeshNv`, `draw_mesh_tasks_nv`).
+    ///
+    /// `GL_NV_mesh_shader`.
+    pub mesh_shader_nv: bool,
+    /// Pipeline statistics queries (e.g. `QueryType::VertexShaderInvocations`).
+    ///
+    /// GL 4.6 or `GL_ARB_pipeline_statistics_query`.
+    pub pipeline_statistics_query: bool,
+    /// Anisotropic texture filtering.
+    ///
+    /// GL 4.6, `GL_ARB_texture_filter_anisotropic` or `GL_EXT_texture_filter_anisotropic`.
+    pub sampler_anisotropy: bool,
+    /// Clamping of the depth bias.
+    ///
+    /// GL 4.6, `GL_ARB_polygon_offset_clamp` or `GL_EXT_polygon_offset_clamp`.
+    pub polygon_offset_clamp: bool,
+}
+
+impl DeviceFeatures {
+    /// Check if an extension is supported (e.g `GL_ARB_bindless_texture`).
+    pub fn supports_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|ext| ext == name)
+    }
+}
@@ -0,0 +1,510 @@
+//! Shader diagnostics.
+//!
+//! Compile and link logs are free form text, which differs between drivers.
+//! The logs are parsed into structured diagnostics, supporting the following dialects:
+//!
+//! * NVIDIA: `0(12) : error C1008: undefined variable "foo"`
+//! * Mesa: `0:12(5): error: `foo' undeclared`
+//! * AMD: `ERROR: 0:12: 'foo' : undeclared identifier`
+//!
+//! The AMD summary line (`ERROR: 1 compilation errors.  No code generated.`) is reported
+//! with info severity.
+//!
+//! Lines not matching any dialect are kept as diagnostics without location.
+
+use crate::pipeline::ShaderStage;
+use std::fmt;
+
+/// Severity of a shader diagnostic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+    /// Additional information, like stage headers of link logs.
+    Info,
+    Warning,
+    Error,
+}
+
+/// Diagnostic message of a shader compile or pipeline link log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: DiagnosticSeverity,
+    /// Index of the shader source string.
+    pub source: Option<u32>,
+    /// Line number, starting at 1.
+    pub line: Option<u32>,
+    /// Column number, starting at 1.
+    ///
+    /// Only reported by Mesa.
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Parse an info log into diagnostics.
+    pub fn parse_log(log: &str) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        for line in log.lines() {
+            let text = line.trim();
+            if text.chars().all(|c| c == '-' || c == '\0') {
+                continue;
+            }
+
+            if let Some(diagnostic) = Self::parse_line(text) {
+                diagnostics.push(diagnostic);
+                continue;
+            }
+
+            // Indented lines continue the previous message.
+            match diagnostics.last_mut() {
+                Some(last) if line.starts_with(char::is_whitespace) => {
+                    last.message.push('\n');
+                    last.message.push_str(text);
+                }
+                _ => diagnostics.push(Diagnostic {
+                    severity: DiagnosticSeverity::Info,
+                    source: None,
+                    line: None,
+                    column: None,
+                    message: text.to_string(),
+                }),
+            }
+        }
+        diagnostics
+    }
+
+    fn parse_line(text: &str) -> Option<Diagnostic> {
+        Self::parse_amd(text)
+            .or_else(|| Self::parse_nvidia(text))
+            .or_else(|| Self::parse_mesa(text))
+    }
+
+    /// `ERROR: 0:12: message` or `ERROR: message`
+    fn parse_amd(text: &str) -> Option<Diagnostic> {
+        let (severity, rest) = if let Some(rest) = text.strip_prefix("ERROR:") {
+            (DiagnosticSeverity::Error, rest)
+        } else if let Some(rest) = text.strip_prefix("WARNING:") {
+            (DiagnosticSeverity::Warning, rest)
+        } else {
+            return None;
+        };
+        let rest = rest.trim_start();
+
+        let mut parts = rest.splitn(3, ':');
+        let location = match (parts.next(), parts.next(), parts.next()) {
+            (Some(source), Some(line), Some(message)) => match (number(source), number(line)) {
+                (Some(source), Some(line)) => Some((source, line, message)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        Some(match location {
+            Some((source, line, message)) => Diagnostic {
+                severity,
+                source: Some(source),
+                line: Some(line),
+                column: None,
+                message: strip_code(message.trim()).to_string(),
+            },
+            // The trailing error count isn't a diagnostic on its own.
+            None if is_summary(strip_code(rest)) => Diagnostic {
+                severity: DiagnosticSeverity::Info,
+                source: None,
+                line: None,
+                column: None,
+                message: rest.to_string(),
+            },
+            None => Diagnostic {
+                severity,
+                source: None,
+                line: None,
+                column: None,
+                message: rest.to_string(),
+            },
+        })
+    }
+
+    /// `0(12) : error C1008: message`
+    fn parse_nvidia(text: &str) -> Option<Diagnostic> {
+        let open = text.find('(')?;
+        let close = open + text[open..].find(')')?;
+        let source = number(&text[..open])?;
+        let line = number(&text[open + 1..close])?;
+        let rest = text[close + 1..].trim_start().strip_prefix(':')?;
+        let (severity, rest) = severity(rest.trim_start())?;
+
+        // Skip the error code (`C1008:`).
+        let message = match rest.find(':') {
+            Some(colon)
+                if rest[..colon]
+                    .trim()
+                    .starts_with(|c: char| c.is_ascii_uppercase()) =>
+            {
+                &rest[colon + 1..]
+            }
+            _ => rest,
+        };
+
+        Some(Diagnostic {
+            severity,
+            source: Some(source),
+            line: Some(line),
+            column: None,
+            message: message.trim_start_matches(':').trim().to_string(),
+        })
+    }
+
+    /// `0:12(5): error: message` or `error: message`
+    fn parse_mesa(text: &str) -> Option<Diagnostic> {
+        if let Some((severity, rest)) = severity(text) {
+            return Some(Diagnostic {
+                severity,
+                source: None,
+                line: None,
+                column: None,
+                message: rest.trim_start_matches(':').trim().to_string(),
+            });
+        }
+
+        let colon = text.find(':')?;
+        let open = colon + text[colon..].find('(')?;
+        let close = open + text[open..].find(')')?;
+        let source = number(&text[..colon])?;
+        let line = number(&text[colon + 1..open])?;
+        let column = number(&text[open + 1..close])?;
+        let rest = text[close + 1..].strip_prefix(':')?;
+        let (severity, message) = severity(rest.trim_start())?;
+
+        Some(Diagnostic {
+            severity,
+            source: Some(source),
+            line: Some(line),
+            column: Some(column.max(1)),
+            message: message.trim_start_matches(':').trim().to_string(),
+        })
+    }
+}
+
+fn number(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Split a lowercase severity prefix (`error`, `warning`, `info`) from the message.
+fn severity(text: &str) -> Option<(DiagnosticSeverity, &str)> {
+    [
+        ("error", DiagnosticSeverity::Error),
+        ("warning", DiagnosticSeverity::Warning),
+        ("info", DiagnosticSeverity::Info),
+    ]
+    .iter()
+    .find_map(|&(prefix, severity)| {
+        let rest = text.strip_prefix(prefix)?;
+        if rest.starts_with(':') || rest.starts_with(' ') {
+            Some((severity, rest))
+        } else {
+            None
+        }
+    })
+}
+
+/// AMD summary line (`1 compilation errors.  No code generated.`).
+fn is_summary(message: &str) -> bool {
+    match message.split_once(' ') {
+        Some((count, rest)) => number(count).is_some() && rest.starts_with("compilation "),
+        None => false,
+    }
+}
+
+/// Strip AMD error codes (`error(#143)`) from the message.
+fn strip_code(message: &str) -> &str {
+    let code = message
+        .strip_prefix("error(#")
+        .or_else(|| message.strip_prefix("warning(#"));
+    match code.and_then(|code| code.find(')').map(|close| &code[close + 1..])) {
+        Some(rest) => rest.trim_start(),
+        None => message,
+    }
+}
+
+/// Info log of a failed shader compilation or pipeline link.
+///
+/// `Display` renders the diagnostics similar to `rustc`, including the offending
+/// source line if the shader source is available.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderLog {
+    /// Unprocessed info log.
+    pub log: String,
+    /// Parsed diagnostics of the log.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Shader stage of compile logs.
+    pub stage: Option<ShaderStage>,
+    /// GLSL shader source of compile logs.
+    pub source: Option<String>,
+}
+
+impl ShaderLog {
+    pub(crate) fn new(log: String, stage: Option<ShaderStage>, source: Option<String>) -> Self {
+        ShaderLog {
+            diagnostics: Diagnostic::parse_log(&log),
+            log,
+            stage,
+            source,
+        }
+    }
+
+    /// Diagnostics with error severity.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+    }
+
+    fn source_line(&self, diagnostic: &Diagnostic) -> Option<&str> {
+        // Shaders are compiled from a single source string.
+        if diagnostic.source.unwrap_or(0) != 0 {
+            return None;
+        }
+        let line = diagnostic.line?.checked_sub(1)?;
+        self.source.as_ref()?.lines().nth(line as usize)
+    }
+}
+
+impl fmt::Display for ShaderLog {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(fmt)?;
+            }
+            let severity = match diagnostic.severity {
+                DiagnosticSeverity::Info => "info",
+                DiagnosticSeverity::Warning => "warning",
+                DiagnosticSeverity::Error => "error",
+            };
+            write!(fmt, "{}: {}", severity, diagnostic.message)?;
+
+            let line = match diagnostic.line {
+                Some(line) => line,
+                None => continue,
+            };
+            let gutter = " ".repeat(line.to_string().len());
+            write!(fmt, "\n{}--> ", gutter)?;
+            if let Some(stage) = self.stage {
+                write!(fmt, "{:?} shader ", stage)?;
+            }
+            write!(fmt, "{}:{}", diagnostic.source.unwrap_or(0), line)?;
+            if let Some(column) = diagnostic.column {
+                write!(fmt, ":{}", column)?;
+            }
+
+            if let Some(text) = self.source_line(diagnostic) {
+                let text = text.trim_end();
+                let chars = text.chars().count();
+                let indent = chars - text.trim_start().chars().count();
+                let (start, len) = match diagnostic.column {
+                    Some(column) => ((column as usize).saturating_sub(1).min(chars), 1),
+                    None => (indent, (chars - indent).max(1)),
+                };
+                // Keep tabs in the marker line to align with the source line.
+                let offset = text
+                    .chars()
+                    .take(start)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect::<String>();
+                write!(fmt, "\n{} |", gutter)?;
+                write!(fmt, "\n{} | {}", line, text)?;
+                write!(fmt, "\n{} | {}{}", gutter, offset, "^".repeat(len))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiagnosticSeverity::{Error, Info, Warning};
+
+    fn diagnostic(
+        severity: DiagnosticSeverity,
+        location: Option<(u32, u32, Option<u32>)>,
+        message: &str,
+    ) -> Diagnostic {
+        Diagnostic {
+            severity,
+            source: location.map(|(source, _, _)| source),
+            line: location.map(|(_, line, _)| line),
+            column: location.and_then(|(_, _, column)| column),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn nvidia() {
+        let log = "0(12) : error C1008: undefined variable \"foo\"\n\
+                   0(3) : warning C7022: unrecognized profile specifier \"core\"\n\
+                   0(20) : error C1115: unable to find compatible overloaded function \"texture(sampler2D, vec3)\"\n\
+                   \x20   candidates are: texture(sampler2D, vec2)\n";
+        assert_eq!(
+            Diagnostic::parse_log(log),
+            [
+                diagnostic(Error, Some((0, 12, None)), "undefined variable \"foo\""),
+                diagnostic(
+                    Warning,
+                    Some((0, 3, None)),
+                    "unrecognized profile specifier \"core\""
+                ),
+                diagnostic(
+                    Error,
+                    Some((0, 20, None)),
+                    "unable to find compatible overloaded function \"texture(sampler2D, vec3)\"\n\
+                     candidates are: texture(sampler2D, vec2)"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn nvidia_link() {
+        let log = "Vertex info\n\
+                   -----------\n\
+                   0(5) : error C5145: must write to gl_Position\n\
+                   \n\
+                   Fragment info\n\
+                   -------------\n\
+                   0(8) : warning C7050: \"color\" might be used before being initialized\n\0";
+        assert_eq!(
+            Diagnostic::parse_log(log),
+            [
+                diagnostic(Info, None, "Vertex info"),
+                diagnostic(Error, Some((0, 5, None)), "must write to gl_Position"),
+                diagnostic(Info, None, "Fragment info"),
+                diagnostic(
+                    Warning,
+                    Some((0, 8, None)),
+                    "\"color\" might be used before being initialized"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mesa() {
+        let log = "0:12(5): error: `foo' undeclared\n\
+                   0:3(10): warning: extension `GL_ARB_foo' unsupported in fragment shader\n\
+                   0:7(0): error: syntax error, unexpected '}'\n\
+                   error: linking with uncompiled/unspecialized shader\n";
+        assert_eq!(
+            Diagnostic::parse_log(log),
+            [
+                diagnostic(Error, Some((0, 12, Some(5))), "`foo' undeclared"),
+                diagnostic(
+                    Warning,
+                    Some((0, 3, Some(10))),
+                    "extension `GL_ARB_foo' unsupported in fragment shader"
+                ),
+                diagnostic(Error, Some((0, 7, Some(1))), "syntax error, unexpected '}'"),
+                diagnostic(Error, None, "linking with uncompiled/unspecialized shader"),
+            ]
+        );
+    }
+
+    #[test]
+    fn amd() {
+        let log = "Fragment shader failed to compile with the following errors:\n\
+                   ERROR: 0:12: error(#143) Undeclared identifier: foo\n\
+                   WARNING: 0:4: warning(#402) Implicit truncation of vector type\n\
+                   ERROR: 0:15: 'bar' : undeclared identifier\n\
+                   ERROR: error(#273) 2 compilation errors.  No code generated\n";
+        let diagnostics = Diagnostic::parse_log(log);
+        assert_eq!(
+            diagnostics,
+            [
+                diagnostic(
+                    Info,
+                    None,
+                    "Fragment shader failed to compile with the following errors:"
+                ),
+                diagnostic(Error, Some((0, 12, None)), "Undeclared identifier: foo"),
+                diagnostic(
+                    Warning,
+                    Some((0, 4, None)),
+                    "Implicit truncation of vector type"
+                ),
+                diagnostic(Error, Some((0, 15, None)), "'bar' : undeclared identifier"),
+                diagnostic(
+                    Info,
+                    None,
+                    "error(#273) 2 compilation errors.  No code generated"
+                ),
+            ]
+        );
+
+        let log = ShaderLog::new(log.to_string(), None, None);
+        assert_eq!(log.errors().count(), 2);
+    }
+
+    #[test]
+    fn amd_summary() {
+        assert_eq!(
+            Diagnostic::parse_log(
+                "ERROR: 0:1: 'x' : syntax error\nERROR: 1 compilation errors.  No code generated."
+            ),
+            [
+                diagnostic(Error, Some((0, 1, None)), "'x' : syntax error"),
+                diagnostic(Info, None, "1 compilation errors.  No code generated."),
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let source = "#version 450 core\n\
+                      void main() {\n\
+                      \tfoo = 1.0;\n\
+                      }\n";
+        let log = ShaderLog::new(
+            "0:3(2): error: `foo' undeclared\n\
+             0:3(7): warning: implicit conversion\n\
+             error: compilation failed\n"
+                .to_string(),
+            Some(ShaderStage::Fragment),
+            Some(source.to_string()),
+        );
+        assert_eq!(
+            log.to_string(),
+            "error: `foo' undeclared\n \
+             --> Fragment shader 0:3:2\n  \
+             |\n\
+             3 | \tfoo = 1.0;\n  \
+             | \t^\n\
+             warning: implicit conversion\n \
+             --> Fragment shader 0:3:7\n  \
+             |\n\
+             3 | \tfoo = 1.0;\n  \
+             | \t     ^\n\
+             error: compilation failed"
+        );
+    }
+
+    #[test]
+    fn display_without_column() {
+        let source = (1..=9).map(|_| "\n").collect::<String>() + "\t\tcolor = vec4(foo);\n";
+        let log = ShaderLog::new(
+            "0(10) : error C1008: undefined variable \"foo\"\n".to_string(),
+            None,
+            Some(source),
+        );
+        assert_eq!(
+            log.to_string(),
+            "error: undefined variable \"foo\"\n  \
+             --> 0:10\n   \
+             |\n\
+             10 | \t\tcolor = vec4(foo);\n   \
+             | \t\t^^^^^^^^^^^^^^^^^^"
+        );
+    }
+}
@@ -2,9 +2,11 @@
 
 use crate::__gl;
 
-use crate::device::Device;
+use crate::device::{Device, DeviceStatus};
+use crate::diagnostic::ShaderLog;
 use crate::pipeline::{Pipeline, Shader};
-use std::{error, fmt, result};
+use std::panic::Location;
+use std::{error, fmt, result, thread};
 
 /// Error return codes
 ///
@@ -14,26 +16,155 @@ use std::{error, fmt, result};
 /// or indicate driver or implementation issues.
 ///
 /// API validation is provided by the debug functionality on device creation.
+/// Devices created with `DeviceDesc::error_checks` additionally report API misuse as `Error::Api`.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
     OutOfMemory,
 
-    /// Shader compilation failure.
-    CompileError(Shader),
+    /// Shader compilation failure, with the parsed compile log.
+    CompileError(Shader, ShaderLog),
 
-    /// Link pipeline failure.
-    LinkError(Pipeline),
+    /// Link pipeline failure, with the parsed link log.
+    LinkError(Pipeline, ShaderLog),
+
+    /// Creating a device owned context failed.
+    ContextCreation(String),
+
+    /// Reading or writing a capture stream failed.
+    Capture(String),
+
+    /// Arguments violate the valid usage rules of a call (`grr::safe`).
+    InvalidUsage(String),
+
+    /// The context has been lost due to a device reset.
+    ///
+    /// All objects of the device are invalid, the device needs to be recreated.
+    /// See [`Device::status`](struct.Device.html#method.status) for the cause of the reset.
+    DeviceLost,
+
+    /// API error raised by a call (`DeviceDesc::error_checks`).
+    Api {
+        code: ApiError,
+        /// Device function raising the error.
+        function: &'static str,
+        /// Call site of the function.
+        location: &'static Location<'static>,
+    },
+}
+
+/// API error codes reported by the context.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    InvalidEnum = __gl::INVALID_ENUM,
+    InvalidValue = __gl::INVALID_VALUE,
+    InvalidOperation = __gl::INVALID_OPERATION,
+    InvalidFramebufferOperation = __gl::INVALID_FRAMEBUFFER_OPERATION,
+    StackOverflow = __gl::STACK_OVERFLOW,
+    StackUnderflow = __gl::STACK_UNDERFLOW,
+}
+
+impl ApiError {
+    fn from_raw(code: __gl::types::GLenum) -> Option<Self> {
+        match code {
+            __gl::INVALID_ENUM => Some(ApiError::InvalidEnum),
+            __gl::INVALID_VALUE => Some(ApiError::InvalidValue),
+            __gl::INVALID_OPERATION => Some(ApiError::InvalidOperation),
+            __gl::INVALID_FRAMEBUFFER_OPERATION => Some(ApiError::InvalidFramebufferOperation),
+            __gl::STACK_OVERFLOW => Some(ApiError::StackOverflow),
+            __gl::STACK_UNDERFLOW => Some(ApiError::StackUnderflow),
+            _ => None,
+        }
+    }
+}
+
+/// Outermost device call in flight, for attributing API errors.
+#[derive(Copy, Clone)]
+pub(crate) struct ApiCall {
+    function: &'static str,
+    location: &'static Location<'static>,
+}
+
+/// Checks for API errors at the end of a device call (`DeviceDesc::error_checks`).
+///
+/// Also resumes panics of the debug callback raised during the call.
+pub(crate) struct ApiScope<'a> {
+    device: &'a Device,
+    /// Outermost call with enabled error checks.
+    checks: bool,
+}
+
+impl Drop for ApiScope<'_> {
+    fn drop(&mut self) {
+        let result = if self.checks {
+            let result = unsafe { self.device.get_error() };
+            self.device.1.api_call.set(None);
+            result
+        } else {
+            Ok(())
+        };
+
+        if thread::panicking() {
+            return;
+        }
+        self.device.resume_debug_panic();
+        if let Err(err @ Error::Api { .. }) = result {
+            panic!("{}", err);
+        }
+    }
 }
 
 /// A specialized Result type for `grr` operations.
 pub type Result<T> = result::Result<T, Error>;
 
 impl Device {
+    /// Clear all error flags of the context, reporting the first one.
     pub(crate) unsafe fn get_error(&self) -> Result<()> {
         let err = self.0.GetError();
+        // Each error code has its own flag, drain the remaining ones.
+        // Lost contexts keep reporting `CONTEXT_LOST`.
+        if err != __gl::NO_ERROR {
+            loop {
+                match self.0.GetError() {
+                    __gl::NO_ERROR | __gl::CONTEXT_LOST => break,
+                    _ => {}
+                }
+            }
+        }
         match err {
             __gl::OUT_OF_MEMORY => Err(Error::OutOfMemory),
-            _ => Ok(()),
+            __gl::CONTEXT_LOST => Err(Error::DeviceLost),
+            _ if self.status() != DeviceStatus::Ok => Err(Error::DeviceLost),
+            _ => match (ApiError::from_raw(err), self.1.api_call.get()) {
+                (Some(code), Some(call)) => Err(Error::Api {
+                    code,
+                    function: call.function,
+                    location: call.location,
+                }),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Enter a device call, checking for API errors when the returned scope is dropped.
+    ///
+    /// API errors are returned by `get_error` inside of the scope, remaining errors
+    /// panic at the end of the outermost call. Nested calls are attributed to the outermost one.
+    #[track_caller]
+    pub(crate) fn api_scope(&self, function: &'static str) -> ApiScope<'_> {
+        if !self.1.error_checks || self.1.api_call.get().is_some() {
+            return ApiScope {
+                device: self,
+                checks: false,
+            };
+        }
+        self.1.api_call.set(Some(ApiCall {
+            function,
+            location: Location::caller(),
+        }));
+        ApiScope {
+            device: self,
+            checks: true,
         }
     }
 }
@@ -44,8 +175,17 @@ impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
         match *self {
             Error::OutOfMemory => write!(fmt, "OutOfMemory"),
-            Error::CompileError(_) => write!(fmt, "CompileError"),
-            Error::LinkError(_) => write!(fmt, "LinkError"),
+            Error::CompileError(_, ref log) => write!(fmt, "CompileError:\n{}", log),
+            Error::LinkError(_, ref log) => write!(fmt, "LinkError:\n{}", log),
+            Error::ContextCreation(ref msg) => write!(fmt, "ContextCreation: {}", msg),
+            Error::Capture(ref msg) => write!(fmt, "Capture: {}", msg),
+            Error::InvalidUsage(ref msg) => write!(fmt, "InvalidUsage: {}", msg),
+            Error::DeviceLost => write!(fmt, "DeviceLost"),
+            Error::Api {
+                code,
+                function,
+                location,
+            } => write!(fmt, "Api: {:?} in `{}` at {}", code, function, location),
         }
     }
 }
@@ -41,6 +41,7 @@ use crate::__gl;
 ///
 /// Each component is followed by the number of bits used to represent
 /// it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Format {
     // unsigned normalized integer formats
     R8_UNORM = __gl::R8,
@@ -131,6 +132,37 @@ impl Format {
         self.base_format().num_components()
     }
 
+    /// Return the size of a single texel in bytes.
+    ///
+    /// Implementations may use a different internal representation,
+    /// e.g. padding three component formats or `D24_UNORM`.
+    pub fn texel_size(self) -> u32 {
+        use Format::*;
+        match self {
+            D16_UNORM => 2,
+            D24_UNORM | D32_UNORM | D32_SFLOAT | D24_UNORM_S8_UINT => 4,
+            D32_SFLOAT_S8_UINT => 8,
+            S8_UINT => 1,
+
+            R8_UNORM | R8G8_UNORM | R8G8B8_UNORM | R8G8B8A8_UNORM | R8_SNORM | R8G8_SNORM
+            | R8G8B8_SNORM | R8G8B8A8_SNORM | R8_SINT | R8G8_SINT | R8G8B8_SINT | R8G8B8A8_SINT
+            | R8_UINT | R8G8_UINT | R8G8B8_UINT | R8G8B8A8_UINT | R8G8B8_SRGB | R8G8B8A8_SRGB => {
+                self.num_components()
+            }
+
+            R16_UNORM | R16G16_UNORM | R16G16B16_UNORM | R16G16B16A16_UNORM | R16_SNORM
+            | R16G16_SNORM | R16G16B16_SNORM | R16G16B16A16_SNORM | R16_SFLOAT | R16G16_SFLOAT
+            | R16G16B16_SFLOAT | R16G16B16A16_SFLOAT | R16_SINT | R16G16_SINT | R16G16B16_SINT
+            | R16G16B16A16_SINT | R16_UINT | R16G16_UINT | R16G16B16_UINT | R16G16B16A16_UINT => {
+                2 * self.num_components()
+            }
+
+            R32_SFLOAT | R32G32_SFLOAT | R32G32B32_SFLOAT | R32G32B32A32_SFLOAT | R32_SINT
+            | R32G32_SINT | R32G32B32_SINT | R32G32B32A32_SINT | R32_UINT | R32G32_UINT
+            | R32G32B32_UINT | R32G32B32A32_UINT => 4 * self.num_components(),
+        }
+    }
+
     /// Return the corresponding base format for this format.
     pub fn base_format(self) -> BaseFormat {
         use Format::*;
@@ -163,6 +195,7 @@ impl Format {
 #[allow(non_camel_case_types)]
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BaseFormat {
     R = __gl::RED,
     RG = __gl::RG,
@@ -192,6 +225,7 @@ impl BaseFormat {
 #[allow(non_camel_case_types)]
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FormatLayout {
     U8 = __gl::UNSIGNED_BYTE,
     U16 = __gl::UNSIGNED_SHORT,
@@ -0,0 +1,304 @@
+//! Frames in flight
+
+use crate::buffer::{Buffer, BufferRange, MappingFlags, MemoryFlags};
+use crate::device::Device;
+use crate::error::{Error, Result};
+use crate::sync::{Fence, FenceStatus};
+
+use std::slice;
+use std::time::{Duration, Instant};
+
+/// Host stall statistics of a frame ring.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FrameRingStats {
+    /// Number of started frames.
+    pub frames: u64,
+    /// Number of `begin_frame` calls which had to wait for a slice.
+    pub stalls: u64,
+    /// Accumulated time the host was blocked waiting for slices.
+    pub stall_time: Duration,
+    /// Longest single wait for a slice.
+    pub max_stall: Duration,
+}
+
+/// Ring of persistently mapped buffer slices for streaming per-frame data.
+///
+/// The buffer is split into `frames` slices of equal size. Each frame writes into
+/// the current slice between `begin_frame` and `end_frame`. The slice is fenced at
+/// the end of the frame and can only be reused once the device finished all commands
+/// submitted in that frame.
+///
+/// ```no_run
+/// # unsafe fn frame(grr: &grr::Device) -> grr::Result<()> {
+/// let mut ring = grr.create_frame_ring(64 << 10, 3, Some("per frame"))?;
+/// loop {
+///     grr.begin_frame(&mut ring, u64::MAX)?;
+///     ring.data()[..4].copy_from_slice(&1.0f32.to_ne_bytes());
+///     grr.bind_uniform_buffers(0, &[ring.range()]);
+///     // ..
+///     grr.end_frame(&mut ring);
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FrameRing {
+    buffer: Buffer,
+    ptr: *mut u8,
+    frame_size: u64,
+    fences: Vec<Option<Fence>>,
+    frame: usize,
+    in_frame: bool,
+    stats: FrameRingStats,
+}
+
+// Mapped memory and sync objects are not tied to a thread.
+unsafe impl Send for FrameRing {}
+
+impl FrameRing {
+    /// Underlying buffer of all slices.
+    pub fn buffer(&self) -> Buffer {
+        self.buffer
+    }
+
+    /// Number of slices.
+    pub fn frames(&self) -> u32 {
+        self.fences.len() as _
+    }
+
+    /// Size in bytes of a single slice.
+    ///
+    /// Rounded up to the buffer offset alignment of the device.
+    pub fn frame_size(&self) -> u64 {
+        self.frame_size
+    }
+
+    /// Index of the current slice.
+    pub fn frame_index(&self) -> u32 {
+        self.frame as _
+    }
+
+    /// Buffer range of the current slice.
+    pub fn range(&self) -> BufferRange {
+        BufferRange {
+            buffer: self.buffer,
+            offset: self.frame as u64 * self.frame_size,
+            size: self.frame_size,
+        }
+    }
+
+    /// Mapped memory of the current slice.
+    ///
+    /// # Valid usage
+    ///
+    /// - Must only be written between `begin_frame` and `end_frame`.
+    pub unsafe fn data(&mut self) -> &mut [u8] {
+        let offset = self.frame * self.frame_size as usize;
+        slice::from_raw_parts_mut(self.ptr.add(offset), self.frame_size as _)
+    }
+
+    /// Host stall statistics.
+    pub fn stats(&self) -> FrameRingStats {
+        self.stats
+    }
+
+    /// Reset the stall statistics.
+    pub fn reset_stats(&mut self) {
+        self.stats = FrameRingStats::default();
+    }
+}
+
+impl Device {
+    /// Create a ring of `frames` persistently mapped buffer slices.
+    ///
+    /// # Parameters
+    ///
+    /// - `frame_size`: Size in bytes of a slice, rounded up to the buffer offset alignment.
+    /// - `frames`: Number of slices (frames in flight).
+    /// - `label`: Optional debug name of the buffer (see `object_name`).
+    ///
+    /// Returns `Error::InvalidUsage` if `frames` is `0`.
+    #[track_caller]
+    pub unsafe fn create_frame_ring(
+        &self,
+        frame_size: u64,
+        frames: u32,
+        label: Option<&str>,
+    ) -> Result<FrameRing> {
+        let _scope = self.api_scope("create_frame_ring");
+        if frames == 0 {
+            return Err(Error::InvalidUsage(
+                "frame ring requires at least one frame".into(),
+            ));
+        }
+        let limits = self.limits();
+        let alignment = limits
+            .uniform_buffer_offset_alignment
+            .max(limits.storage_buffer_offset_alignment)
+            .max(1) as u64;
+        let frame_size = (frame_size + alignment - 1) / alignment * alignment;
+        let size = frame_size * frames as u64;
+
+        let buffer = self.create_buffer(
+            size,
+            MemoryFlags::CPU_MAP_WRITE | MemoryFlags::COHERENT,
+            label,
+        )?;
+        let ptr = self
+            .map_buffer::<u8>(buffer, 0..size, MappingFlags::empty())
+            .as_mut_ptr();
+
+        Ok(FrameRing {
+            buffer,
+            ptr,
+            frame_size,
+            fences: vec![None; frames as usize],
+            frame: 0,
+            in_frame: false,
+            stats: FrameRingStats::default(),
+        })
+    }
+
+    /// Delete a frame ring.
+    ///
+    /// Pending frames don't need to be completed.
+    #[track_caller]
+    pub unsafe fn delete_frame_ring(&self, ring: FrameRing) {
+        let _scope = self.api_scope("delete_frame_ring");
+        for fence in ring.fences.into_iter().flatten() {
+            self.delete_fence(fence);
+        }
+        self.unmap_buffer(ring.buffer);
+        self.delete_buffer(ring.buffer);
+    }
+
+    /// Begin a new frame, waiting until the current slice is no longer used by the device.
+    ///
+    /// # Parameters
+    ///
+    /// - `timeout`: Timeout in nanoseconds. `0` only polls, `u64::MAX` blocks until available.
+    ///
+    /// # Return
+    ///
+    /// Returns if the slice is available. The frame is only started on success,
+    /// otherwise `begin_frame` needs to be called again.
+    ///
+    /// # Valid usage
+    ///
+    /// - The previous frame must have been ended with `end_frame`.
+    #[track_caller]
+    pub unsafe fn begin_frame(&self, ring: &mut FrameRing, timeout: u64) -> Result<bool> {
+        let _scope = self.api_scope("begin_frame");
+        debug_assert!(!ring.in_frame, "frame already started");
+
+        if let Some(fence) = ring.fences[ring.frame] {
+            let start = Instant::now();
+            let status = self.wait_fence(fence, timeout)?;
+            // Polling doesn't block the host.
+            if status != FenceStatus::AlreadySignaled && timeout > 0 {
+                let stall = start.elapsed();
+                ring.stats.stalls += 1;
+                ring.stats.stall_time += stall;
+                ring.stats.max_stall = ring.stats.max_stall.max(stall);
+            }
+            if status == FenceStatus::TimeoutExpired {
+                return Ok(false);
+            }
+
+            self.delete_fence(fence);
+            ring.fences[ring.frame] = None;
+        }
+
+        ring.in_frame = true;
+        ring.stats.frames += 1;
+        Ok(true)
+    }
+
+    /// End the current frame, fencing its slice and advancing to the next one.
+    ///
+    /// During an active capture, the contents of the slice are recorded.
+    ///
+    /// # Valid usage
+    ///
+    /// - The frame must have been started with `begin_frame`.
+    #[track_caller]
+    pub unsafe fn end_frame(&self, ring: &mut FrameRing) {
+        let _scope = self.api_scope("end_frame");
+        debug_assert!(ring.in_frame, "frame not started");
+
+        #[cfg(feature = "capture")]
+        {
+            let range = ring.range();
+            self.capture_flush(range.buffer, range.offset..range.offset + range.size);
+        }
+        ring.fences[ring.frame] = Some(self.create_fence());
+        ring.frame = (ring.frame + 1) % ring.fences.len();
+        ring.in_frame = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::__gl;
+    use crate::backend::tests::device;
+    use crate::*;
+
+    #[test]
+    fn rotation() {
+        let (grr, _recording) = device();
+        unsafe {
+            assert!(matches!(
+                grr.create_frame_ring(256, 0, None),
+                Err(Error::InvalidUsage(_))
+            ));
+
+            let mut ring = grr.create_frame_ring(100, 3, None).unwrap();
+            assert_eq!(ring.frames(), 3);
+            assert_eq!(ring.frame_size(), 100);
+            for frame in 0..5 {
+                assert!(grr.begin_frame(&mut ring, u64::MAX).unwrap());
+                assert_eq!(ring.frame_index(), frame % 3);
+                assert_eq!(ring.range().offset, (frame % 3) as u64 * 100);
+                ring.data()[0] = frame as u8;
+                grr.end_frame(&mut ring);
+            }
+            assert_eq!(
+                ring.stats(),
+                FrameRingStats {
+                    frames: 5,
+                    ..Default::default()
+                }
+            );
+            grr.delete_frame_ring(ring);
+        }
+    }
+
+    #[test]
+    fn stalls() {
+        let (grr, recording) = device();
+        unsafe {
+            let mut ring = grr.create_frame_ring(64, 2, None).unwrap();
+            for _ in 0..2 {
+                assert!(grr.begin_frame(&mut ring, u64::MAX).unwrap());
+                grr.end_frame(&mut ring);
+            }
+
+            // The slice of the first frame is still in use by the device.
+            recording.set_wait_status(__gl::CONDITION_SATISFIED);
+            assert!(grr.begin_frame(&mut ring, u64::MAX).unwrap());
+            grr.end_frame(&mut ring);
+            assert_eq!(ring.stats().stalls, 1);
+
+            recording.set_wait_status(__gl::TIMEOUT_EXPIRED);
+            // Polling doesn't count as stall.
+            assert!(!grr.begin_frame(&mut ring, 0).unwrap());
+            assert_eq!(ring.stats().stalls, 1);
+            assert!(!grr.begin_frame(&mut ring, 1000).unwrap());
+
+            let stats = ring.stats();
+            assert_eq!(stats.frames, 3);
+            assert_eq!(stats.stalls, 2);
+            assert!(stats.max_stall <= stats.stall_time);
+            grr.delete_frame_ring(ring);
+        }
+    }
+}
@@ -2,13 +2,19 @@
 
 use crate::__gl;
 use crate::__gl::types::{GLenum, GLuint};
+use crate::cache::StateKey;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
 use crate::debug::{Object, ObjectType};
 use crate::device::Device;
 use crate::error::Result;
+use crate::handle::{Generation, Handle};
 use crate::{Format, ImageView, Region};
 
 /// Attachment clearing description.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClearAttachment {
     ColorInt(usize, [i32; 4]),
     ColorUint(usize, [u32; 4]),
@@ -20,6 +26,7 @@ pub enum ClearAttachment {
 
 /// Attachment reference.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Attachment {
     Color(usize),
     Depth,
@@ -39,22 +46,28 @@ impl Attachment {
 }
 
 ///
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttachmentView {
     Image(ImageView),
     Renderbuffer(Renderbuffer),
 }
 
 /// Framebuffer handle.
-#[repr(transparent)]
-#[derive(Clone, Copy)]
-pub struct Framebuffer(pub(crate) GLuint);
+#[cfg_attr(not(debug_assertions), repr(transparent))]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Framebuffer(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Framebuffer {
     /// Default framebuffer handle.
     ///
     /// Thie is the base framebuffer associated with the context.
     /// It also represents the internal swapchain for presentation.
-    pub const DEFAULT: Self = Framebuffer(0);
+    pub const DEFAULT: Self = Framebuffer(0, Generation::UNTRACKED);
 }
 
 impl Object for Framebuffer {
@@ -64,10 +77,20 @@ impl Object for Framebuffer {
     }
 }
 
+impl Handle for Framebuffer {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 /// Renderbuffer handle.
-#[repr(transparent)]
-#[derive(Clone, Copy)]
-pub struct Renderbuffer(GLuint);
+#[cfg_attr(not(debug_assertions), repr(transparent))]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Renderbuffer(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for Renderbuffer {
     const TYPE: ObjectType = ObjectType::Renderbuffer;
@@ -76,37 +99,68 @@ impl Object for Renderbuffer {
     }
 }
 
+impl Handle for Renderbuffer {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 impl Device {
     /// Create a new framebuffer.
-    pub unsafe fn create_framebuffer(&self) -> Result<Framebuffer> {
+    #[track_caller]
+    pub unsafe fn create_framebuffer(&self, label: Option<&str>) -> Result<Framebuffer> {
+        let _scope = self.api_scope("create_framebuffer");
         let mut framebuffer = 0;
         self.0.CreateFramebuffers(1, &mut framebuffer);
         self.get_error()?;
 
-        Ok(Framebuffer(framebuffer))
+        let framebuffer = Framebuffer(
+            framebuffer,
+            self.create_handle(ObjectType::Framebuffer, framebuffer),
+        );
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Framebuffer, framebuffer.0, 0);
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateFramebuffer { framebuffer });
+        self.label_object(framebuffer, label);
+        Ok(framebuffer)
     }
 
     /// Delete a framebuffer.
+    #[track_caller]
     pub unsafe fn delete_framebuffer(&self, framebuffer: Framebuffer) {
+        let _scope = self.api_scope("delete_framebuffer");
         self.delete_framebuffers(&[framebuffer])
     }
 
     /// Delete multiple framebuffers.
+    #[track_caller]
     pub unsafe fn delete_framebuffers(&self, framebuffers: &[Framebuffer]) {
-        self.0.DeleteFramebuffers(
-            framebuffers.len() as _,
-            framebuffers.as_ptr() as *const _, // newtype
-        );
+        let _scope = self.api_scope("delete_framebuffers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteFramebuffers {
+            framebuffers: framebuffers.to_vec(),
+        });
+        self.delete_handles("delete_framebuffers", framebuffers);
+        // Deleting a bound framebuffer resets the binding.
+        self.forget_state(StateKey::Framebuffer(__gl::DRAW_FRAMEBUFFER));
+        self.forget_state(StateKey::Framebuffer(__gl::READ_FRAMEBUFFER));
+        let framebuffers = framebuffers.iter().map(|fb| fb.0).collect::<Vec<_>>();
+        self.0
+            .DeleteFramebuffers(framebuffers.len() as _, framebuffers.as_ptr());
     }
 
     /// Create a new framebuffer.
+    #[track_caller]
     pub unsafe fn create_renderbuffer(
         &self,
         format: Format,
         width: u32,
         height: u32,
         samples: u32,
+        label: Option<&str>,
     ) -> Result<Renderbuffer> {
+        let _scope = self.api_scope("create_renderbuffer");
         let mut renderbuffer = 0;
         self.0.CreateRenderbuffers(1, &mut renderbuffer);
         self.get_error()?;
@@ -124,24 +178,59 @@ impl Device {
                 .NamedRenderbufferStorage(renderbuffer, format as _, width as _, height as _);
         }
 
-        Ok(Renderbuffer(renderbuffer))
+        let renderbuffer = Renderbuffer(
+            renderbuffer,
+            self.create_handle(ObjectType::Renderbuffer, renderbuffer),
+        );
+        #[cfg(feature = "tracking")]
+        self.track_resource(
+            ObjectType::Renderbuffer,
+            renderbuffer.0,
+            format.texel_size() as u64 * width as u64 * height as u64 * samples.max(1) as u64,
+        );
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateRenderbuffer {
+            format,
+            width,
+            height,
+            samples,
+            renderbuffer,
+        });
+        self.label_object(renderbuffer, label);
+        Ok(renderbuffer)
     }
 
     /// Delete a renderbuffer.
+    #[track_caller]
     pub unsafe fn delete_renderbuffer(&self, renderbuffer: Renderbuffer) {
+        let _scope = self.api_scope("delete_renderbuffer");
         self.delete_renderbuffers(&[renderbuffer])
     }
 
     /// Delete multiple renderbuffers.
+    #[track_caller]
     pub unsafe fn delete_renderbuffers(&self, renderbuffers: &[Renderbuffer]) {
-        self.0.DeleteRenderbuffers(
-            renderbuffers.len() as _,
-            renderbuffers.as_ptr() as *const _, // newtype
-        );
+        let _scope = self.api_scope("delete_renderbuffers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteRenderbuffers {
+            renderbuffers: renderbuffers.to_vec(),
+        });
+        self.delete_handles("delete_renderbuffers", renderbuffers);
+        let renderbuffers = renderbuffers.iter().map(|rb| rb.0).collect::<Vec<_>>();
+        self.0
+            .DeleteRenderbuffers(renderbuffers.len() as _, renderbuffers.as_ptr());
     }
 
     /// Clear framebuffer attachment.
+    #[track_caller]
     pub unsafe fn clear_attachment(&self, fb: Framebuffer, cv: ClearAttachment) {
+        let _scope = self.api_scope("clear_attachment");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::ClearAttachment {
+            framebuffer: fb,
+            clear: cv,
+        });
+        self.check_handle("clear_attachment", fb);
         match cv {
             ClearAttachment::ColorInt(id, color) => {
                 self.0
@@ -171,12 +260,21 @@ impl Device {
     }
 
     ///
+    #[track_caller]
     pub unsafe fn invalidate_attachments(
         &self,
         framebuffer: Framebuffer,
         attachments: &[Attachment],
         region: Region,
     ) {
+        let _scope = self.api_scope("invalidate_attachments");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::InvalidateAttachments {
+            framebuffer,
+            attachments: attachments.to_vec(),
+            region,
+        });
+        self.check_handle("invalidate_attachments", framebuffer);
         let attachments = attachments
             .iter()
             .map(|att| att.target())
@@ -193,33 +291,102 @@ impl Device {
         )
     }
 
+    /// Enable or disable linear to sRGB conversion on framebuffer writes.
+    ///
+    /// Only affects attachments with an sRGB format.
+    #[track_caller]
+    pub unsafe fn set_framebuffer_srgb(&self, enable: bool) {
+        let _scope = self.api_scope("set_framebuffer_srgb");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetFramebufferSrgb { enable });
+        self.set_capability(__gl::FRAMEBUFFER_SRGB, enable);
+    }
+
     /// Bind a framebuffer for draw and read commands.
     ///
     /// This will overwrite both (draw and read) binding points.
+    #[track_caller]
     pub unsafe fn bind_framebuffer(&self, framebuffer: Framebuffer) {
-        self.0.BindFramebuffer(__gl::FRAMEBUFFER, framebuffer.0);
+        let _scope = self.api_scope("bind_framebuffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindFramebuffer { framebuffer });
+        self.check_handle("bind_framebuffer", framebuffer);
+        let draw = self.state_changed(
+            StateKey::Framebuffer(__gl::DRAW_FRAMEBUFFER),
+            &[framebuffer.0],
+        );
+        let read = self.state_changed(
+            StateKey::Framebuffer(__gl::READ_FRAMEBUFFER),
+            &[framebuffer.0],
+        );
+        match (draw, read) {
+            (true, true) => self.0.BindFramebuffer(__gl::FRAMEBUFFER, framebuffer.0),
+            (true, false) => self
+                .0
+                .BindFramebuffer(__gl::DRAW_FRAMEBUFFER, framebuffer.0),
+            (false, true) => self
+                .0
+                .BindFramebuffer(__gl::READ_FRAMEBUFFER, framebuffer.0),
+            (false, false) => {}
+        }
     }
 
     /// Bind a framebuffer for draw commands.
+    #[track_caller]
     pub unsafe fn bind_draw_framebuffer(&self, framebuffer: Framebuffer) {
-        self.0
-            .BindFramebuffer(__gl::DRAW_FRAMEBUFFER, framebuffer.0);
+        let _scope = self.api_scope("bind_draw_framebuffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindDrawFramebuffer { framebuffer });
+        self.check_handle("bind_draw_framebuffer", framebuffer);
+        if self.state_changed(
+            StateKey::Framebuffer(__gl::DRAW_FRAMEBUFFER),
+            &[framebuffer.0],
+        ) {
+            self.0
+                .BindFramebuffer(__gl::DRAW_FRAMEBUFFER, framebuffer.0);
+        }
     }
 
     /// Bind a framebuffer for read commands.
+    #[track_caller]
     pub unsafe fn bind_read_framebuffer(&self, framebuffer: Framebuffer) {
-        self.0
-            .BindFramebuffer(__gl::READ_FRAMEBUFFER, framebuffer.0);
+        let _scope = self.api_scope("bind_read_framebuffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindReadFramebuffer { framebuffer });
+        self.check_handle("bind_read_framebuffer", framebuffer);
+        if self.state_changed(
+            StateKey::Framebuffer(__gl::READ_FRAMEBUFFER),
+            &[framebuffer.0],
+        ) {
+            self.0
+                .BindFramebuffer(__gl::READ_FRAMEBUFFER, framebuffer.0);
+        }
     }
 
     /// Bind attachments to the framebuffer.
     ///
     /// All previously bound attachments become invalid.
+    #[track_caller]
     pub unsafe fn bind_attachments(
         &self,
         framebuffer: Framebuffer,
         attachments: &[(Attachment, AttachmentView)],
     ) {
+        let _scope = self.api_scope("bind_attachments");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindAttachments {
+            framebuffer,
+            attachments: attachments.to_vec(),
+        });
+        self.check_handle("bind_attachments", framebuffer);
+        for (_, view) in attachments {
+            match *view {
+                AttachmentView::Image(image) => self.check_handle("bind_attachments", image),
+                AttachmentView::Renderbuffer(renderbuffer) => {
+                    self.check_handle("bind_attachments", renderbuffer)
+                }
+            }
+        }
         assert_ne!(
             framebuffer.0, 0,
             "The default framebuffer can't be changed."
@@ -249,7 +416,15 @@ impl Device {
     /// Defines the color render targets for the next draw calls.
     /// This builds the link between fragment outputs in the fragment shader
     /// and attachments bound on the framebuffer.
+    #[track_caller]
     pub unsafe fn set_color_attachments(&self, framebuffer: Framebuffer, attachments: &[u32]) {
+        let _scope = self.api_scope("set_color_attachments");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetColorAttachments {
+            framebuffer,
+            attachments: attachments.to_vec(),
+        });
+        self.check_handle("set_color_attachments", framebuffer);
         assert_ne!(
             framebuffer.0, 0,
             "The default framebuffer can't be changed."
@@ -267,7 +442,15 @@ impl Device {
     }
 
     /// Specify read attachment.
+    #[track_caller]
     pub unsafe fn set_read_attachment(&self, framebuffer: Framebuffer, attachment: u32) {
+        let _scope = self.api_scope("set_read_attachment");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::SetReadAttachment {
+            framebuffer,
+            attachment,
+        });
+        self.check_handle("set_read_attachment", framebuffer);
         assert_ne!(
             framebuffer.0, 0,
             "The default framebuffer can't be changed."
@@ -0,0 +1,340 @@
+//! Device groups.
+//!
+//! Contexts of a share group share buffers, images, samplers, shaders, pipelines,
+//! renderbuffers and fences. Container objects (vertex arrays, framebuffers and queries)
+//! are only valid on the context which created them.
+//!
+//! Devices created from a `DeviceGroup` share the handle, tracking and validation
+//! registries of shared objects. In debug builds, handles of container objects are stamped with
+//! the owning device and using a container object on another device of the group panics.
+
+#[cfg(debug_assertions)]
+use crate::__gl::types::GLuint;
+use crate::debug::Object;
+#[cfg(any(debug_assertions, feature = "tracking"))]
+use crate::debug::ObjectType;
+use crate::device::{Device, DeviceDesc};
+#[cfg(feature = "headless")]
+use crate::error::Result;
+#[cfg(debug_assertions)]
+use crate::handle;
+#[cfg(feature = "headless")]
+use crate::headless;
+use crate::sync::Fence;
+#[cfg(feature = "tracking")]
+use crate::tracking;
+#[cfg(feature = "validation")]
+use crate::validation;
+use crate::{Buffer, Image, Pipeline, Renderbuffer, Sampler, Shader, TypedBuffer};
+
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+#[cfg(any(
+    debug_assertions,
+    feature = "tracking",
+    feature = "validation",
+    feature = "headless"
+))]
+use std::sync::Mutex;
+#[cfg(any(feature = "tracking", feature = "headless"))]
+use std::sync::PoisonError;
+
+/// State shared by all devices of a group.
+pub(crate) struct GroupShared {
+    next_member: AtomicU32,
+    #[cfg(debug_assertions)]
+    handles: Arc<Mutex<handle::Registry>>,
+    #[cfg(feature = "tracking")]
+    tracking: Arc<Mutex<tracking::Registry>>,
+    #[cfg(feature = "validation")]
+    validation_objects: Arc<Mutex<validation::Objects>>,
+    /// Context shared by all headless devices of the group, not used for rendering.
+    #[cfg(feature = "headless")]
+    headless: Mutex<Option<headless::Context>>,
+}
+
+/// Membership of a device in a group.
+pub(crate) struct Member {
+    shared: Arc<GroupShared>,
+    index: u32,
+}
+
+/// Group of devices over contexts sharing their objects.
+///
+/// ```no_run
+/// # #[cfg(feature = "headless")]
+/// # unsafe fn group() -> grr::Result<()> {
+/// let group = grr::DeviceGroup::new();
+/// let upload = group.create_headless_device(grr::DeviceDesc::default())?;
+/// let render = group.create_headless_device(grr::DeviceDesc::default())?;
+///
+/// upload.make_current()?;
+/// let buffer = upload.create_buffer_from_host(&[0; 64], grr::MemoryFlags::empty(), None)?;
+/// let transfer = upload.transfer(buffer);
+///
+/// render.make_current()?;
+/// let buffer = render.acquire(transfer);
+/// render.delete_buffer(buffer);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeviceGroup(Arc<GroupShared>);
+
+impl DeviceGroup {
+    pub fn new() -> Self {
+        DeviceGroup(Arc::new(GroupShared {
+            next_member: AtomicU32::new(0),
+            #[cfg(debug_assertions)]
+            handles: Arc::default(),
+            #[cfg(feature = "tracking")]
+            tracking: Arc::default(),
+            #[cfg(feature = "validation")]
+            validation_objects: Arc::default(),
+            #[cfg(feature = "headless")]
+            headless: Mutex::default(),
+        }))
+    }
+
+    /// Create a new device of the group from an existing context.
+    ///
+    /// The context must share its objects with the contexts of all other devices of
+    /// the group (e.g. `with_shared_lists` in glutin). See `Device::new_with_desc`.
+    pub unsafe fn create_device<F>(&self, loader: F, desc: DeviceDesc) -> Device
+    where
+        F: FnMut(&str) -> *const c_void,
+    {
+        let mut device = Device::new_with_desc(loader, desc);
+        self.join(&mut device);
+        device
+    }
+
+    /// Create a new device of the group owning a headless context.
+    ///
+    /// The context is made current on the calling thread. All headless devices of the group
+    /// need to request the same robustness (`DeviceDesc::robust`). See `Device::new_headless`.
+    #[cfg(feature = "headless")]
+    pub unsafe fn create_headless_device(&self, desc: DeviceDesc) -> Result<Device> {
+        let debug = match desc.debug {
+            crate::Debug::Enable { .. } => true,
+            crate::Debug::Disable => desc.debug_filter.log_capacity > 0,
+        };
+        let context = {
+            let mut root = self
+                .0
+                .headless
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if root.is_none() {
+                *root = Some(headless::Context::new(false, desc.robust)?);
+            }
+            root.as_ref().unwrap().new_shared(debug, desc.robust)?
+        };
+        let mut device = Device::with_headless_context(context, desc);
+        self.join(&mut device);
+        Ok(device)
+    }
+
+    fn join(&self, device: &mut Device) {
+        let shared = &self.0;
+        #[cfg(debug_assertions)]
+        {
+            device.1.handles = shared.handles.clone();
+        }
+        #[cfg(feature = "tracking")]
+        {
+            device.1.tracking = shared.tracking.clone();
+        }
+        #[cfg(feature = "validation")]
+        {
+            device.1.validation_objects = shared.validation_objects.clone();
+        }
+        device.1.group = Some(Member {
+            shared: shared.clone(),
+            index: shared.next_member.fetch_add(1, Ordering::Relaxed),
+        });
+    }
+}
+
+impl Default for DeviceGroup {
+    fn default() -> Self {
+        DeviceGroup::new()
+    }
+}
+
+impl Drop for DeviceGroup {
+    fn drop(&mut self) {
+        #[cfg(feature = "tracking")]
+        if Arc::strong_count(&self.0.tracking) == 1 {
+            let registry = self
+                .0
+                .tracking
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            tracking::report_leaks(&registry.resources(), "device group");
+        }
+    }
+}
+
+/// Object shared between the contexts of a device group.
+pub trait SharedObject: Object {}
+
+impl SharedObject for Buffer {}
+impl SharedObject for Image {}
+impl SharedObject for Sampler {}
+impl SharedObject for Shader {}
+impl SharedObject for Pipeline {}
+impl SharedObject for Renderbuffer {}
+impl<T: 'static> SharedObject for TypedBuffer<T> {}
+
+/// Shared object in transfer between two devices of a group.
+///
+/// Created by [`Device::transfer`](struct.Device.html#method.transfer).
+#[derive(Debug)]
+#[must_use = "transfers need to be acquired by the receiving device"]
+pub struct Transfer<T> {
+    object: T,
+    fence: Fence,
+    /// Address of the group state.
+    group: usize,
+}
+
+impl Device {
+    /// Key of the context an object of type `ty` belongs to.
+    ///
+    /// Shared objects belong to all contexts of a group, container objects to the device.
+    #[cfg(any(debug_assertions, feature = "tracking"))]
+    pub(crate) fn context_key(&self, ty: ObjectType) -> u32 {
+        self.owner_index(ty).unwrap_or(0)
+    }
+
+    /// Group index of the device owning an object of type `ty`.
+    ///
+    /// Only container objects of devices in a group have an owner.
+    #[cfg(any(debug_assertions, feature = "tracking"))]
+    pub(crate) fn owner_index(&self, ty: ObjectType) -> Option<u32> {
+        match self.1.group {
+            Some(ref member) if !ty.is_shared() => Some(member.index),
+            _ => None,
+        }
+    }
+
+    /// Check that a container object is not owned by another device of the group.
+    ///
+    /// `owner` is the stamped group index of the owning device plus one, `0` if not owned.
+    #[cfg(debug_assertions)]
+    pub(crate) fn check_owner(&self, call: &str, ty: ObjectType, name: GLuint, owner: u32) {
+        let (index, owner) = match (self.owner_index(ty), owner.checked_sub(1)) {
+            (Some(index), Some(owner)) if index != owner => (index, owner),
+            _ => return,
+        };
+        panic!(
+            "{}: {:?} {} is owned by device {} of the group, used on device {} \
+             (container objects are not shared between contexts)",
+            call, ty, name, owner, index
+        );
+    }
+
+    /// Index of the device in its device group.
+    pub fn group_index(&self) -> Option<u32> {
+        self.1.group.as_ref().map(|member| member.index)
+    }
+
+    /// Release a shared object for usage on another device of the group.
+    ///
+    /// Fences all previously submitted commands of this device and flushes them.
+    /// The receiving device needs to `acquire` the object before using it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device isn't part of a device group.
+    #[track_caller]
+    pub unsafe fn transfer<T: SharedObject>(&self, object: T) -> Transfer<T> {
+        let _scope = self.api_scope("transfer");
+        let member = self
+            .1
+            .group
+            .as_ref()
+            .expect("transfer: device is not part of a device group");
+        let fence = self.create_fence();
+        // Fences need to be flushed to be visible to other contexts.
+        self.0.Flush();
+        Transfer {
+            object,
+            fence,
+            group: Arc::as_ptr(&member.shared) as usize,
+        }
+    }
+
+    /// Acquire a shared object released by another device of the group.
+    ///
+    /// Commands submitted after this call wait on the device until the commands submitted
+    /// before the `transfer` on the releasing device have been completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object was released by a device of another group.
+    #[track_caller]
+    pub unsafe fn acquire<T: SharedObject>(&self, transfer: Transfer<T>) -> T {
+        let _scope = self.api_scope("acquire");
+        let same_group = self.1.group.as_ref().map_or(false, |member| {
+            Arc::as_ptr(&member.shared) as usize == transfer.group
+        });
+        assert!(
+            same_group,
+            "acquire: object was transferred from a device of another group"
+        );
+        self.server_wait_fence(transfer.fence);
+        self.delete_fence(transfer.fence);
+        transfer.object
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+    use crate::{Debug, RecordingBackend};
+
+    /// Group of two devices, each with its own name namespace like separate contexts.
+    fn group() -> (DeviceGroup, Device, Device) {
+        let group = DeviceGroup::new();
+        let device = || {
+            let mut device =
+                unsafe { Device::with_backend(RecordingBackend::new(), Debug::Disable) };
+            group.join(&mut device);
+            device
+        };
+        let (a, b) = (device(), device());
+        (group, a, b)
+    }
+
+    #[test]
+    fn own_container_objects() {
+        let (_group, a, b) = group();
+        unsafe {
+            let vao_a = a.create_vertex_array(&[], None).unwrap();
+            let vao_b = b.create_vertex_array(&[], None).unwrap();
+            // Both contexts hand out the same name.
+            assert_eq!(vao_a.0, vao_b.0);
+
+            a.bind_vertex_array(vao_a);
+            b.bind_vertex_array(vao_b);
+            a.delete_vertex_array(vao_a);
+            b.delete_vertex_array(vao_b);
+        }
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "bind_vertex_array: VertexArray 1 is owned by device 0 of the group, \
+                               used on device 1"
+    )]
+    fn foreign_container_object() {
+        let (_group, a, b) = group();
+        unsafe {
+            let vao_a = a.create_vertex_array(&[], None).unwrap();
+            let _vao_b = b.create_vertex_array(&[], None).unwrap();
+            b.bind_vertex_array(vao_a);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Generation checked handles.
+//!
+//! GL recycles object names after deletion, a stale handle may therefore silently alias
+//! an object created later on. In debug builds (`debug_assertions`), every handle created
+//! by the device is stamped with a generation and the device keeps a registry of the live objects.
+//! Passing a deleted or recycled handle to the device panics, naming the offending call.
+//!
+//! Handles not created by the device (e.g. `Framebuffer::DEFAULT` or deserialized handles)
+//! are untracked and never checked. Release builds don't carry any generation.
+//!
+//! Devices of a `DeviceGroup` share the registry, container objects are registered per device.
+//! Container handles are additionally stamped with the owning device of the group,
+//! using a container object on another device of the group panics.
+
+use crate::__gl::types::GLuint;
+use crate::debug::{Object, ObjectType};
+use crate::device::Device;
+
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+#[cfg(debug_assertions)]
+use std::sync::{MutexGuard, PoisonError};
+
+/// Creation stamp of a handle.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Generation {
+    #[cfg(debug_assertions)]
+    generation: u32,
+    /// Group index of the device owning a container object plus one, `0` if not owned.
+    #[cfg(debug_assertions)]
+    owner: u32,
+}
+
+impl Generation {
+    /// Generation of handles not tracked by the registry.
+    pub(crate) const UNTRACKED: Generation = Generation {
+        #[cfg(debug_assertions)]
+        generation: 0,
+        #[cfg(debug_assertions)]
+        owner: 0,
+    };
+}
+
+/// Object handle with a generation.
+pub(crate) trait Handle: Object {
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    fn generation(&self) -> Generation;
+}
+
+/// Generations of all live objects.
+#[cfg(debug_assertions)]
+#[derive(Default)]
+pub(crate) struct Registry {
+    next: u32,
+    /// Generations by context key (see `Device::context_key`), type and name.
+    live: HashMap<(u32, ObjectType, GLuint), u32>,
+}
+
+#[cfg(debug_assertions)]
+impl Registry {
+    fn check(&self, call: &str, key: (u32, ObjectType, GLuint), generation: u32) {
+        if generation == 0 {
+            return;
+        }
+        let (_, ty, name) = key;
+        match self.live.get(&key) {
+            Some(&live) if live == generation => {}
+            Some(_) => panic!(
+                "{}: stale {:?} handle {} (deleted and recycled by a newer object)",
+                call, ty, name
+            ),
+            None => panic!("{}: stale {:?} handle {} (deleted)", call, ty, name),
+        }
+    }
+}
+
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+impl Device {
+    #[cfg(debug_assertions)]
+    fn handles(&self) -> MutexGuard<'_, Registry> {
+        self.1
+            .handles
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Stamp a newly created object with a new generation and its owning device.
+    #[cfg(debug_assertions)]
+    pub(crate) fn create_handle(&self, ty: ObjectType, name: GLuint) -> Generation {
+        let mut registry = self.handles();
+        registry.next += 1;
+        let generation = registry.next;
+        registry
+            .live
+            .insert((self.context_key(ty), ty, name), generation);
+        Generation {
+            generation,
+            owner: self.owner_index(ty).map_or(0, |index| index + 1),
+        }
+    }
+
+    /// Stamp a newly created object with a new generation and its owning device.
+    #[cfg(not(debug_assertions))]
+    pub(crate) fn create_handle(&self, ty: ObjectType, name: GLuint) -> Generation {
+        Generation {}
+    }
+
+    /// Check that the handle refers to a live object of this device.
+    pub(crate) fn check_handle<H: Handle>(&self, call: &str, handle: H) {
+        #[cfg(debug_assertions)]
+        {
+            let generation = handle.generation();
+            self.check_owner(call, H::TYPE, handle.handle(), generation.owner);
+            self.handles().check(
+                call,
+                (self.context_key(H::TYPE), H::TYPE, handle.handle()),
+                generation.generation,
+            );
+        }
+    }
+
+    /// Check that all handles refer to live objects.
+    pub(crate) fn check_handles<H: Handle>(&self, call: &str, handles: &[H]) {
+        for &handle in handles {
+            self.check_handle(call, handle);
+        }
+    }
+
+    /// Check that the handles refer to live objects and invalidate their generations.
+    ///
+    /// Also removes the objects from the resource registry (`tracking` feature).
+    pub(crate) fn delete_handles<H: Handle>(&self, call: &str, handles: &[H]) {
+        #[cfg(feature = "tracking")]
+        for handle in handles {
+            self.untrack_resource(H::TYPE, handle.handle());
+        }
+        #[cfg(debug_assertions)]
+        {
+            let mut registry = self.handles();
+            for handle in handles {
+                let key = (self.context_key(H::TYPE), H::TYPE, handle.handle());
+                let generation = handle.generation();
+                self.check_owner(call, H::TYPE, handle.handle(), generation.owner);
+                let generation = generation.generation;
+                registry.check(call, key, generation);
+                if generation != 0 {
+                    registry.live.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::Generation;
+    use crate::backend::tests::device;
+    use crate::*;
+
+    #[test]
+    fn live() {
+        let (grr, _recording) = device();
+        unsafe {
+            let buffer = grr.create_buffer(16, MemoryFlags::empty(), None).unwrap();
+            grr.invalidate_buffer(buffer);
+            grr.delete_buffer(buffer);
+            // Untracked handles are never checked.
+            grr.invalidate_buffer(Buffer(buffer.0, buffer.1, Generation::UNTRACKED));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalidate_buffer: stale Buffer handle 1 (deleted)")]
+    fn deleted() {
+        let (grr, _recording) = device();
+        unsafe {
+            let buffer = grr.create_buffer(16, MemoryFlags::empty(), None).unwrap();
+            grr.delete_buffer(buffer);
+            grr.invalidate_buffer(buffer);
+        }
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "invalidate_buffer: stale Buffer handle 1 (deleted and recycled by a newer object)"
+    )]
+    fn recycled() {
+        let (grr, _recording) = device();
+        unsafe {
+            let buffer = grr.create_buffer(16, MemoryFlags::empty(), None).unwrap();
+            grr.delete_buffer(buffer);
+            // `RecordingBackend` never reuses names, recycle the name like a GL driver would.
+            grr.create_handle(ObjectType::Buffer, buffer.0);
+            grr.invalidate_buffer(buffer);
+        }
+    }
+}
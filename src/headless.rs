@@ -0,0 +1,321 @@
+//! Headless EGL context.
+//!
+//! Owns a GL 4.5 core context without any window system surface. The context
+//! is created on the surfaceless Mesa platform if available, falling back to
+//! the default display with a small pbuffer surface.
+//!
+//! All contexts share the display of the process, contexts of a device group
+//! additionally share their objects.
+
+use crate::__egl;
+use crate::__egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
+use crate::error::{Error, Result};
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+#[cfg(target_os = "windows")]
+const EGL_LIBRARY: &str = "libEGL.dll";
+#[cfg(not(target_os = "windows"))]
+const EGL_LIBRARY: &str = "libEGL.so.1";
+
+/// Initialized display, shared by all contexts of the process.
+struct Display {
+    egl: __egl::Egl,
+    display: EGLDisplay,
+    // Must outlive all function pointers loaded from it.
+    _library: libloading::Library,
+}
+
+unsafe impl Send for Display {}
+unsafe impl Sync for Display {}
+
+/// Display with the number of contexts using it.
+///
+/// EGL returns the same display for every call in a process and `eglTerminate` invalidates
+/// all its contexts. The display is therefore only terminated after the last context has
+/// been destroyed.
+struct SharedDisplay {
+    display: Arc<Display>,
+    contexts: usize,
+}
+
+static DISPLAY: Mutex<Option<SharedDisplay>> = Mutex::new(None);
+
+impl Display {
+    /// Acquire the display of the process, initializing it if required.
+    unsafe fn acquire() -> Result<Arc<Display>> {
+        let mut shared = DISPLAY.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(ref mut shared) = *shared {
+            shared.contexts += 1;
+            return Ok(shared.display.clone());
+        }
+
+        let display = Arc::new(Display::initialize()?);
+        *shared = Some(SharedDisplay {
+            display: display.clone(),
+            contexts: 1,
+        });
+        Ok(display)
+    }
+
+    /// Release the display, terminating it if no context uses it anymore.
+    unsafe fn release() {
+        let mut shared = DISPLAY.lock().unwrap_or_else(PoisonError::into_inner);
+        let last = match *shared {
+            Some(ref mut shared) => {
+                shared.contexts -= 1;
+                shared.contexts == 0
+            }
+            None => false,
+        };
+        if last {
+            let Display {
+                ref egl, display, ..
+            } = *shared.take().unwrap().display;
+            egl.Terminate(display);
+        }
+    }
+
+    unsafe fn initialize() -> Result<Display> {
+        let library = libloading::Library::new(EGL_LIBRARY)
+            .map_err(|err| Error::ContextCreation(format!("{}: {}", EGL_LIBRARY, err)))?;
+        // Extension functions aren't necessarily exported by the library.
+        let get_proc_address = library
+            .get::<unsafe extern "system" fn(*const c_char) -> *const c_void>(
+                b"eglGetProcAddress\0",
+            )
+            .ok();
+        let egl = __egl::Egl::load_with(|symbol| {
+            let symbol = CString::new(symbol).unwrap();
+            match library.get::<*const c_void>(symbol.as_bytes_with_nul()) {
+                Ok(sym) => *sym,
+                Err(_) => get_proc_address
+                    .as_ref()
+                    .map_or(ptr::null(), |get| get(symbol.as_ptr())),
+            }
+        });
+
+        let client_extensions = query_extensions(&egl, __egl::NO_DISPLAY);
+        let has_client_extension = |name: &str| client_extensions.iter().any(|ext| ext == name);
+        let display = if !has_client_extension("EGL_MESA_platform_surfaceless") {
+            egl.GetDisplay(__egl::DEFAULT_DISPLAY as _)
+        } else if client_version(&egl) >= (1, 5) {
+            egl.GetPlatformDisplay(
+                __egl::PLATFORM_SURFACELESS_MESA,
+                __egl::DEFAULT_DISPLAY as _,
+                ptr::null(),
+            )
+        } else if has_client_extension("EGL_EXT_platform_base")
+            && egl.GetPlatformDisplayEXT.is_loaded()
+        {
+            egl.GetPlatformDisplayEXT(
+                __egl::PLATFORM_SURFACELESS_MESA,
+                __egl::DEFAULT_DISPLAY as _,
+                ptr::null(),
+            )
+        } else {
+            egl.GetDisplay(__egl::DEFAULT_DISPLAY as _)
+        };
+        if display == __egl::NO_DISPLAY {
+            return Err(egl_error(&egl, "eglGetDisplay"));
+        }
+
+        let (mut major, mut minor) = (0, 0);
+        if egl.Initialize(display, &mut major, &mut minor) == __egl::FALSE {
+            return Err(egl_error(&egl, "eglInitialize"));
+        }
+
+        Ok(Display {
+            egl,
+            display,
+            _library: library,
+        })
+    }
+}
+
+pub(crate) struct Context {
+    display: Arc<Display>,
+    context: EGLContext,
+    surface: EGLSurface,
+}
+
+// Same as for externally created contexts, it's the responsibility of the user
+// to make the context current when moving the device to another thread.
+unsafe impl Send for Context {}
+
+impl Context {
+    pub unsafe fn new(debug: bool, robust: bool) -> Result<Self> {
+        Context::create(Display::acquire()?, __egl::NO_CONTEXT, debug, robust)
+    }
+
+    /// Create a new context sharing objects with this context.
+    ///
+    /// Both contexts need to use the same reset notification strategy (`robust`).
+    pub unsafe fn new_shared(&self, debug: bool, robust: bool) -> Result<Self> {
+        Context::create(Display::acquire()?, self.context, debug, robust)
+    }
+
+    unsafe fn create(
+        display: Arc<Display>,
+        share: EGLContext,
+        debug: bool,
+        robust: bool,
+    ) -> Result<Self> {
+        let mut context = Context {
+            display,
+            context: __egl::NO_CONTEXT,
+            surface: __egl::NO_SURFACE,
+        };
+
+        let egl = &context.display.egl;
+        let display = context.display.display;
+        let surfaceless = query_extensions(egl, display)
+            .iter()
+            .any(|ext| ext == "EGL_KHR_surfaceless_context");
+
+        if egl.BindAPI(__egl::OPENGL_API) == __egl::FALSE {
+            return Err(egl_error(egl, "eglBindAPI"));
+        }
+
+        let surface_type = if surfaceless { 0 } else { __egl::PBUFFER_BIT };
+        let config_attribs = [
+            __egl::RENDERABLE_TYPE as EGLint,
+            __egl::OPENGL_BIT as _,
+            __egl::SURFACE_TYPE as _,
+            surface_type as _,
+            __egl::NONE as _,
+        ];
+        let mut config: EGLConfig = ptr::null();
+        let mut num_configs = 0;
+        if egl.ChooseConfig(
+            display,
+            config_attribs.as_ptr(),
+            &mut config,
+            1,
+            &mut num_configs,
+        ) == __egl::FALSE
+            || num_configs == 0
+        {
+            return Err(egl_error(egl, "eglChooseConfig"));
+        }
+
+        let context_attribs = [
+            __egl::CONTEXT_MAJOR_VERSION as EGLint,
+            4,
+            __egl::CONTEXT_MINOR_VERSION as _,
+            5,
+            __egl::CONTEXT_OPENGL_PROFILE_MASK as _,
+            __egl::CONTEXT_OPENGL_CORE_PROFILE_BIT as _,
+            __egl::CONTEXT_OPENGL_DEBUG as _,
+            if debug { __egl::TRUE } else { __egl::FALSE } as _,
+            __egl::CONTEXT_OPENGL_ROBUST_ACCESS as _,
+            if robust { __egl::TRUE } else { __egl::FALSE } as _,
+            __egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY as _,
+            if robust {
+                __egl::LOSE_CONTEXT_ON_RESET
+            } else {
+                __egl::NO_RESET_NOTIFICATION
+            } as _,
+            __egl::NONE as _,
+        ];
+        context.context = egl.CreateContext(display, config, share, context_attribs.as_ptr());
+        if context.context == __egl::NO_CONTEXT {
+            return Err(egl_error(egl, "eglCreateContext"));
+        }
+
+        if !surfaceless {
+            let pbuffer_attribs = [
+                __egl::WIDTH as EGLint,
+                1,
+                __egl::HEIGHT as _,
+                1,
+                __egl::NONE as _,
+            ];
+            context.surface = egl.CreatePbufferSurface(display, config, pbuffer_attribs.as_ptr());
+            if context.surface == __egl::NO_SURFACE {
+                return Err(egl_error(egl, "eglCreatePbufferSurface"));
+            }
+        }
+
+        context.make_current()?;
+        Ok(context)
+    }
+
+    /// Make the context current on the calling thread.
+    pub unsafe fn make_current(&self) -> Result<()> {
+        let Display {
+            ref egl, display, ..
+        } = *self.display;
+        if egl.MakeCurrent(display, self.surface, self.surface, self.context) == __egl::FALSE {
+            return Err(egl_error(egl, "eglMakeCurrent"));
+        }
+        Ok(())
+    }
+
+    pub unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let symbol = CString::new(symbol).unwrap();
+        self.display.egl.GetProcAddress(symbol.as_ptr()) as *const _
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            let Display {
+                ref egl, display, ..
+            } = *self.display;
+            if egl.GetCurrentContext() == self.context {
+                egl.MakeCurrent(
+                    display,
+                    __egl::NO_SURFACE,
+                    __egl::NO_SURFACE,
+                    __egl::NO_CONTEXT,
+                );
+            }
+            if self.surface != __egl::NO_SURFACE {
+                egl.DestroySurface(display, self.surface);
+            }
+            if self.context != __egl::NO_CONTEXT {
+                egl.DestroyContext(display, self.context);
+            }
+            Display::release();
+        }
+    }
+}
+
+unsafe fn query_extensions(egl: &__egl::Egl, display: EGLDisplay) -> Vec<String> {
+    let extensions = egl.QueryString(display, __egl::EXTENSIONS as _);
+    if extensions.is_null() {
+        // Client extensions are only supported with EGL 1.5 or `EGL_EXT_client_extensions`.
+        egl.GetError();
+        return Vec::new();
+    }
+
+    CStr::from_ptr(extensions)
+        .to_string_lossy()
+        .split_whitespace()
+        .map(String::from)
+        .collect()
+}
+
+/// Version of the client library, `(0, 0)` before EGL 1.5.
+unsafe fn client_version(egl: &__egl::Egl) -> (u32, u32) {
+    let version = egl.QueryString(__egl::NO_DISPLAY, __egl::VERSION as _);
+    if version.is_null() {
+        egl.GetError();
+        return (0, 0);
+    }
+
+    let version = CStr::from_ptr(version).to_string_lossy();
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|n| n.parse().unwrap_or(0));
+    (numbers.next().unwrap_or(0), numbers.next().unwrap_or(0))
+}
+
+unsafe fn egl_error(egl: &__egl::Egl, function: &str) -> Error {
+    Error::ContextCreation(format!("{} failed (0x{:X})", function, egl.GetError()))
+}
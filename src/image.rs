@@ -2,6 +2,8 @@
 
 use crate::__gl;
 use crate::__gl::types::{GLenum, GLuint};
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
 use std::ops::Range;
 
@@ -10,6 +12,7 @@ use crate::debug::{Object, ObjectType};
 use crate::device::Device;
 use crate::error::Result;
 use crate::format::Format;
+use crate::handle::{Generation, Handle};
 use crate::Extent;
 
 /// Image resource handle.
@@ -24,10 +27,13 @@ use crate::Extent;
 ///
 /// The API only uses images directly when the function call
 /// affects the underlying memory (e.g copy operations).
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     pub(crate) raw: GLuint,
     pub(crate) target: GLenum,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) generation: Generation,
 }
 
 impl Object for Image {
@@ -37,6 +43,12 @@ impl Object for Image {
     }
 }
 
+impl Handle for Image {
+    fn generation(&self) -> Generation {
+        self.generation
+    }
+}
+
 impl Image {
     /// Get image view from current image.
     ///
@@ -44,14 +56,15 @@ impl Image {
     ///
     /// - These image view **must** not be deleted.
     pub fn as_view(&self) -> ImageView {
-        ImageView(self.raw)
+        ImageView(self.raw, self.generation)
     }
 }
 
 /// Image dimensionality type.
 ///
 /// Layer, as in arrays or cube maps, don't affect the dimensionality type.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImageType {
     // One dimensional image.
     D1 {
@@ -172,9 +185,13 @@ impl ImageType {
 /// Image Views denote subranges of an image storage. Pipelines will
 /// only access image data via views. Views alias the memory of the associated
 /// image.
-#[repr(transparent)]
-#[derive(Copy, Clone)]
-pub struct ImageView(pub(crate) GLuint);
+#[cfg_attr(not(debug_assertions), repr(transparent))]
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageView(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for ImageView {
     const TYPE: ObjectType = ObjectType::Image; // internally it's an image
@@ -183,10 +200,17 @@ impl Object for ImageView {
     }
 }
 
+impl Handle for ImageView {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 /// Image View type.
 ///
 /// An `ImageViewType` maps roughly to OpenGL texture targets.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImageViewType {
     D1,
     D2,
@@ -199,6 +223,7 @@ pub enum ImageViewType {
 
 /// Subresource of an image.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubresourceRange {
     /// Range of mip levels.
     pub levels: Range<u32>,
@@ -207,6 +232,7 @@ pub struct SubresourceRange {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubresourceLayers {
     /// Mipmap level.
     pub level: u32,
@@ -216,7 +242,15 @@ pub struct SubresourceLayers {
 
 impl Device {
     ///
-    pub unsafe fn create_image(&self, ty: ImageType, format: Format, levels: u32) -> Result<Image> {
+    #[track_caller]
+    pub unsafe fn create_image(
+        &self,
+        ty: ImageType,
+        format: Format,
+        levels: u32,
+        label: Option<&str>,
+    ) -> Result<Image> {
+        let _scope = self.api_scope("create_image");
         let target = match ty {
             ImageType::D1 { layers: 1, .. } => __gl::TEXTURE_1D,
             ImageType::D1 { .. } => __gl::TEXTURE_1D_ARRAY,
@@ -277,11 +311,38 @@ impl Device {
         }
         self.get_error()?;
 
-        Ok(Image { raw: image, target })
+        let image = Image {
+            raw: image,
+            target,
+            generation: self.create_handle(ObjectType::Image, image),
+        };
+        #[cfg(feature = "tracking")]
+        self.track_resource(
+            ObjectType::Image,
+            image.raw,
+            crate::tracking::image_size(ty, format, levels),
+        );
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateImage {
+            ty,
+            format,
+            levels,
+            image,
+        });
+        self.label_object(image, label);
+        Ok(image)
     }
 
     /// Create a texel buffer.
-    pub unsafe fn create_texel_buffer(&self, buffer: BufferRange, format: Format) -> Result<Image> {
+    #[track_caller]
+    pub unsafe fn create_texel_buffer(
+        &self,
+        buffer: BufferRange,
+        format: Format,
+        label: Option<&str>,
+    ) -> Result<Image> {
+        let _scope = self.api_scope("create_texel_buffer");
+        self.check_handle("create_texel_buffer", buffer.buffer);
         let mut image = 0;
         self.0.CreateTextures(__gl::TEXTURE_BUFFER, 1, &mut image);
         self.get_error()?;
@@ -295,19 +356,39 @@ impl Device {
         );
         self.get_error()?;
 
-        Ok(Image {
+        let image = Image {
             raw: image,
             target: __gl::TEXTURE_BUFFER,
-        })
+            generation: self.create_handle(ObjectType::Image, image),
+        };
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Image, image.raw, 0);
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateTexelBuffer {
+            buffer,
+            format,
+            image,
+        });
+        self.label_object(image, label);
+        Ok(image)
     }
 
     /// Delete an images.
+    #[track_caller]
     pub unsafe fn delete_image(&self, image: Image) {
+        let _scope = self.api_scope("delete_image");
         self.delete_images(&[image]);
     }
 
     /// Delete multiple images.
+    #[track_caller]
     pub unsafe fn delete_images(&self, images: &[Image]) {
+        let _scope = self.api_scope("delete_images");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteImages {
+            images: images.to_vec(),
+        });
+        self.delete_handles("delete_images", images);
         let images = images.iter().map(|i| i.raw).collect::<Vec<_>>();
 
         self.0
@@ -315,13 +396,17 @@ impl Device {
     }
 
     /// Create an image view from an image.
+    #[track_caller]
     pub unsafe fn create_image_view(
         &self,
         image: Image,
         ty: ImageViewType,
         format: Format,
         range: SubresourceRange,
+        label: Option<&str>,
     ) -> Result<ImageView> {
+        let _scope = self.api_scope("create_image_view");
+        self.check_handle("create_image_view", image);
         let target = match ty {
             ImageViewType::D1 => __gl::TEXTURE_1D,
             ImageViewType::D2 if image.target == __gl::TEXTURE_2D_MULTISAMPLE => {
@@ -353,20 +438,35 @@ impl Device {
         );
         self.get_error()?;
 
-        Ok(ImageView(view))
+        let view = ImageView(view, self.create_handle(ObjectType::Image, view));
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Image, view.0, 0);
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateImageView {
+            image,
+            ty,
+            format,
+            range: range.clone(),
+            view,
+        });
+        self.label_object(view, label);
+        Ok(view)
     }
 
     /// Create an image and an associated view.
     ///
     /// The image view type is derived from the `ImageType`.
-    /// It creates either non-arrayed or arrayed view types.
+    /// It creates either non-arrayed or arrayed view types. The `label` names both objects.
+    #[track_caller]
     pub unsafe fn create_image_and_view(
         &self,
         ty: ImageType,
         format: Format,
         levels: u32,
+        label: Option<&str>,
     ) -> Result<(Image, ImageView)> {
-        let image = self.create_image(ty, format, levels)?;
+        let _scope = self.api_scope("create_image_and_view");
+        let image = self.create_image(ty, format, levels, label)?;
         let view_ty = ty.view_ty();
         let image_view = self.create_image_view(
             image,
@@ -376,32 +476,56 @@ impl Device {
                 levels: 0..levels,
                 layers: 0..ty.layers(),
             },
+            label,
         )?;
 
         Ok((image, image_view))
     }
 
     /// Delete an image views.
+    #[track_caller]
     pub unsafe fn delete_image_view(&self, view: ImageView) {
+        let _scope = self.api_scope("delete_image_view");
         self.delete_image_views(&[view]);
     }
 
     /// Delete multipe image views.
+    #[track_caller]
     pub unsafe fn delete_image_views(&self, views: &[ImageView]) {
-        self.0.DeleteTextures(
-            views.len() as _,
-            views.as_ptr() as *const _, // newtype
-        );
+        let _scope = self.api_scope("delete_image_views");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteImageViews {
+            views: views.to_vec(),
+        });
+        self.delete_handles("delete_image_views", views);
+        let views = views.iter().map(|view| view.0).collect::<Vec<_>>();
+        self.0.DeleteTextures(views.len() as _, views.as_ptr());
     }
 
     /// Bind image views to texture units.
+    #[track_caller]
     pub unsafe fn bind_image_views(&self, first: u32, views: &[ImageView]) {
+        let _scope = self.api_scope("bind_image_views");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindImageViews {
+            first,
+            views: views.to_vec(),
+        });
+        self.check_handles("bind_image_views", views);
         let views = views.iter().map(|view| view.0).collect::<Vec<_>>();
         self.0.BindTextures(first, views.len() as _, views.as_ptr());
     }
 
     /// Bind image views to storage image units.
+    #[track_caller]
     pub unsafe fn bind_storage_image_views(&self, first: u32, views: &[ImageView]) {
+        let _scope = self.api_scope("bind_storage_image_views");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindStorageImageViews {
+            first,
+            views: views.to_vec(),
+        });
+        self.check_handles("bind_storage_image_views", views);
         let views = views.iter().map(|view| view.0).collect::<Vec<_>>();
         self.0
             .BindImageTextures(first, views.len() as _, views.as_ptr());
@@ -414,7 +538,12 @@ impl Device {
     /// creation.
     ///
     /// The downscaling filter is implementation dependent!
+    #[track_caller]
     pub unsafe fn generate_mipmaps(&self, image: Image) {
+        let _scope = self.api_scope("generate_mipmaps");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::GenerateMipmaps { image });
+        self.check_handle("generate_mipmaps", image);
         self.0.GenerateTextureMipmap(image.raw);
     }
 }
@@ -34,9 +34,9 @@
 //!         grr::Device::new(
 //!             |symbol| window.get_proc_address(symbol) as *const _,
 //!             grr::Debug::Enable {
-//!                 callback: |_, _, _, _, msg| {
-//!                     println!("{:?}", msg);
-//!                 },
+//!                 callback: Box::new(|msg| {
+//!                     println!("{:?}", msg.message);
+//!                 }),
 //!                 flags: grr::DebugReport::all(),
 //!             },
 //!         )
@@ -52,7 +52,7 @@
 //! function calls on a [`Device`](struct.Device.html) object. The calls often translate directly to one GL call.
 //! All other objects created are opaque handles!
 //!
-//! * **Resource**: Objects with associated memory. Can be a [`Buffer`](struct.Buffer.html) (untyped) or an [`Image`](struct.Image.html).
+//! * **Resource**: Objects with associated memory. Can be a [`Buffer`](struct.Buffer.html) (untyped or as [`TypedBuffer`](struct.TypedBuffer.html)) or an [`Image`](struct.Image.html).
 //! * **Pipeline**: There currently are two sort of pipelines supported:
 //!       [*Graphics*](struct.Device.html#method.create_graphics_pipeline) and
 //!       [*Compute*](struct.Device.html#method.create_compute_pipeline)
@@ -67,32 +67,60 @@
 #[macro_use]
 extern crate bitflags;
 
+#[cfg(feature = "headless")]
+mod __egl;
 mod __gl;
 
+mod backend;
 mod buffer;
+mod cache;
+#[cfg(feature = "capture")]
+mod capture;
 mod command;
 mod debug;
 mod device;
+mod diagnostic;
 mod error;
 mod format;
+mod frame;
 mod framebuffer;
+mod group;
+mod handle;
+#[cfg(feature = "headless")]
+mod headless;
 mod image;
 mod pipeline;
+mod pod;
 mod query;
+mod readback;
+pub mod safe;
 mod sampler;
+mod state;
+mod suballoc;
 mod sync;
+#[cfg(feature = "tracking")]
+mod tracking;
 mod transfer;
+#[cfg(feature = "validation")]
+mod validation;
 mod vertex;
 
 pub use crate::{
-    buffer::*, command::*, debug::*, device::*, error::*, format::*, framebuffer::*, image::*,
-    pipeline::*, query::*, sampler::*, sync::*, transfer::*, vertex::*,
+    backend::*, buffer::*, cache::StateCacheStats, command::*, debug::*, device::*, diagnostic::*,
+    error::*, format::*, frame::*, framebuffer::*, group::*, image::*, pipeline::*, pod::*,
+    query::*, readback::*, sampler::*, state::*, suballoc::*, sync::*, transfer::*, vertex::*,
 };
 
+#[cfg(feature = "capture")]
+pub use crate::capture::*;
+#[cfg(feature = "tracking")]
+pub use crate::tracking::Resource;
+
 pub const WHOLE_SIZE: u64 = !0;
 
 ///
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Region {
     pub x: i32,
     pub y: i32,
@@ -104,6 +132,7 @@ pub struct Region {
 
 /// Starting location for copying from or to texture data.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Offset {
     pub x: i32,
     pub y: i32,
@@ -116,6 +145,7 @@ impl Offset {
 
 ///
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Extent {
     pub width: u32,
     pub height: u32,
@@ -127,6 +157,7 @@ pub struct Extent {
 /// Used in depth test, stencil test and sampling depth textures.
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Compare {
     Less = __gl::LESS,
     LessEqual = __gl::LEQUAL,
@@ -142,6 +173,7 @@ pub enum Compare {
 ///
 /// Reinterprets the passed data as raw memory.
 /// Be aware of possible packing and aligning rules by Rust compared to OpenGL.
+#[deprecated(note = "use `as_bytes`, which requires the data to be `Pod`")]
 pub fn as_u8_slice<T>(data: &[T]) -> &[u8] {
     let len = std::mem::size_of::<T>() * data.len();
     unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, len) }
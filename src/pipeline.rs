@@ -2,10 +2,15 @@
 
 use crate::__gl;
 use crate::__gl::types::{GLint, GLuint};
+use crate::cache::StateKey;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
 use crate::debug::{Object, ObjectType};
 use crate::device::Device;
+use crate::diagnostic::ShaderLog;
 use crate::error::{Error, Result};
+use crate::handle::{Generation, Handle};
 use crate::Compare;
 
 use std::ptr;
@@ -25,9 +30,13 @@ use std::ptr;
 /// for writing shaders. The OpenGL drivers will translate the GLSL shaders into IHV specific machine language
 /// via an built-in compiler. Beside the shader representation in text form (GLSL) with GL 4.6 comes also support
 /// for the binary SPIR-V format.
-#[repr(transparent)]
+#[cfg_attr(not(debug_assertions), repr(transparent))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct Shader(GLuint);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shader(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for Shader {
     const TYPE: ObjectType = ObjectType::Shader;
@@ -36,12 +45,22 @@ impl Object for Shader {
     }
 }
 
+impl Handle for Shader {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 /// Graphics or Compute pipeline.
 ///
 /// Specifies how draw or dispatch commands are executed.
-#[repr(transparent)]
+#[cfg_attr(not(debug_assertions), repr(transparent))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct Pipeline(pub(crate) GLuint);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pipeline(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for Pipeline {
     const TYPE: ObjectType = ObjectType::Pipeline;
@@ -50,11 +69,18 @@ impl Object for Pipeline {
     }
 }
 
+impl Handle for Pipeline {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 /// Shader Stages.
 ///
 /// Each [`Shader`](struct.Shader.html) has an associated stage in the pipeline.
 /// See [`GraphicsPipelineDesc`](struct.GraphicsPipelineDesc.html) for more details about graphics pipeline stages.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ShaderStage {
     /// Vertex stage.
     Vertex,
@@ -76,6 +102,7 @@ pub enum ShaderStage {
 
 bitflags!(
     /// Shader compilation flags.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct ShaderFlags: u8 {
         /// Write compilation errors to stdout.
         const VERBOSE = 0x1;
@@ -84,6 +111,7 @@ bitflags!(
 
 bitflags!(
     /// Pipeline link flags.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct PipelineFlags: u8 {
         /// Write link errors to stdout.
         const VERBOSE = 0x1;
@@ -186,6 +214,7 @@ impl From<MeshPipelineDesc> for GraphicsPipelineDesc {
 ///
 /// Configures the input assembler for primitive shading.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputAssembly {
     /// Specifies if a special vertex index indicates a restart of the primitive assembly.
     pub primitive_restart: Option<u32>,
@@ -195,6 +224,7 @@ pub struct InputAssembly {
 ///
 /// Controls the rasterization process for converting primitives into fragments.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rasterization {
     /// Clamp depth values of fragments to the z-planes instead of clipping.
     pub depth_clamp: bool,
@@ -231,6 +261,7 @@ impl Default for Rasterization {
 /// Used during [`Rasterization`](struct.Rasterization.html).
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PolygonMode {
     Point = __gl::POINT,
     Line = __gl::LINE,
@@ -242,6 +273,7 @@ pub enum PolygonMode {
 /// Used during [`Rasterization`](struct.Rasterization.html).
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CullMode {
     Front = __gl::FRONT,
     Back = __gl::BACK,
@@ -253,6 +285,7 @@ pub enum CullMode {
 /// Used during [`Rasterization`](struct.Rasterization.html).
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FrontFace {
     CounterClockwise = __gl::CCW,
     Clockwise = __gl::CW,
@@ -260,12 +293,14 @@ pub enum FrontFace {
 
 ///
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorBlend {
     pub attachments: Vec<ColorBlendAttachment>,
 }
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendFactor {
     Zero = __gl::ZERO,
     One = __gl::ONE,
@@ -290,6 +325,7 @@ pub enum BlendFactor {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendOp {
     Add = __gl::FUNC_ADD,
     Substract = __gl::FUNC_SUBTRACT,
@@ -299,6 +335,7 @@ pub enum BlendOp {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlendChannel {
     pub src_factor: BlendFactor,
     pub dst_factor: BlendFactor,
@@ -307,6 +344,7 @@ pub struct BlendChannel {
 
 ///
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorBlendAttachment {
     pub blend_enable: bool,
     pub color: BlendChannel,
@@ -315,6 +353,7 @@ pub struct ColorBlendAttachment {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StencilOp {
     Keep = __gl::KEEP,
     Zero = __gl::ZERO,
@@ -328,6 +367,7 @@ pub enum StencilOp {
 
 /// Stencil operation settings, per-face.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StencilFace {
     /// Action taken on the buffer when the stencil test
     /// fails on the new fragment.
@@ -373,6 +413,7 @@ impl Default for StencilFace {
 
 /// Depth and stencil test and associated options.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DepthStencil {
     /// Whether or not a depth test is applied to fragments.
     pub depth_test: bool,
@@ -410,6 +451,7 @@ impl Default for DepthStencil {
 
 ///
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Multisample {
     pub sample_shading: bool,
     pub min_sample_shading: f32,
@@ -424,19 +466,20 @@ pub enum ShaderSource<'a> {
     Glsl,
     /// SPIR-V binary shader.
     ///
-    /// Requires GL 4.6 support.
+    /// Requires GL 4.6 support (`DeviceFeatures::spirv`).
     Spirv { entrypoint: &'a str },
 }
 
 impl Device {
     /// Compile a new shader from GLSL, returning the shader object iff compilation was successful.
+    #[track_caller]
     unsafe fn compile_shader(
         &self,
         stage: ShaderStage,
         ty: ShaderSource,
         source: &[u8],
     ) -> Result<Shader> {
-        let stage = match stage {
+        let raw_stage = match stage {
             ShaderStage::Vertex => __gl::VERTEX_SHADER,
             ShaderStage::TessellationControl => __gl::TESS_CONTROL_SHADER,
             ShaderStage::TessellationEvaluation => __gl::TESS_EVALUATION_SHADER,
@@ -448,7 +491,7 @@ impl Device {
         };
 
         let shader = {
-            let shader = self.0.CreateShader(stage);
+            let shader = self.0.CreateShader(raw_stage);
             self.get_error()?;
 
             match ty {
@@ -475,8 +518,10 @@ impl Device {
                 }
             }
 
-            Shader(shader)
+            Shader(shader, self.create_handle(ObjectType::Shader, shader))
         };
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Shader, shader.0, 0);
         let status = {
             let mut status = 0;
             self.0
@@ -486,7 +531,18 @@ impl Device {
 
         if status != GLint::from(__gl::TRUE) {
             //self.0.DeleteShader(shader);
-            return Err(Error::CompileError(shader));
+            let source = match ty {
+                ShaderSource::Glsl => {
+                    let text = String::from_utf8_lossy(source);
+                    Some(text.trim_end_matches('\0').to_string())
+                }
+                ShaderSource::Spirv { .. } => None,
+            };
+            let log = self.get_shader_log(shader).unwrap_or_default();
+            return Err(Error::CompileError(
+                shader,
+                ShaderLog::new(log, Some(stage), source),
+            ));
         }
 
         Ok(shader)
@@ -499,14 +555,39 @@ impl Device {
     /// - `source` must be a NULL-terminated C-String.
     /// - The GLSL shader version must be `450 core` or higher.
     /// - The `stage` parameter must be a valid stage of the passed shader source.
+    #[track_caller]
     pub unsafe fn create_shader(
         &self,
         stage: ShaderStage,
         ty: ShaderSource,
         source: &[u8],
         flags: ShaderFlags,
+        label: Option<&str>,
     ) -> Result<Shader> {
+        let _scope = self.api_scope("create_shader");
+        #[cfg(feature = "capture")]
+        let entrypoint = match ty {
+            ShaderSource::Glsl => None,
+            ShaderSource::Spirv { entrypoint } => Some(entrypoint),
+        };
         let shader = self.compile_shader(stage, ty, source);
+        #[cfg(feature = "capture")]
+        if let Ok(shader) = shader {
+            self.capture(|| Command::CreateShader {
+                stage,
+                entrypoint: entrypoint.map(String::from),
+                source: source.to_vec(),
+                flags,
+                shader,
+            });
+        }
+        #[cfg(feature = "validation")]
+        if let Ok(s) | Err(Error::CompileError(s, _)) = shader {
+            self.validator().create_shader(s, stage);
+        }
+        if let Ok(s) | Err(Error::CompileError(s, _)) = shader {
+            self.label_object(s, label);
+        }
 
         // If we're not in a verbose mode, just return the result of
         // the shader compilation.
@@ -515,7 +596,7 @@ impl Device {
         }
 
         match shader {
-            Ok(s) | Err(Error::CompileError(s)) => {
+            Ok(s) | Err(Error::CompileError(s, _)) => {
                 if shader.is_err() {
                     println!("Shader could not be compiled successfully ({:?})", stage);
                 }
@@ -532,7 +613,10 @@ impl Device {
     }
 
     /// Return the log, if any, from compiling the shader.
+    #[track_caller]
     pub unsafe fn get_shader_log(&self, shader: Shader) -> Option<String> {
+        let _scope = self.api_scope("get_shader_log");
+        self.check_handle("get_shader_log", shader);
         let mut len = {
             let mut len = 0;
             self.0
@@ -553,12 +637,30 @@ impl Device {
     }
 
     /// Delete a shader.
+    #[track_caller]
     pub unsafe fn delete_shader(&self, shader: Shader) {
+        let _scope = self.api_scope("delete_shader");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteShaders {
+            shaders: vec![shader],
+        });
+        self.delete_handles("delete_shader", &[shader]);
+        #[cfg(feature = "validation")]
+        self.validator().delete_shaders("delete_shader", &[shader]);
         self.0.DeleteShader(shader.0);
     }
 
     /// Delete multiple shaders.
+    #[track_caller]
     pub unsafe fn delete_shaders(&self, shaders: &[Shader]) {
+        let _scope = self.api_scope("delete_shaders");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteShaders {
+            shaders: shaders.to_vec(),
+        });
+        self.delete_handles("delete_shaders", shaders);
+        #[cfg(feature = "validation")]
+        self.validator().delete_shaders("delete_shaders", shaders);
         for shader in shaders.iter() {
             self.0.DeleteShader(shader.0);
         }
@@ -569,7 +671,10 @@ impl Device {
     ///
     /// - Ok(log) if the link was successful.
     /// - Err(log) if the link failed.
+    #[track_caller]
     pub unsafe fn get_pipeline_log(&self, pipeline: Pipeline) -> Option<String> {
+        let _scope = self.api_scope("get_pipeline_log");
+        self.check_handle("get_pipeline_log", pipeline);
         let mut len = {
             let mut len = 0;
             self.0
@@ -604,15 +709,20 @@ impl Device {
     ///   `ShaderStage::Geometry` if specified.
     /// - The fragment shader in `desc` must be valid and created with
     ///   `ShaderStage::Fragment` if specified.
+    #[track_caller]
     pub unsafe fn create_graphics_pipeline<D>(
         &self,
         desc: D,
         flags: PipelineFlags,
+        label: Option<&str>,
     ) -> Result<Pipeline>
     where
         D: Into<GraphicsPipelineDesc>,
     {
+        let _scope = self.api_scope("create_graphics_pipeline");
         let desc = desc.into();
+        #[cfg(feature = "validation")]
+        self.validator().create_graphics_pipeline(&desc);
 
         let shaders: Vec<_> = [
             desc.vertex_shader,
@@ -627,7 +737,7 @@ impl Device {
         .filter_map(|&x| x)
         .collect();
 
-        self.create_pipeline(&shaders, flags)
+        self.create_pipeline(&shaders, flags, label)
     }
 
     /// Create a compute pipeline.
@@ -637,12 +747,17 @@ impl Device {
     /// # Valid usage
     ///
     /// - The compute shader in must be valid and created with `ShaderStage::Compute`.
+    #[track_caller]
     pub unsafe fn create_compute_pipeline(
         &self,
         compute_shader: Shader,
         flags: PipelineFlags,
+        label: Option<&str>,
     ) -> Result<Pipeline> {
-        self.create_pipeline(&[compute_shader], flags)
+        let _scope = self.api_scope("create_compute_pipeline");
+        #[cfg(feature = "validation")]
+        self.validator().create_compute_pipeline(compute_shader);
+        self.create_pipeline(&[compute_shader], flags, label)
     }
 
     /// Create a generic pipeline with an arbitrary list of shaders.
@@ -653,13 +768,20 @@ impl Device {
     ///
     /// - The shaders must all be valid.
     /// - The shader stages must be mutually compatible.
+    #[track_caller]
     pub unsafe fn create_pipeline(
         &self,
         shaders: &[Shader],
         flags: PipelineFlags,
+        label: Option<&str>,
     ) -> Result<Pipeline> {
+        let _scope = self.api_scope("create_pipeline");
+        self.check_handles("create_pipeline", shaders);
         let pipeline = self.0.CreateProgram();
         self.get_error()?;
+        let generation = self.create_handle(ObjectType::Pipeline, pipeline);
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Pipeline, pipeline, 0);
 
         for shader in shaders {
             self.0.AttachShader(pipeline, shader.0);
@@ -677,19 +799,40 @@ impl Device {
                 status
             };
 
+            let pipeline = Pipeline(pipeline, generation);
             if status == GLint::from(__gl::TRUE) {
-                Ok(Pipeline(pipeline))
+                Ok(pipeline)
             } else {
-                Err(Error::LinkError(Pipeline(pipeline)))
+                let log = self.get_pipeline_log(pipeline).unwrap_or_default();
+                Err(Error::LinkError(pipeline, ShaderLog::new(log, None, None)))
             }
         };
+        #[cfg(feature = "capture")]
+        if let Ok(pipeline) = pipeline_result {
+            self.capture(|| Command::CreatePipeline {
+                shaders: shaders.to_vec(),
+                flags,
+                pipeline,
+            });
+        }
+        #[cfg(feature = "validation")]
+        self.validator().create_pipeline(
+            shaders,
+            match pipeline_result {
+                Ok(p) | Err(Error::LinkError(p, _)) => Some(p),
+                _ => None,
+            },
+        );
+        if let Ok(p) | Err(Error::LinkError(p, _)) = pipeline_result {
+            self.label_object(p, label);
+        }
 
         if !flags.contains(PipelineFlags::VERBOSE) {
             return pipeline_result;
         }
 
         match pipeline_result {
-            Ok(p) | Err(Error::LinkError(p)) => {
+            Ok(p) | Err(Error::LinkError(p, _)) => {
                 if pipeline_result.is_err() {
                     println!("Pipeline could not be linked.");
                 }
@@ -704,12 +847,36 @@ impl Device {
     }
 
     /// Delete a pipeline.
+    #[track_caller]
     pub unsafe fn delete_pipeline(&self, pipeline: Pipeline) {
+        let _scope = self.api_scope("delete_pipeline");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeletePipelines {
+            pipelines: vec![pipeline],
+        });
+        self.delete_handles("delete_pipeline", &[pipeline]);
+        #[cfg(feature = "validation")]
+        self.validator()
+            .delete_pipelines("delete_pipeline", &[pipeline]);
+        // Deleted names may be recycled while the program is still in use.
+        self.forget_state(StateKey::Program);
         self.0.DeleteProgram(pipeline.0);
     }
 
     /// Delete multiple pipelines.
+    #[track_caller]
     pub unsafe fn delete_pipelines(&self, pipelines: &[Pipeline]) {
+        let _scope = self.api_scope("delete_pipelines");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeletePipelines {
+            pipelines: pipelines.to_vec(),
+        });
+        self.delete_handles("delete_pipelines", pipelines);
+        #[cfg(feature = "validation")]
+        self.validator()
+            .delete_pipelines("delete_pipelines", pipelines);
+        // Deleted names may be recycled while the program is still in use.
+        self.forget_state(StateKey::Program);
         for pipeline in pipelines {
             self.0.DeleteProgram(pipeline.0);
         }
@@ -717,7 +884,10 @@ impl Device {
 
     /// For a compute pipeline, return the size of the work group
     /// defined in the main shader.
+    #[track_caller]
     pub unsafe fn get_work_group_size(&self, pipeline: Pipeline) -> Option<[i32; 3]> {
+        let _scope = self.api_scope("get_work_group_size");
+        self.check_handle("get_work_group_size", pipeline);
         // On error, `sizes` won't be changed. So, we can initialize
         // with negative values and check for success by looking for
         // non-negative values.
@@ -735,38 +905,56 @@ impl Device {
     }
 
     /// Bind input assembly pipeline state.
+    #[track_caller]
     pub unsafe fn bind_input_assembly_state(&self, state: InputAssembly) {
+        let _scope = self.api_scope("bind_input_assembly_state");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindInputAssemblyState { state });
         match state.primitive_restart {
             Some(index) => {
-                self.0.Enable(__gl::PRIMITIVE_RESTART);
-                self.0.PrimitiveRestartIndex(index);
+                self.set_capability(__gl::PRIMITIVE_RESTART, true);
+                if self.state_changed(StateKey::PrimitiveRestartIndex, &[index]) {
+                    self.0.PrimitiveRestartIndex(index);
+                }
             }
             None => {
-                self.0.Disable(__gl::PRIMITIVE_RESTART);
+                self.set_capability(__gl::PRIMITIVE_RESTART, false);
             }
         }
     }
 
     /// Bind color blending pipeline state.
+    #[track_caller]
     pub unsafe fn bind_color_blend_state(&self, state: &ColorBlend) {
+        let _scope = self.api_scope("bind_color_blend_state");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindColorBlendState {
+            state: state.clone(),
+        });
         for (i, attachment) in state.attachments.iter().enumerate() {
             let slot = i as u32;
             if attachment.blend_enable {
-                self.0.Enablei(__gl::BLEND, slot);
-                self.0.BlendEquationSeparatei(
-                    slot,
+                self.set_capability_indexed(__gl::BLEND, slot, true);
+                let equation = [
                     attachment.color.blend_op as _,
                     attachment.alpha.blend_op as _,
-                );
-                self.0.BlendFuncSeparatei(
-                    slot,
+                ];
+                if self.state_changed(StateKey::BlendEquation(slot), &equation) {
+                    self.0
+                        .BlendEquationSeparatei(slot, equation[0], equation[1]);
+                }
+                let func = [
                     attachment.color.src_factor as _,
                     attachment.color.dst_factor as _,
                     attachment.alpha.src_factor as _,
                     attachment.alpha.dst_factor as _,
-                );
+                ];
+                if self.state_changed(StateKey::BlendFunc(slot), &func) {
+                    self.0
+                        .BlendFuncSeparatei(slot, func[0], func[1], func[2], func[3]);
+                }
             } else {
-                self.0.Disablei(__gl::BLEND, slot);
+                self.set_capability_indexed(__gl::BLEND, slot, false);
             }
         }
     }
@@ -790,128 +978,137 @@ impl Device {
     /// });
     /// # }
     /// ```
+    #[track_caller]
     pub unsafe fn bind_depth_stencil_state(&self, state: &DepthStencil) {
+        let _scope = self.api_scope("bind_depth_stencil_state");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindDepthStencilState { state: *state });
+        self.set_capability(__gl::DEPTH_TEST, state.depth_test);
         if state.depth_test {
-            self.0.Enable(__gl::DEPTH_TEST);
-            self.0.DepthMask(if state.depth_write {
-                __gl::TRUE
-            } else {
-                __gl::FALSE
-            });
-            self.0.DepthFunc(state.depth_compare_op as _);
-        } else {
-            self.0.Disable(__gl::DEPTH_TEST);
+            if self.state_changed(StateKey::DepthMask, &[state.depth_write as _]) {
+                self.0.DepthMask(if state.depth_write {
+                    __gl::TRUE
+                } else {
+                    __gl::FALSE
+                });
+            }
+            if self.state_changed(StateKey::DepthFunc, &[state.depth_compare_op as _]) {
+                self.0.DepthFunc(state.depth_compare_op as _);
+            }
         }
 
+        self.set_capability(__gl::STENCIL_TEST, state.stencil_test);
         if state.stencil_test {
-            self.0.Enable(__gl::STENCIL_TEST);
-            self.0.StencilFuncSeparate(
-                __gl::FRONT,
-                state.stencil_front.compare_op as _,
-                state.stencil_front.reference as _,
-                state.stencil_front.compare_mask,
-            );
-            self.0.StencilOpSeparate(
-                __gl::FRONT,
-                state.stencil_front.fail as _,
-                state.stencil_front.depth_fail as _,
-                state.stencil_front.pass as _,
-            );
-            self.0.StencilFuncSeparate(
-                __gl::BACK,
-                state.stencil_back.compare_op as _,
-                state.stencil_back.reference as _,
-                state.stencil_back.compare_mask,
-            );
-            self.0.StencilOpSeparate(
-                __gl::BACK,
-                state.stencil_back.fail as _,
-                state.stencil_back.depth_fail as _,
-                state.stencil_back.pass as _,
-            );
-        } else {
-            self.0.Disable(__gl::STENCIL_TEST);
+            for &(face, stencil) in &[
+                (__gl::FRONT, &state.stencil_front),
+                (__gl::BACK, &state.stencil_back),
+            ] {
+                let func = [
+                    stencil.compare_op as _,
+                    stencil.reference,
+                    stencil.compare_mask,
+                ];
+                if self.state_changed(StateKey::StencilFunc(face), &func) {
+                    self.0
+                        .StencilFuncSeparate(face, func[0], func[1] as _, func[2]);
+                }
+                let op = [
+                    stencil.fail as _,
+                    stencil.depth_fail as _,
+                    stencil.pass as _,
+                ];
+                if self.state_changed(StateKey::StencilOp(face), &op) {
+                    self.0.StencilOpSeparate(face, op[0], op[1], op[2]);
+                }
+            }
         }
     }
 
     /// Bind rasterization pipeline state.
+    #[track_caller]
     pub unsafe fn bind_rasterization_state(&self, state: &Rasterization) {
-        if state.depth_clamp {
-            self.0.Enable(__gl::DEPTH_CLAMP);
-        } else {
-            self.0.Disable(__gl::DEPTH_CLAMP);
-        }
-
-        if state.rasterizer_discard {
-            self.0.Enable(__gl::RASTERIZER_DISCARD);
-        } else {
-            self.0.Disable(__gl::RASTERIZER_DISCARD);
-        }
+        let _scope = self.api_scope("bind_rasterization_state");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindRasterizationState { state: *state });
+        self.set_capability(__gl::DEPTH_CLAMP, state.depth_clamp);
+        self.set_capability(__gl::RASTERIZER_DISCARD, state.rasterizer_discard);
 
         let bias_primitive = match state.polygon_mode {
             PolygonMode::Point => __gl::POLYGON_OFFSET_POINT,
             PolygonMode::Line => __gl::POLYGON_OFFSET_LINE,
             PolygonMode::Fill => __gl::POLYGON_OFFSET_FILL,
         };
+        self.set_capability(bias_primitive, state.depth_bias);
 
-        if state.depth_bias {
-            self.0.Enable(bias_primitive);
-        } else {
-            self.0.Disable(bias_primitive);
+        if self.state_changed(StateKey::PolygonMode, &[state.polygon_mode as _]) {
+            self.0
+                .PolygonMode(__gl::FRONT_AND_BACK, state.polygon_mode as _);
+        }
+        if self.state_changed(StateKey::FrontFace, &[state.front_face as _]) {
+            self.0.FrontFace(state.front_face as _);
         }
-
-        self.0
-            .PolygonMode(__gl::FRONT_AND_BACK, state.polygon_mode as _);
-        self.0.FrontFace(state.front_face as _);
 
         match state.cull_mode {
             Some(cull) => {
-                self.0.Enable(__gl::CULL_FACE);
-                self.0.CullFace(cull as _);
+                self.set_capability(__gl::CULL_FACE, true);
+                if self.state_changed(StateKey::CullFace, &[cull as _]) {
+                    self.0.CullFace(cull as _);
+                }
             }
             None => {
-                self.0.Disable(__gl::CULL_FACE);
+                self.set_capability(__gl::CULL_FACE, false);
             }
         }
     }
 
+    #[track_caller]
     pub unsafe fn bind_multisample_state(&self, state: Option<&Multisample>) {
+        let _scope = self.api_scope("bind_multisample_state");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindMultisampleState {
+            state: state.copied(),
+        });
         match state {
             Some(state) => {
-                self.0.Enable(__gl::MULTISAMPLE);
-
-                if state.sample_shading {
-                    self.0.Enable(__gl::SAMPLE_SHADING);
+                self.set_capability(__gl::MULTISAMPLE, true);
+
+                self.set_capability(__gl::SAMPLE_SHADING, state.sample_shading);
+                if state.sample_shading
+                    && self.state_changed(
+                        StateKey::MinSampleShading,
+                        &[state.min_sample_shading.to_bits()],
+                    )
+                {
                     self.0.MinSampleShading(state.min_sample_shading);
-                } else {
-                    self.0.Disable(__gl::SAMPLE_SHADING);
                 }
 
-                self.0
-                    .SampleMaski(0, (state.sample_mask & 0xFFFF_FFFF) as _);
-                self.0
-                    .SampleMaski(1, ((state.sample_mask >> 32) & 0xFFFF_FFFF) as _);
-
-                if state.alpha_to_coverage {
-                    self.0.Enable(__gl::SAMPLE_ALPHA_TO_COVERAGE);
-                } else {
-                    self.0.Disable(__gl::SAMPLE_ALPHA_TO_COVERAGE);
+                for word in 0..2 {
+                    let mask = ((state.sample_mask >> (32 * word)) & 0xFFFF_FFFF) as u32;
+                    if self.state_changed(StateKey::SampleMask(word), &[mask]) {
+                        self.0.SampleMaski(word, mask);
+                    }
                 }
 
-                if state.alpha_to_one {
-                    self.0.Enable(__gl::SAMPLE_ALPHA_TO_ONE);
-                } else {
-                    self.0.Disable(__gl::SAMPLE_ALPHA_TO_ONE);
-                }
+                self.set_capability(__gl::SAMPLE_ALPHA_TO_COVERAGE, state.alpha_to_coverage);
+                self.set_capability(__gl::SAMPLE_ALPHA_TO_ONE, state.alpha_to_one);
             }
             None => {
-                self.0.Disable(__gl::MULTISAMPLE);
+                self.set_capability(__gl::MULTISAMPLE, false);
             }
         }
     }
 
     /// Bind a pipeline for usage.
+    #[track_caller]
     pub unsafe fn bind_pipeline(&self, pipeline: Pipeline) {
-        self.0.UseProgram(pipeline.0);
+        let _scope = self.api_scope("bind_pipeline");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindPipeline { pipeline });
+        self.check_handle("bind_pipeline", pipeline);
+        #[cfg(feature = "validation")]
+        self.validator().bind_pipeline(pipeline);
+        if self.state_changed(StateKey::Program, &[pipeline.0]) {
+            self.0.UseProgram(pipeline.0);
+        }
     }
 }
@@ -0,0 +1,141 @@
+//! Plain old data
+//!
+//! Host data copied into or mapped from device memory is reinterpreted as raw bytes.
+//! `Pod` marks types for which every byte pattern is a valid value and which don't contain
+//! padding, pointers or references.
+//!
+//! User structs implement `Pod` via the [`pod!`](macro.pod.html) macro, which checks the
+//! layout at compile time. With the `bytemuck` feature, types implementing `bytemuck::Pod`
+//! can be passed via the [`Bytemuck`](struct.Bytemuck.html) wrapper.
+
+use std::marker::PhantomData;
+use std::{mem, slice};
+
+/// Plain old data types.
+///
+/// # Safety
+///
+/// Implementing types must be `repr(C)` or `repr(transparent)`, must not contain padding
+/// and must be valid for any bit pattern. Prefer the [`pod!`](macro.pod.html) macro
+/// over manual implementations.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+impl_pod!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Element size of device memory viewed as a slice of `T`.
+pub(crate) struct Stride<T>(PhantomData<T>);
+
+impl<T: Pod> Stride<T> {
+    /// Size in bytes of `T`, fails to compile for zero-sized types (e.g. `[T; 0]`).
+    pub(crate) const SIZE: usize = {
+        assert!(
+            mem::size_of::<T>() != 0,
+            "zero-sized types can't be used as elements of device memory"
+        );
+        mem::size_of::<T>()
+    };
+}
+
+/// Wrapper implementing `Pod` for types implementing `bytemuck::Pod`.
+///
+/// ```
+/// # #[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+/// # #[repr(C)]
+/// # struct Vertex {
+/// #     pos: [f32; 2],
+/// # }
+/// let vertices = [Vertex { pos: [0.0; 2] }; 3];
+/// let data = grr::as_bytes(grr::Bytemuck::from_slice(&vertices));
+/// assert_eq!(data.len(), 24);
+/// ```
+#[cfg(feature = "bytemuck")]
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Bytemuck<T>(pub T);
+
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: bytemuck::Pod> Pod for Bytemuck<T> {}
+
+#[cfg(feature = "bytemuck")]
+impl<T: bytemuck::Pod> Bytemuck<T> {
+    /// View a slice of `bytemuck::Pod` values as wrapped values.
+    pub fn from_slice(data: &[T]) -> &[Bytemuck<T>] {
+        unsafe { slice::from_raw_parts(data.as_ptr() as *const Bytemuck<T>, data.len()) }
+    }
+
+    /// View a mutable slice of `bytemuck::Pod` values as wrapped values.
+    pub fn from_slice_mut(data: &mut [T]) -> &mut [Bytemuck<T>] {
+        unsafe { slice::from_raw_parts_mut(data.as_mut_ptr() as *mut Bytemuck<T>, data.len()) }
+    }
+}
+
+/// Define `repr(C)` structs implementing [`Pod`](trait.Pod.html).
+///
+/// The structs additionally derive `Clone` and `Copy`. All fields need to implement `Pod`
+/// and the struct must not contain padding, otherwise compilation fails.
+///
+/// ```
+/// grr::pod! {
+///     #[derive(Debug)]
+///     pub struct Vertex {
+///         pub pos: [f32; 3],
+///         pub uv: [f32; 2],
+///     }
+/// }
+///
+/// assert_eq!(grr::as_bytes(&[Vertex { pos: [0.0; 3], uv: [0.0; 2] }]).len(), 20);
+/// ```
+///
+/// ```compile_fail
+/// grr::pod! {
+///     struct Padded {
+///         a: u8,
+///         b: u32,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! pod {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident: $ty:ty),+ $(,)?
+        }
+    )*) => {$(
+        $(#[$attr])*
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),+
+        }
+
+        const _: () = {
+            const fn is_pod<T: $crate::Pod>() {}
+            $(is_pod::<$ty>();)+
+            assert!(
+                ::std::mem::size_of::<$name>() == 0 $(+ ::std::mem::size_of::<$ty>())+,
+                concat!("`", stringify!($name), "` contains padding"),
+            );
+        };
+
+        unsafe impl $crate::Pod for $name {}
+    )*};
+}
+
+/// View a slice of plain old data as raw bytes.
+pub fn as_bytes<T: Pod>(data: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
+}
+
+/// View a mutable slice of plain old data as raw bytes.
+pub fn as_bytes_mut<T: Pod>(data: &mut [T]) -> &mut [u8] {
+    unsafe { slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, mem::size_of_val(data)) }
+}
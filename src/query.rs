@@ -1,11 +1,16 @@
 use crate::__gl;
 use crate::__gl::types::GLuint;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
+use crate::debug::{Object, ObjectType};
 use crate::device::Device;
+use crate::handle::{Generation, Handle};
 
 ///
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QueryType {
     ///
     Timestamp = __gl::TIMESTAMP,
@@ -56,6 +61,7 @@ bitflags!(
 ///
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConditionalMode {
     ///
     NoWait = __gl::QUERY_NO_WAIT,
@@ -71,20 +77,73 @@ pub enum ConditionalMode {
     WaitByRegionInverted = __gl::QUERY_BY_REGION_WAIT_INVERTED,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query {
-    raw: GLuint,
-    ty: QueryType,
+    pub(crate) raw: GLuint,
+    pub(crate) ty: QueryType,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) generation: Generation,
+}
+
+impl Object for Query {
+    const TYPE: ObjectType = ObjectType::Query;
+    fn handle(&self) -> GLuint {
+        self.raw
+    }
+}
+
+impl Handle for Query {
+    fn generation(&self) -> Generation {
+        self.generation
+    }
 }
 
 impl Device {
-    pub unsafe fn create_query(&self, ty: QueryType) -> Query {
+    #[track_caller]
+    pub unsafe fn create_query(&self, ty: QueryType, label: Option<&str>) -> Query {
+        let _scope = self.api_scope("create_query");
         let mut query = 0;
         self.0.CreateQueries(ty as _, 1, &mut query as *mut _);
-        Query { raw: query, ty }
+        let query = Query {
+            raw: query,
+            ty,
+            generation: self.create_handle(ObjectType::Query, query),
+        };
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Query, query.raw, 0);
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateQuery { ty, query });
+        self.label_object(query, label);
+        query
+    }
+
+    /// Delete a query.
+    #[track_caller]
+    pub unsafe fn delete_query(&self, query: Query) {
+        let _scope = self.api_scope("delete_query");
+        self.delete_queries(&[query]);
+    }
+
+    /// Delete multiple queries.
+    #[track_caller]
+    pub unsafe fn delete_queries(&self, queries: &[Query]) {
+        let _scope = self.api_scope("delete_queries");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteQueries {
+            queries: queries.to_vec(),
+        });
+        self.delete_handles("delete_queries", queries);
+        let queries = queries.iter().map(|query| query.raw).collect::<Vec<_>>();
+        self.0.DeleteQueries(queries.len() as _, queries.as_ptr());
     }
 
+    #[track_caller]
     pub unsafe fn begin_query(&self, query: Query) {
+        let _scope = self.api_scope("begin_query");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BeginQuery { query });
+        self.check_handle("begin_query", query);
         #[allow(clippy::match_single_binding)]
         let index = match query.ty {
             _ => 0,
@@ -93,7 +152,12 @@ impl Device {
         self.0.BeginQueryIndexed(query.ty as _, index, query.raw);
     }
 
+    #[track_caller]
     pub unsafe fn end_query(&self, query: Query) {
+        let _scope = self.api_scope("end_query");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::EndQuery { query });
+        self.check_handle("end_query", query);
         #[allow(clippy::match_single_binding)]
         let index = match query.ty {
             _ => 0,
@@ -102,11 +166,19 @@ impl Device {
         self.0.EndQueryIndexed(query.ty as _, index);
     }
 
+    #[track_caller]
     pub unsafe fn write_timestamp(&self, query: Query) {
+        let _scope = self.api_scope("write_timestamp");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::WriteTimestamp { query });
+        self.check_handle("write_timestamp", query);
         self.0.QueryCounter(query.raw, __gl::TIMESTAMP);
     }
 
+    #[track_caller]
     pub unsafe fn get_query_result_u32(&self, query: Query, flags: QueryResultFlags) -> u32 {
+        let _scope = self.api_scope("get_query_result_u32");
+        self.check_handle("get_query_result_u32", query);
         let mut result = 0;
         let flags = if flags.contains(QueryResultFlags::WAIT) {
             __gl::QUERY_RESULT
@@ -117,7 +189,10 @@ impl Device {
         result
     }
 
+    #[track_caller]
     pub unsafe fn get_query_result_u64(&self, query: Query, flags: QueryResultFlags) -> u64 {
+        let _scope = self.api_scope("get_query_result_u64");
+        self.check_handle("get_query_result_u64", query);
         let mut result = 0;
         let flags = if flags.contains(QueryResultFlags::WAIT) {
             __gl::QUERY_RESULT
@@ -128,11 +203,20 @@ impl Device {
         result
     }
 
+    #[track_caller]
     pub unsafe fn begin_conditional_rendering(&self, query: Query, mode: ConditionalMode) {
+        let _scope = self.api_scope("begin_conditional_rendering");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BeginConditionalRendering { query, mode });
+        self.check_handle("begin_conditional_rendering", query);
         self.0.BeginConditionalRender(query.raw, mode as _);
     }
 
+    #[track_caller]
     pub unsafe fn end_conditional_rendering(&self) {
+        let _scope = self.api_scope("end_conditional_rendering");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::EndConditionalRendering);
         self.0.EndConditionalRender();
     }
 }
@@ -0,0 +1,345 @@
+//! Asynchronous readback
+
+use crate::buffer::{Buffer, BufferRange, MappingFlags, MemoryFlags};
+use crate::device::Device;
+use crate::error::{Error, Result};
+use crate::image::Image;
+use crate::pod::{Pod, Stride};
+use crate::sync::{Fence, FenceStatus};
+use crate::transfer::{BufferImageCopy, HostImageCopy, MemoryLayout};
+use crate::Region;
+
+use std::ops::Range;
+use std::{mem, slice};
+
+/// Minimal size in bytes of staging buffers.
+const MIN_STAGING_SIZE: u64 = 4 << 10;
+
+/// Persistently mapped staging buffer.
+#[derive(Debug)]
+struct Staging {
+    buffer: Buffer,
+    ptr: *const u8,
+    size: u64,
+}
+
+/// Pool of staging buffers for asynchronous readbacks.
+///
+/// Staging buffers are persistently mapped for reading and reused once the
+/// readback using them is released.
+///
+/// ```no_run
+/// # unsafe fn readback(grr: &grr::Device, buffer: grr::Buffer) -> grr::Result<()> {
+/// let mut pool = grr.create_readback_pool(Some("readback"));
+/// let mut readback = grr.readback_buffer(&mut pool, buffer, 0..256)?;
+/// // ..
+/// if grr.poll_readback(&mut readback)? {
+///     let values = readback.data_as::<u32>().unwrap();
+///     // ..
+/// }
+/// grr.release_readback(&mut pool, readback);
+/// grr.delete_readback_pool(pool);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ReadbackPool {
+    label: Option<String>,
+    free: Vec<Staging>,
+    allocated: u64,
+    pending: usize,
+}
+
+// Mapped memory is not tied to a thread.
+unsafe impl Send for ReadbackPool {}
+
+impl ReadbackPool {
+    /// Size in bytes of all staging buffers of the pool.
+    pub fn allocated(&self) -> u64 {
+        self.allocated
+    }
+
+    /// Number of readbacks not released yet.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+}
+
+/// Pending copy of device memory into a staging buffer.
+///
+/// The data is available once `Device::poll_readback` or `Device::wait_readback`
+/// report completion. Needs to be returned to its pool via `Device::release_readback`.
+#[derive(Debug)]
+#[must_use = "readbacks need to be released to their pool"]
+pub struct Readback {
+    staging: Staging,
+    size: u64,
+    fence: Fence,
+    ready: bool,
+}
+
+// Mapped memory and sync objects are not tied to a thread.
+unsafe impl Send for Readback {}
+
+impl Readback {
+    /// Size in bytes of the read data.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns if the copy has been completed.
+    ///
+    /// Only updated by `Device::poll_readback` and `Device::wait_readback`.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Read data, `None` if the copy hasn't been completed yet.
+    pub fn data(&self) -> Option<&[u8]> {
+        if self.ready {
+            Some(unsafe { slice::from_raw_parts(self.staging.ptr, self.size as _) })
+        } else {
+            None
+        }
+    }
+
+    /// Read data as elements of type `T`, `None` if the copy hasn't been completed yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size isn't a multiple of the size of `T`.
+    pub fn data_as<T: Pod>(&self) -> Option<&[T]> {
+        let data = self.data()?;
+        let stride = Stride::<T>::SIZE;
+        assert_eq!(
+            data.len() % stride,
+            0,
+            "readback size ({}) is not a multiple of the element size ({})",
+            data.len(),
+            stride
+        );
+        assert_eq!(
+            data.as_ptr() as usize % mem::align_of::<T>(),
+            0,
+            "staging memory is not aligned for the element type"
+        );
+        Some(unsafe { slice::from_raw_parts(data.as_ptr() as *const T, data.len() / stride) })
+    }
+}
+
+impl Device {
+    /// Create an empty pool of staging buffers.
+    ///
+    /// # Parameters
+    ///
+    /// - `label`: Optional debug name of the staging buffers (see `object_name`).
+    pub fn create_readback_pool(&self, label: Option<&str>) -> ReadbackPool {
+        ReadbackPool {
+            label: label.map(String::from),
+            free: Vec::new(),
+            allocated: 0,
+            pending: 0,
+        }
+    }
+
+    /// Delete a readback pool and all its staging buffers.
+    ///
+    /// # Valid usage
+    ///
+    /// - All readbacks of the pool must have been released.
+    #[track_caller]
+    pub unsafe fn delete_readback_pool(&self, pool: ReadbackPool) {
+        let _scope = self.api_scope("delete_readback_pool");
+        debug_assert_eq!(pool.pending, 0, "readback pool has pending readbacks");
+        for staging in pool.free {
+            self.unmap_buffer(staging.buffer);
+            self.delete_buffer(staging.buffer);
+        }
+    }
+
+    /// Take the smallest free staging buffer of at least `size` bytes or create a new one.
+    unsafe fn acquire_staging(&self, pool: &mut ReadbackPool, size: u64) -> Result<Staging> {
+        let best = pool
+            .free
+            .iter()
+            .enumerate()
+            .filter(|(_, staging)| staging.size >= size)
+            .min_by_key(|(_, staging)| staging.size)
+            .map(|(i, _)| i);
+        if let Some(i) = best {
+            return Ok(pool.free.swap_remove(i));
+        }
+
+        let size = size.max(MIN_STAGING_SIZE).next_power_of_two();
+        let buffer = self.create_buffer(
+            size,
+            MemoryFlags::CPU_MAP_READ | MemoryFlags::COHERENT,
+            pool.label.as_deref(),
+        )?;
+        let ptr = self
+            .map_buffer::<u8>(buffer, 0..size, MappingFlags::empty())
+            .as_ptr();
+        pool.allocated += size;
+        Ok(Staging { buffer, ptr, size })
+    }
+
+    /// Fence the copy into the staging buffer.
+    unsafe fn submit_readback(
+        &self,
+        pool: &mut ReadbackPool,
+        staging: Staging,
+        size: u64,
+    ) -> Readback {
+        pool.pending += 1;
+        Readback {
+            staging,
+            size,
+            fence: self.create_fence(),
+            ready: false,
+        }
+    }
+
+    /// Read a range of a buffer back to the host.
+    ///
+    /// # Valid usage
+    ///
+    /// - `range.start` must be smaller than `range.end`.
+    /// - `range.end` may not be larger than the size of the buffer.
+    #[track_caller]
+    pub unsafe fn readback_buffer(
+        &self,
+        pool: &mut ReadbackPool,
+        buffer: Buffer,
+        range: Range<u64>,
+    ) -> Result<Readback> {
+        let _scope = self.api_scope("readback_buffer");
+        let size = range.end.saturating_sub(range.start);
+        let staging = self.acquire_staging(pool, size)?;
+        self.copy_buffer(buffer, range.start, staging.buffer, 0, size);
+        Ok(self.submit_readback(pool, staging, size))
+    }
+
+    /// Read a region of an image subresource back to the host.
+    ///
+    /// The data is laid out according to `region.host_layout`.
+    ///
+    /// # Valid usage
+    ///
+    /// - The extent of the region must not be empty.
+    /// - See `copy_image_to_host`.
+    #[track_caller]
+    pub unsafe fn readback_image(
+        &self,
+        pool: &mut ReadbackPool,
+        image: Image,
+        region: HostImageCopy,
+    ) -> Result<Readback> {
+        let _scope = self.api_scope("readback_image");
+        let (_, extent) = Self::map_subresource_region(
+            image,
+            &region.image_subresource,
+            region.image_offset,
+            region.image_extent,
+        );
+        let size = readback_size(
+            &region.host_layout,
+            (extent.width, extent.height, extent.depth),
+        )?;
+        let staging = self.acquire_staging(pool, size)?;
+        self.copy_image_to_buffer(
+            image,
+            staging.buffer,
+            BufferImageCopy {
+                buffer_offset: 0,
+                buffer_layout: region.host_layout,
+                image_subresource: region.image_subresource,
+                image_offset: region.image_offset,
+                image_extent: region.image_extent,
+            },
+        );
+        Ok(self.submit_readback(pool, staging, size))
+    }
+
+    /// Read a region of the current read framebuffer back to the host.
+    ///
+    /// The data is laid out according to `layout`.
+    ///
+    /// # Valid usage
+    ///
+    /// - The region must not be empty.
+    /// - See `copy_attachment_to_host`.
+    #[track_caller]
+    pub unsafe fn readback_attachment(
+        &self,
+        pool: &mut ReadbackPool,
+        region: Region,
+        layout: MemoryLayout,
+    ) -> Result<Readback> {
+        let _scope = self.api_scope("readback_attachment");
+        let size = readback_size(&layout, (region.w as _, region.h as _, 1))?;
+        let staging = self.acquire_staging(pool, size)?;
+        self.copy_attachment_to_buffer(
+            region,
+            layout,
+            BufferRange {
+                buffer: staging.buffer,
+                offset: 0,
+                size,
+            },
+        );
+        Ok(self.submit_readback(pool, staging, size))
+    }
+
+    /// Check if a readback has been completed, without blocking.
+    #[track_caller]
+    pub unsafe fn poll_readback(&self, readback: &mut Readback) -> Result<bool> {
+        let _scope = self.api_scope("poll_readback");
+        self.wait_readback(readback, 0)
+    }
+
+    /// Wait until a readback has been completed.
+    ///
+    /// # Parameters
+    ///
+    /// - `timeout`: Timeout in nanoseconds. `0` only polls, `u64::MAX` blocks until completed.
+    ///
+    /// # Return
+    ///
+    /// Returns if the readback has been completed.
+    #[track_caller]
+    pub unsafe fn wait_readback(&self, readback: &mut Readback, timeout: u64) -> Result<bool> {
+        let _scope = self.api_scope("wait_readback");
+        if !readback.ready {
+            readback.ready =
+                self.wait_fence(readback.fence, timeout)? != FenceStatus::TimeoutExpired;
+        }
+        Ok(readback.ready)
+    }
+
+    /// Return the staging buffer of a readback to its pool.
+    ///
+    /// Pending readbacks don't need to be completed.
+    ///
+    /// # Valid usage
+    ///
+    /// - `readback` must have been created from `pool`.
+    #[track_caller]
+    pub unsafe fn release_readback(&self, pool: &mut ReadbackPool, readback: Readback) {
+        let _scope = self.api_scope("release_readback");
+        self.delete_fence(readback.fence);
+        pool.pending -= 1;
+        pool.free.push(readback.staging);
+    }
+}
+
+/// Number of bytes of a pixel transfer into a staging buffer.
+fn readback_size(layout: &MemoryLayout, texels: (u32, u32, u32)) -> Result<u64> {
+    if texels.0 == 0 || texels.1 == 0 || texels.2 == 0 {
+        return Err(Error::InvalidUsage("readback region is empty".into()));
+    }
+    layout.transfer_size(texels).ok_or_else(|| {
+        Error::InvalidUsage(
+            "packed depth stencil layouts require `BaseFormat::DepthStencil`".into(),
+        )
+    })
+}
@@ -0,0 +1,775 @@
+//! Safe resource wrappers.
+//!
+//! Owned resources are tied to the lifetime of the device they have been created from
+//! and delete themselves on drop. Methods validate their arguments against the
+//! `Valid usage` rules of the underlying device functions and return
+//! `Error::InvalidUsage` instead of calling into GL with invalid arguments.
+//!
+//! The contract of the unsafe device constructors still applies: the context of the device
+//! must be alive and current on the calling thread whenever the device or one of
+//! its resources is used.
+//!
+//! Raw handles can be accessed via `raw` for usage with the unsafe device API.
+//! The owned resource must outlive every usage of the raw handle.
+
+use crate::{
+    Buffer, Device, Error, Extent, Format, Framebuffer, Image, ImageType, ImageView, ImageViewType,
+    MappingFlags, MemoryFlags, MemoryLayout, Offset, Pipeline, PipelineFlags, Renderbuffer, Result,
+    Sampler, SamplerDesc, Shader, ShaderFlags, ShaderSource, ShaderStage, SubresourceLayers,
+    SubresourceRange, VertexArray, VertexAttributeDesc,
+};
+
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Range};
+use std::{fmt, mem, slice};
+
+fn invalid<T>(msg: String) -> Result<T> {
+    Err(Error::InvalidUsage(msg))
+}
+
+/// Defines an owned resource type without additional metadata.
+macro_rules! owned {
+    ($(#[$attr:meta])* $name:ident($raw:ident) => $delete:ident) => {
+        $(#[$attr])*
+        pub struct $name<'d> {
+            device: &'d Device,
+            raw: $raw,
+        }
+
+        impl<'d> $name<'d> {
+            /// Device owning the resource.
+            pub fn device(&self) -> &'d Device {
+                self.device
+            }
+
+            /// Raw resource handle.
+            pub fn raw(&self) -> $raw {
+                self.raw
+            }
+
+            /// Release ownership of the raw resource handle without deleting it.
+            pub fn into_raw(self) -> $raw {
+                let raw = self.raw;
+                mem::forget(self);
+                raw
+            }
+        }
+
+        impl Drop for $name<'_> {
+            fn drop(&mut self) {
+                unsafe { self.device.$delete(self.raw) }
+            }
+        }
+
+        impl fmt::Debug for $name<'_> {
+            fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                fmt.debug_tuple(stringify!($name)).field(&self.raw).finish()
+            }
+        }
+    };
+}
+
+/// Owned buffer.
+pub struct OwnedBuffer<'d> {
+    device: &'d Device,
+    raw: Buffer,
+    size: u64,
+    memory: MemoryFlags,
+}
+
+impl<'d> OwnedBuffer<'d> {
+    /// Create a new empty buffer.
+    ///
+    /// See `Device::create_buffer`.
+    #[track_caller]
+    pub fn new(device: &'d Device, size: u64, memory: MemoryFlags) -> Result<Self> {
+        if size == 0 {
+            return invalid("create_buffer: `size` must be non-zero".into());
+        }
+        let raw = unsafe { device.create_buffer(size, memory, None)? };
+        Ok(OwnedBuffer {
+            device,
+            raw,
+            size,
+            memory,
+        })
+    }
+
+    /// Create a new buffer from host memory data.
+    ///
+    /// See `Device::create_buffer_from_host`.
+    #[track_caller]
+    pub fn from_host(device: &'d Device, data: &[u8], memory: MemoryFlags) -> Result<Self> {
+        if data.is_empty() {
+            return invalid("create_buffer_from_host: `data` must be non-empty".into());
+        }
+        let raw = unsafe { device.create_buffer_from_host(data, memory, None)? };
+        Ok(OwnedBuffer {
+            device,
+            raw,
+            size: data.len() as _,
+            memory,
+        })
+    }
+
+    /// Device owning the resource.
+    pub fn device(&self) -> &'d Device {
+        self.device
+    }
+
+    /// Raw resource handle.
+    pub fn raw(&self) -> Buffer {
+        self.raw
+    }
+
+    /// Release ownership of the raw resource handle without deleting it.
+    pub fn into_raw(self) -> Buffer {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// Size of the buffer in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Memory properties specified on creation.
+    pub fn memory(&self) -> MemoryFlags {
+        self.memory
+    }
+
+    fn check_region(&self, call: &str, offset: u64, size: u64) -> Result<()> {
+        match offset.checked_add(size) {
+            Some(end) if end <= self.size => Ok(()),
+            _ => invalid(format!(
+                "{}: region {}+{} exceeds the buffer size ({})",
+                call, offset, size, self.size
+            )),
+        }
+    }
+
+    /// Copy memory from the host into the buffer.
+    ///
+    /// The buffer must be created with `MemoryFlags::DYNAMIC`.
+    #[track_caller]
+    pub fn write(&self, offset: u64, data: &[u8]) -> Result<()> {
+        if !self.memory.contains(MemoryFlags::DYNAMIC) {
+            return invalid("write: buffer must be created with `DYNAMIC`".into());
+        }
+        self.check_region("write", offset, data.len() as _)?;
+        unsafe { self.device.copy_host_to_buffer(self.raw, offset as _, data) };
+        Ok(())
+    }
+
+    /// Copy a region of the buffer into another buffer.
+    ///
+    /// Source and destination region may not overlap.
+    #[track_caller]
+    pub fn copy_to(
+        &self,
+        src_offset: u64,
+        dst: &OwnedBuffer,
+        dst_offset: u64,
+        size: u64,
+    ) -> Result<()> {
+        self.check_region("copy_to", src_offset, size)?;
+        dst.check_region("copy_to", dst_offset, size)?;
+        if self.raw.0 == dst.raw.0
+            && src_offset < dst_offset + size
+            && dst_offset < src_offset + size
+        {
+            return invalid("copy_to: source and destination regions overlap".into());
+        }
+        unsafe {
+            self.device
+                .copy_buffer(self.raw, src_offset, dst.raw, dst_offset, size)
+        };
+        Ok(())
+    }
+
+    /// Map a region of the buffer into host memory.
+    ///
+    /// The buffer must be created with `CPU_MAP_READ` or `CPU_MAP_WRITE` and is unmapped
+    /// when the returned mapping is dropped.
+    #[track_caller]
+    pub fn map(&mut self, range: Range<u64>, mapping: MappingFlags) -> Result<MappedBuffer<'_>> {
+        if !self
+            .memory
+            .intersects(MemoryFlags::CPU_MAP_READ | MemoryFlags::CPU_MAP_WRITE)
+        {
+            return invalid(
+                "map: buffer must be created with `CPU_MAP_READ` or `CPU_MAP_WRITE`".into(),
+            );
+        }
+        if range.end <= range.start {
+            return invalid(format!(
+                "map: `range.end` ({}) must be larger than `range.start` ({})",
+                range.end, range.start
+            ));
+        }
+        self.check_region("map", range.start, range.end - range.start)?;
+        let write_only = MappingFlags::INVALIDATE_RANGE
+            | MappingFlags::INVALIDATE_BUFFER
+            | MappingFlags::FLUSH_EXPLICIT;
+        if mapping.intersects(write_only) && !self.memory.contains(MemoryFlags::CPU_MAP_WRITE) {
+            return invalid(format!(
+                "map: `{:?}` requires the buffer to be created with `CPU_MAP_WRITE`",
+                mapping & write_only
+            ));
+        }
+
+        let data = unsafe { self.device.map_buffer::<u8>(self.raw, range, mapping) };
+        Ok(MappedBuffer {
+            device: self.device,
+            raw: self.raw,
+            ptr: data.as_mut_ptr(),
+            len: data.len(),
+            writable: self.memory.contains(MemoryFlags::CPU_MAP_WRITE),
+            flush_explicit: mapping.contains(MappingFlags::FLUSH_EXPLICIT),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl Drop for OwnedBuffer<'_> {
+    fn drop(&mut self) {
+        unsafe { self.device.delete_buffer(self.raw) }
+    }
+}
+
+impl fmt::Debug for OwnedBuffer<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("OwnedBuffer")
+            .field("raw", &self.raw)
+            .field("size", &self.size)
+            .field("memory", &self.memory)
+            .finish()
+    }
+}
+
+/// Host mapped region of a buffer.
+///
+/// Borrows the buffer mutably for the duration of the mapping.
+/// If the buffer hasn't been created with `CPU_MAP_READ` the contents of the mapping are undefined.
+///
+/// Writes to mappings with `FLUSH_EXPLICIT` need to be flushed via `flush`.
+///
+/// # Panics
+///
+/// Mutable access panics if the buffer hasn't been created with `CPU_MAP_WRITE`.
+pub struct MappedBuffer<'b> {
+    device: &'b Device,
+    raw: Buffer,
+    ptr: *mut u8,
+    len: usize,
+    writable: bool,
+    flush_explicit: bool,
+    _marker: PhantomData<&'b mut [u8]>,
+}
+
+impl MappedBuffer<'_> {
+    /// Flush host writes to a subrange of the mapping, relative to its start.
+    #[track_caller]
+    pub fn flush(&self, range: Range<u64>) -> Result<()> {
+        if !self.flush_explicit {
+            return invalid("flush: buffer is not mapped with `FLUSH_EXPLICIT`".into());
+        }
+        if range.end <= range.start || range.end > self.len as u64 {
+            return invalid(format!(
+                "flush: range {:?} is empty or exceeds the mapping (size {})",
+                range, self.len
+            ));
+        }
+        unsafe { self.device.flush_mapped_buffer_range(self.raw, range) };
+        Ok(())
+    }
+}
+
+impl Deref for MappedBuffer<'_> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for MappedBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        assert!(self.writable, "buffer is not mapped for writing");
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for MappedBuffer<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.unmap_buffer(self.raw);
+        }
+    }
+}
+
+/// Owned image.
+pub struct OwnedImage<'d> {
+    device: &'d Device,
+    raw: Image,
+    ty: ImageType,
+    format: Format,
+    levels: u32,
+}
+
+impl<'d> OwnedImage<'d> {
+    /// Create a new image.
+    ///
+    /// See `Device::create_image`.
+    #[track_caller]
+    pub fn new(device: &'d Device, ty: ImageType, format: Format, levels: u32) -> Result<Self> {
+        let extent = ty.full_extent();
+        if extent.width == 0 || extent.height == 0 || extent.depth == 0 || ty.layers() == 0 {
+            return invalid("create_image: image dimensions must be non-zero".into());
+        }
+        let max_levels = 32
+            - extent
+                .width
+                .max(extent.height)
+                .max(extent.depth)
+                .leading_zeros();
+        if levels == 0 || levels > max_levels {
+            return invalid(format!(
+                "create_image: `levels` ({}) must be in range 1..={}",
+                levels, max_levels
+            ));
+        }
+        if let ImageType::D2 { samples, .. } = ty {
+            if samples == 0 {
+                return invalid("create_image: `samples` must be non-zero".into());
+            }
+            if samples > 1 {
+                return invalid("create_image: multisampled images are not supported".into());
+            }
+        }
+
+        let raw = unsafe { device.create_image(ty, format, levels, None)? };
+        Ok(OwnedImage {
+            device,
+            raw,
+            ty,
+            format,
+            levels,
+        })
+    }
+
+    /// Device owning the resource.
+    pub fn device(&self) -> &'d Device {
+        self.device
+    }
+
+    /// Raw resource handle.
+    pub fn raw(&self) -> Image {
+        self.raw
+    }
+
+    /// Release ownership of the raw resource handle without deleting it.
+    pub fn into_raw(self) -> Image {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// Image dimensions.
+    pub fn ty(&self) -> ImageType {
+        self.ty
+    }
+
+    /// Image format.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Number of mipmap levels.
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+
+    /// Create an image view covering a subresource range of the image.
+    ///
+    /// Image views keep the storage of the image alive.
+    #[track_caller]
+    pub fn create_view(
+        &self,
+        ty: ImageViewType,
+        format: Format,
+        range: SubresourceRange,
+    ) -> Result<OwnedImageView<'d>> {
+        if range.levels.start >= range.levels.end || range.levels.end > self.levels {
+            return invalid(format!(
+                "create_view: level range {:?} is empty or exceeds the image levels ({})",
+                range.levels, self.levels
+            ));
+        }
+        if range.layers.start >= range.layers.end || range.layers.end > self.ty.layers() {
+            return invalid(format!(
+                "create_view: layer range {:?} is empty or exceeds the image layers ({})",
+                range.layers,
+                self.ty.layers()
+            ));
+        }
+        let raw = unsafe {
+            self.device
+                .create_image_view(self.raw, ty, format, range, None)?
+        };
+        Ok(OwnedImageView {
+            device: self.device,
+            raw,
+        })
+    }
+
+    /// Returns the number of texels (width, height, depth) of a copy region in memory.
+    fn check_region(
+        &self,
+        call: &str,
+        subresource: &SubresourceLayers,
+        offset: Offset,
+        extent: Extent,
+    ) -> Result<(u32, u32, u32)> {
+        if subresource.level >= self.levels {
+            return invalid(format!(
+                "{}: level {} exceeds the image levels ({})",
+                call, subresource.level, self.levels
+            ));
+        }
+        let layers = &subresource.layers;
+        if layers.start >= layers.end || layers.end > self.ty.layers() {
+            return invalid(format!(
+                "{}: layer range {:?} is empty or exceeds the image layers ({})",
+                call,
+                layers,
+                self.ty.layers()
+            ));
+        }
+
+        let level_extent = |size: u32| (size >> subresource.level).max(1);
+        let dims = [
+            (offset.x, extent.width, level_extent(self.ty.width())),
+            (offset.y, extent.height, level_extent(self.ty.height())),
+            (offset.z, extent.depth, level_extent(self.ty.depth())),
+        ];
+        for &(start, size, max) in dims.iter() {
+            if start < 0 || size == 0 || start as u64 + size as u64 > max as u64 {
+                return invalid(format!(
+                    "{}: region {:?} {:?} exceeds the image level extent",
+                    call, offset, extent
+                ));
+            }
+        }
+
+        let num_layers = layers.end - layers.start;
+        Ok(match self.ty {
+            ImageType::D1 { .. } => (extent.width, num_layers, 1),
+            ImageType::D2 { .. } => (extent.width, extent.height, num_layers),
+            ImageType::D3 { .. } => (extent.width, extent.height, extent.depth),
+        })
+    }
+
+    /// Copy image data from host memory into a region of the image.
+    ///
+    /// See `Device::copy_host_to_image`.
+    #[track_caller]
+    pub fn copy_from_host(
+        &self,
+        data: &[u8],
+        subresource: SubresourceLayers,
+        offset: Offset,
+        extent: Extent,
+        layout: MemoryLayout,
+    ) -> Result<()> {
+        let call = "copy_from_host";
+        let texels = self.check_region(call, &subresource, offset, extent)?;
+        let required = required_size(call, &layout, texels)?;
+        if (data.len() as u64) < required {
+            return invalid(format!(
+                "{}: `data` ({} bytes) is smaller than the copy region ({} bytes)",
+                call,
+                data.len(),
+                required
+            ));
+        }
+        unsafe {
+            self.device.copy_host_to_image(
+                data,
+                self.raw,
+                crate::HostImageCopy {
+                    host_layout: layout,
+                    image_subresource: subresource,
+                    image_offset: offset,
+                    image_extent: extent,
+                },
+            )
+        };
+        Ok(())
+    }
+
+    /// Copy a region of the image into host memory.
+    ///
+    /// See `Device::copy_image_to_host`.
+    #[track_caller]
+    pub fn copy_to_host(
+        &self,
+        data: &mut [u8],
+        subresource: SubresourceLayers,
+        offset: Offset,
+        extent: Extent,
+        layout: MemoryLayout,
+    ) -> Result<()> {
+        let call = "copy_to_host";
+        let texels = self.check_region(call, &subresource, offset, extent)?;
+        let required = required_size(call, &layout, texels)?;
+        if (data.len() as u64) < required {
+            return invalid(format!(
+                "{}: `data` ({} bytes) is smaller than the copy region ({} bytes)",
+                call,
+                data.len(),
+                required
+            ));
+        }
+        unsafe {
+            self.device.copy_image_to_host(
+                self.raw,
+                data,
+                crate::HostImageCopy {
+                    host_layout: layout,
+                    image_subresource: subresource,
+                    image_offset: offset,
+                    image_extent: extent,
+                },
+            )
+        };
+        Ok(())
+    }
+
+    /// Generate all mipmap levels from the base level.
+    #[track_caller]
+    pub fn generate_mipmaps(&self) {
+        unsafe { self.device.generate_mipmaps(self.raw) }
+    }
+}
+
+impl Drop for OwnedImage<'_> {
+    fn drop(&mut self) {
+        unsafe { self.device.delete_image(self.raw) }
+    }
+}
+
+impl fmt::Debug for OwnedImage<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("OwnedImage")
+            .field("raw", &self.raw)
+            .field("ty", &self.ty)
+            .field("format", &self.format)
+            .field("levels", &self.levels)
+            .finish()
+    }
+}
+
+/// Number of bytes in host memory touched by a pixel transfer of `(width, height, depth)` texels.
+fn required_size(call: &str, layout: &MemoryLayout, texels: (u32, u32, u32)) -> Result<u64> {
+    if !layout.alignment.is_power_of_two() || layout.alignment > 8 {
+        return invalid(format!(
+            "{}: `alignment` ({}) must be 1, 2, 4 or 8",
+            call, layout.alignment
+        ));
+    }
+    match layout.transfer_size(texels) {
+        Some(size) => Ok(size),
+        None => invalid(format!(
+            "{}: packed depth stencil layouts require `BaseFormat::DepthStencil`",
+            call
+        )),
+    }
+}
+
+owned! {
+    /// Owned image view.
+    OwnedImageView(ImageView) => delete_image_view
+}
+
+owned! {
+    /// Owned sampler.
+    OwnedSampler(Sampler) => delete_sampler
+}
+
+impl<'d> OwnedSampler<'d> {
+    /// Create a sampler object.
+    #[track_caller]
+    pub fn new(device: &'d Device, desc: SamplerDesc) -> Result<Self> {
+        let raw = unsafe { device.create_sampler(desc, None)? };
+        Ok(OwnedSampler { device, raw })
+    }
+}
+
+owned! {
+    /// Owned shader.
+    OwnedShader(Shader) => delete_shader
+}
+
+impl<'d> OwnedShader<'d> {
+    /// Create a new shader.
+    ///
+    /// On compilation failure, the parsed log is returned together with the deleted shader handle
+    /// in `Error::CompileError`. See `Device::create_shader`.
+    #[track_caller]
+    pub fn new(
+        device: &'d Device,
+        stage: ShaderStage,
+        ty: ShaderSource,
+        source: &[u8],
+        flags: ShaderFlags,
+    ) -> Result<Self> {
+        if let ShaderSource::Spirv { entrypoint } = ty {
+            if entrypoint.contains('\0') {
+                return invalid("create_shader: `entrypoint` must not contain NUL bytes".into());
+            }
+        }
+        match unsafe { device.create_shader(stage, ty, source, flags, None) } {
+            Ok(raw) => Ok(OwnedShader { device, raw }),
+            Err(Error::CompileError(raw, log)) => {
+                unsafe { device.delete_shader(raw) };
+                Err(Error::CompileError(raw, log))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Return the log, if any, from compiling the shader.
+    #[track_caller]
+    pub fn log(&self) -> Option<String> {
+        unsafe { self.device.get_shader_log(self.raw) }
+    }
+}
+
+/// Shaders of a graphics pipeline.
+///
+/// See `grr::GraphicsPipelineDesc`.
+#[derive(Copy, Clone, Default)]
+pub struct GraphicsPipelineDesc<'a, 'd> {
+    pub vertex_shader: Option<&'a OwnedShader<'d>>,
+    pub tessellation_control_shader: Option<&'a OwnedShader<'d>>,
+    pub tessellation_evaluation_shader: Option<&'a OwnedShader<'d>>,
+    pub geometry_shader: Option<&'a OwnedShader<'d>>,
+    pub fragment_shader: Option<&'a OwnedShader<'d>>,
+    pub mesh_shader: Option<&'a OwnedShader<'d>>,
+    pub task_shader: Option<&'a OwnedShader<'d>>,
+}
+
+owned! {
+    /// Owned pipeline.
+    OwnedPipeline(Pipeline) => delete_pipeline
+}
+
+impl<'d> OwnedPipeline<'d> {
+    /// Create a graphics pipeline.
+    ///
+    /// The shaders are only required during pipeline creation.
+    #[track_caller]
+    pub fn graphics(
+        device: &'d Device,
+        desc: GraphicsPipelineDesc,
+        flags: PipelineFlags,
+    ) -> Result<Self> {
+        let raw = |shader: Option<&OwnedShader>| shader.map(OwnedShader::raw);
+        let desc = crate::GraphicsPipelineDesc {
+            vertex_shader: raw(desc.vertex_shader),
+            tessellation_control_shader: raw(desc.tessellation_control_shader),
+            tessellation_evaluation_shader: raw(desc.tessellation_evaluation_shader),
+            geometry_shader: raw(desc.geometry_shader),
+            fragment_shader: raw(desc.fragment_shader),
+            mesh_shader: raw(desc.mesh_shader),
+            task_shader: raw(desc.task_shader),
+        };
+        Self::from_result(device, unsafe {
+            device.create_graphics_pipeline(desc, flags, None)
+        })
+    }
+
+    /// Create a compute pipeline.
+    #[track_caller]
+    pub fn compute(
+        device: &'d Device,
+        compute_shader: &OwnedShader,
+        flags: PipelineFlags,
+    ) -> Result<Self> {
+        Self::from_result(device, unsafe {
+            device.create_compute_pipeline(compute_shader.raw, flags, None)
+        })
+    }
+
+    #[track_caller]
+    fn from_result(device: &'d Device, pipeline: Result<Pipeline>) -> Result<Self> {
+        match pipeline {
+            Ok(raw) => Ok(OwnedPipeline { device, raw }),
+            Err(Error::LinkError(raw, log)) => {
+                unsafe { device.delete_pipeline(raw) };
+                Err(Error::LinkError(raw, log))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Retrieve the log from the pipeline link.
+    #[track_caller]
+    pub fn log(&self) -> Option<String> {
+        unsafe { self.device.get_pipeline_log(self.raw) }
+    }
+}
+
+owned! {
+    /// Owned vertex array.
+    OwnedVertexArray(VertexArray) => delete_vertex_array
+}
+
+impl<'d> OwnedVertexArray<'d> {
+    /// Create a new vertex array.
+    #[track_caller]
+    pub fn new(device: &'d Device, attributes: &[VertexAttributeDesc]) -> Result<Self> {
+        let raw = unsafe { device.create_vertex_array(attributes, None)? };
+        Ok(OwnedVertexArray { device, raw })
+    }
+}
+
+owned! {
+    /// Owned framebuffer.
+    OwnedFramebuffer(Framebuffer) => delete_framebuffer
+}
+
+impl<'d> OwnedFramebuffer<'d> {
+    /// Create a new framebuffer.
+    #[track_caller]
+    pub fn new(device: &'d Device) -> Result<Self> {
+        let raw = unsafe { device.create_framebuffer(None)? };
+        Ok(OwnedFramebuffer { device, raw })
+    }
+}
+
+owned! {
+    /// Owned renderbuffer.
+    OwnedRenderbuffer(Renderbuffer) => delete_renderbuffer
+}
+
+impl<'d> OwnedRenderbuffer<'d> {
+    /// Create a new renderbuffer.
+    #[track_caller]
+    pub fn new(
+        device: &'d Device,
+        format: Format,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<Self> {
+        if width == 0 || height == 0 || samples == 0 {
+            return invalid(
+                "create_renderbuffer: `width`, `height` and `samples` must be non-zero".into(),
+            );
+        }
+        let raw = unsafe { device.create_renderbuffer(format, width, height, samples, None)? };
+        Ok(OwnedRenderbuffer { device, raw })
+    }
+}
@@ -2,18 +2,25 @@
 
 use crate::__gl;
 use crate::__gl::types::{GLenum, GLuint};
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
 use crate::debug::{Object, ObjectType};
 use crate::device::Device;
 use crate::error::Result;
+use crate::handle::{Generation, Handle};
 use crate::Compare;
 
 use std::ops::Range;
 
 /// Sampler handle.
-#[repr(transparent)]
-#[derive(Clone, Copy)]
-pub struct Sampler(GLuint);
+#[cfg_attr(not(debug_assertions), repr(transparent))]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sampler(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for Sampler {
     const TYPE: ObjectType = ObjectType::Sampler;
@@ -22,9 +29,17 @@ impl Object for Sampler {
     }
 }
 
+impl Handle for Sampler {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 impl Device {
     /// Create a sampler object.
-    pub unsafe fn create_sampler(&self, desc: SamplerDesc) -> Result<Sampler> {
+    #[track_caller]
+    pub unsafe fn create_sampler(&self, desc: SamplerDesc, label: Option<&str>) -> Result<Sampler> {
+        let _scope = self.api_scope("create_sampler");
         let mut sampler = 0;
         self.0.CreateSamplers(1, &mut sampler);
         self.get_error()?;
@@ -79,32 +94,55 @@ impl Device {
             desc.border_color.as_ptr(),
         );
 
-        Ok(Sampler(sampler))
+        let sampler = Sampler(sampler, self.create_handle(ObjectType::Sampler, sampler));
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::Sampler, sampler.0, 0);
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateSampler { desc, sampler });
+        self.label_object(sampler, label);
+        Ok(sampler)
     }
 
     /// Bind samplers to specific texture units.
+    #[track_caller]
     pub unsafe fn bind_samplers(&self, first: u32, samplers: &[Sampler]) {
+        let _scope = self.api_scope("bind_samplers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindSamplers {
+            first,
+            samplers: samplers.to_vec(),
+        });
+        self.check_handles("bind_samplers", samplers);
         let samplers = samplers.iter().map(|s| s.0).collect::<Vec<_>>();
         self.0
             .BindSamplers(first, samplers.len() as _, samplers.as_ptr());
     }
 
     // Delete a sampler.
+    #[track_caller]
     pub unsafe fn delete_sampler(&self, sampler: Sampler) {
+        let _scope = self.api_scope("delete_sampler");
         self.delete_samplers(&[sampler])
     }
 
     /// Delete multiple samplers.
+    #[track_caller]
     pub unsafe fn delete_samplers(&self, samplers: &[Sampler]) {
-        self.0.DeleteSamplers(
-            samplers.len() as _,
-            samplers.as_ptr() as *const _, // newtype
-        );
+        let _scope = self.api_scope("delete_samplers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteSamplers {
+            samplers: samplers.to_vec(),
+        });
+        self.delete_handles("delete_samplers", samplers);
+        let samplers = samplers.iter().map(|s| s.0).collect::<Vec<_>>();
+        self.0
+            .DeleteSamplers(samplers.len() as _, samplers.as_ptr());
     }
 }
 
 /// Sampler Descriptor.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SamplerDesc {
     pub min_filter: Filter,
     pub mag_filter: Filter,
@@ -139,6 +177,7 @@ impl Default for SamplerDesc {
 /// area different from one texture element.
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Filter {
     /// Use the closest texel, by Manhattan distance.
     Nearest = __gl::NEAREST,
@@ -165,6 +204,7 @@ fn map_min_filter(filter: Filter, mip_map: Option<Filter>) -> GLenum {
 /// sampling operations.
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SamplerAddress {
     /// Strip off the integer part of the coordinate, effectively
     /// repeating the texture across the entire space.
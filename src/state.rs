@@ -0,0 +1,642 @@
+//! Context state snapshots.
+//!
+//! Foreign GL code sharing the context with a device (e.g. via [`Device::context`](struct.Device.html#method.context))
+//! and the device itself overwrite each other's context state.
+//! A [`StateSnapshot`](struct.StateSnapshot.html) captures all context state modified by the device,
+//! which can be restored after issuing commands.
+//!
+//! Object state (e.g. vertex array or framebuffer attachments) is not part of the snapshot.
+
+use crate::__gl;
+use crate::__gl::types::{GLboolean, GLenum, GLint, GLuint};
+use crate::device::{Device, Version};
+#[cfg(feature = "capture")]
+use crate::ObjectType;
+
+use std::ptr;
+
+/// Capabilities modified by the device.
+const CAPABILITIES: [GLenum; 18] = [
+    __gl::FRAMEBUFFER_SRGB,
+    __gl::SCISSOR_TEST,
+    __gl::TEXTURE_CUBE_MAP_SEAMLESS,
+    __gl::PROGRAM_POINT_SIZE,
+    __gl::SAMPLE_MASK,
+    __gl::PRIMITIVE_RESTART,
+    __gl::DEPTH_TEST,
+    __gl::STENCIL_TEST,
+    __gl::DEPTH_CLAMP,
+    __gl::RASTERIZER_DISCARD,
+    __gl::POLYGON_OFFSET_POINT,
+    __gl::POLYGON_OFFSET_LINE,
+    __gl::POLYGON_OFFSET_FILL,
+    __gl::CULL_FACE,
+    __gl::MULTISAMPLE,
+    __gl::SAMPLE_SHADING,
+    __gl::SAMPLE_ALPHA_TO_COVERAGE,
+    __gl::SAMPLE_ALPHA_TO_ONE,
+];
+
+/// Non-indexed buffer binding points with their binding query.
+///
+/// Generic uniform and storage bindings are restored after the indexed bindings,
+/// which overwrite them.
+const BUFFER_TARGETS: [(GLenum, GLenum); 6] = [
+    (
+        __gl::DRAW_INDIRECT_BUFFER,
+        __gl::DRAW_INDIRECT_BUFFER_BINDING,
+    ),
+    (
+        __gl::DISPATCH_INDIRECT_BUFFER,
+        __gl::DISPATCH_INDIRECT_BUFFER_BINDING,
+    ),
+    (__gl::PIXEL_PACK_BUFFER, __gl::PIXEL_PACK_BUFFER_BINDING),
+    (__gl::PIXEL_UNPACK_BUFFER, __gl::PIXEL_UNPACK_BUFFER_BINDING),
+    (__gl::UNIFORM_BUFFER, __gl::UNIFORM_BUFFER_BINDING),
+    (
+        __gl::SHADER_STORAGE_BUFFER,
+        __gl::SHADER_STORAGE_BUFFER_BINDING,
+    ),
+];
+
+/// Texture targets of a texture unit with their binding query.
+const TEXTURE_TARGETS: [(GLenum, GLenum); 11] = [
+    (__gl::TEXTURE_1D, __gl::TEXTURE_BINDING_1D),
+    (__gl::TEXTURE_2D, __gl::TEXTURE_BINDING_2D),
+    (__gl::TEXTURE_3D, __gl::TEXTURE_BINDING_3D),
+    (__gl::TEXTURE_1D_ARRAY, __gl::TEXTURE_BINDING_1D_ARRAY),
+    (__gl::TEXTURE_2D_ARRAY, __gl::TEXTURE_BINDING_2D_ARRAY),
+    (__gl::TEXTURE_RECTANGLE, __gl::TEXTURE_BINDING_RECTANGLE),
+    (__gl::TEXTURE_CUBE_MAP, __gl::TEXTURE_BINDING_CUBE_MAP),
+    (
+        __gl::TEXTURE_CUBE_MAP_ARRAY,
+        __gl::TEXTURE_BINDING_CUBE_MAP_ARRAY,
+    ),
+    (__gl::TEXTURE_BUFFER, __gl::TEXTURE_BINDING_BUFFER),
+    (
+        __gl::TEXTURE_2D_MULTISAMPLE,
+        __gl::TEXTURE_BINDING_2D_MULTISAMPLE,
+    ),
+    (
+        __gl::TEXTURE_2D_MULTISAMPLE_ARRAY,
+        __gl::TEXTURE_BINDING_2D_MULTISAMPLE_ARRAY,
+    ),
+];
+
+/// Pixel store parameters.
+const PIXEL_STORE: [GLenum; 6] = [
+    __gl::PACK_ALIGNMENT,
+    __gl::PACK_ROW_LENGTH,
+    __gl::PACK_IMAGE_HEIGHT,
+    __gl::UNPACK_ALIGNMENT,
+    __gl::UNPACK_ROW_LENGTH,
+    __gl::UNPACK_IMAGE_HEIGHT,
+];
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IndexedBuffer {
+    buffer: GLuint,
+    offset: i64,
+    size: i64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct TextureUnit {
+    /// Bound textures, only non-zero bindings.
+    textures: Vec<(GLenum, GLuint)>,
+    sampler: GLuint,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ImageUnit {
+    texture: GLuint,
+    level: GLint,
+    layered: bool,
+    layer: GLint,
+    access: GLenum,
+    format: GLenum,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Blend {
+    enabled: bool,
+    equation_rgb: GLenum,
+    equation_alpha: GLenum,
+    src_rgb: GLenum,
+    dst_rgb: GLenum,
+    src_alpha: GLenum,
+    dst_alpha: GLenum,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Stencil {
+    func: GLenum,
+    reference: GLint,
+    mask: GLuint,
+    fail: GLenum,
+    depth_fail: GLenum,
+    pass: GLenum,
+}
+
+/// Snapshot of the context state modified by the device.
+///
+/// Created by [`Device::save_state`](struct.Device.html#method.save_state).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshot {
+    capabilities: Vec<(GLenum, bool)>,
+    program: GLuint,
+    vertex_array: GLuint,
+    draw_framebuffer: GLuint,
+    read_framebuffer: GLuint,
+    buffers: Vec<(GLenum, GLuint)>,
+    uniform_buffers: Vec<IndexedBuffer>,
+    storage_buffers: Vec<IndexedBuffer>,
+    active_texture: GLenum,
+    texture_units: Vec<TextureUnit>,
+    image_units: Vec<ImageUnit>,
+    viewports: Vec<[f32; 4]>,
+    depth_ranges: Vec<[f64; 2]>,
+    scissors: Vec<[GLint; 4]>,
+    blend: Vec<Blend>,
+    depth_write: bool,
+    depth_func: GLenum,
+    stencil_front: Stencil,
+    stencil_back: Stencil,
+    polygon_mode: GLenum,
+    polygon_offset: (f32, f32),
+    front_face: GLenum,
+    cull_face: GLenum,
+    primitive_restart_index: GLuint,
+    min_sample_shading: f32,
+    sample_mask: Vec<GLuint>,
+    clip_origin: GLenum,
+    clip_depth: GLenum,
+    pixel_store: Vec<(GLenum, GLint)>,
+}
+
+/// Restores a state snapshot when dropped.
+///
+/// Created by [`Device::save_state_scoped`](struct.Device.html#method.save_state_scoped).
+pub struct StateGuard<'a> {
+    device: &'a Device,
+    snapshot: StateSnapshot,
+}
+
+impl StateGuard<'_> {
+    /// Snapshot restored on drop.
+    pub fn snapshot(&self) -> &StateSnapshot {
+        &self.snapshot
+    }
+}
+
+impl StateSnapshot {
+    /// Snapshot with the object names translated by `f`, used for replaying captures.
+    #[cfg(feature = "capture")]
+    pub(crate) fn map_names<F>(&self, f: F) -> StateSnapshot
+    where
+        F: Fn(ObjectType, GLuint) -> GLuint,
+    {
+        let indexed_buffers = |buffers: &[IndexedBuffer]| {
+            buffers
+                .iter()
+                .map(|buffer| IndexedBuffer {
+                    buffer: f(ObjectType::Buffer, buffer.buffer),
+                    ..buffer.clone()
+                })
+                .collect()
+        };
+
+        StateSnapshot {
+            program: f(ObjectType::Pipeline, self.program),
+            vertex_array: f(ObjectType::VertexArray, self.vertex_array),
+            draw_framebuffer: f(ObjectType::Framebuffer, self.draw_framebuffer),
+            read_framebuffer: f(ObjectType::Framebuffer, self.read_framebuffer),
+            buffers: self
+                .buffers
+                .iter()
+                .map(|&(target, buffer)| (target, f(ObjectType::Buffer, buffer)))
+                .collect(),
+            uniform_buffers: indexed_buffers(&self.uniform_buffers),
+            storage_buffers: indexed_buffers(&self.storage_buffers),
+            texture_units: self
+                .texture_units
+                .iter()
+                .map(|unit| TextureUnit {
+                    textures: unit
+                        .textures
+                        .iter()
+                        .map(|&(target, texture)| (target, f(ObjectType::Image, texture)))
+                        .collect(),
+                    sampler: f(ObjectType::Sampler, unit.sampler),
+                })
+                .collect(),
+            image_units: self
+                .image_units
+                .iter()
+                .map(|unit| ImageUnit {
+                    texture: f(ObjectType::Image, unit.texture),
+                    ..unit.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Drop for StateGuard<'_> {
+    fn drop(&mut self) {
+        unsafe { self.device.restore_state(&self.snapshot) }
+    }
+}
+
+impl Device {
+    /// Capture the context state modified by the device.
+    ///
+    /// Includes capabilities, bound pipeline, vertex array and framebuffers, buffer, texture,
+    /// sampler and image bindings, viewports, scissors, blend, depth-stencil, rasterization and
+    /// multisample state, clip control and pixel store parameters.
+    ///
+    /// Capturing the state requires a large number of queries, snapshots should be taken
+    /// at coarse boundaries (e.g. once per frame or pass) instead of per draw call.
+    #[track_caller]
+    pub unsafe fn save_state(&self) -> StateSnapshot {
+        let _scope = self.api_scope("save_state");
+        let max_texture_units = self.state_u32(__gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS);
+        let max_image_units = self.state_u32(__gl::MAX_IMAGE_UNITS);
+        let max_viewports = self.state_u32(__gl::MAX_VIEWPORTS);
+        let max_draw_buffers = self.state_u32(__gl::MAX_DRAW_BUFFERS);
+        let max_sample_mask_words = self.state_u32(__gl::MAX_SAMPLE_MASK_WORDS);
+
+        let active_texture = self.state_u32(__gl::ACTIVE_TEXTURE);
+        let texture_units = (0..max_texture_units)
+            .map(|unit| {
+                self.0.ActiveTexture(__gl::TEXTURE0 + unit);
+                TextureUnit {
+                    textures: TEXTURE_TARGETS
+                        .iter()
+                        .map(|&(target, binding)| (target, self.state_u32(binding)))
+                        .filter(|&(_, texture)| texture != 0)
+                        .collect(),
+                    sampler: self.state_u32(__gl::SAMPLER_BINDING),
+                }
+            })
+            .collect();
+        self.0.ActiveTexture(active_texture);
+
+        let mut buffers = BUFFER_TARGETS
+            .iter()
+            .map(|&(target, binding)| (target, self.state_u32(binding)))
+            .collect::<Vec<_>>();
+        let draw_indirect_count = self.version() >= (Version { major: 4, minor: 6 })
+            || self.has_extension(&["GL_ARB_indirect_parameters"]);
+        if draw_indirect_count {
+            buffers.push((
+                __gl::PARAMETER_BUFFER,
+                self.state_u32(__gl::PARAMETER_BUFFER_BINDING),
+            ));
+        }
+
+        StateSnapshot {
+            capabilities: CAPABILITIES
+                .iter()
+                .map(|&cap| (cap, self.0.IsEnabled(cap) != __gl::FALSE))
+                .collect(),
+            program: self.state_u32(__gl::CURRENT_PROGRAM),
+            vertex_array: self.state_u32(__gl::VERTEX_ARRAY_BINDING),
+            draw_framebuffer: self.state_u32(__gl::DRAW_FRAMEBUFFER_BINDING),
+            read_framebuffer: self.state_u32(__gl::READ_FRAMEBUFFER_BINDING),
+            buffers,
+            uniform_buffers: self.state_indexed_buffers(
+                __gl::MAX_UNIFORM_BUFFER_BINDINGS,
+                [
+                    __gl::UNIFORM_BUFFER_BINDING,
+                    __gl::UNIFORM_BUFFER_START,
+                    __gl::UNIFORM_BUFFER_SIZE,
+                ],
+            ),
+            storage_buffers: self.state_indexed_buffers(
+                __gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS,
+                [
+                    __gl::SHADER_STORAGE_BUFFER_BINDING,
+                    __gl::SHADER_STORAGE_BUFFER_START,
+                    __gl::SHADER_STORAGE_BUFFER_SIZE,
+                ],
+            ),
+            active_texture,
+            texture_units,
+            image_units: (0..max_image_units)
+                .map(|unit| ImageUnit {
+                    texture: self.state_indexed_u32(__gl::IMAGE_BINDING_NAME, unit),
+                    level: self.state_indexed_u32(__gl::IMAGE_BINDING_LEVEL, unit) as _,
+                    layered: self.state_indexed_u32(__gl::IMAGE_BINDING_LAYERED, unit) != 0,
+                    layer: self.state_indexed_u32(__gl::IMAGE_BINDING_LAYER, unit) as _,
+                    access: self.state_indexed_u32(__gl::IMAGE_BINDING_ACCESS, unit),
+                    format: self.state_indexed_u32(__gl::IMAGE_BINDING_FORMAT, unit),
+                })
+                .collect(),
+            viewports: (0..max_viewports)
+                .map(|i| {
+                    let mut viewport = [0.0; 4];
+                    self.0.GetFloati_v(__gl::VIEWPORT, i, viewport.as_mut_ptr());
+                    viewport
+                })
+                .collect(),
+            depth_ranges: (0..max_viewports)
+                .map(|i| {
+                    let mut range = [0.0; 2];
+                    self.0
+                        .GetDoublei_v(__gl::DEPTH_RANGE, i, range.as_mut_ptr());
+                    range
+                })
+                .collect(),
+            scissors: (0..max_viewports)
+                .map(|i| {
+                    let mut scissor = [0; 4];
+                    self.0
+                        .GetIntegeri_v(__gl::SCISSOR_BOX, i, scissor.as_mut_ptr());
+                    scissor
+                })
+                .collect(),
+            blend: (0..max_draw_buffers)
+                .map(|i| Blend {
+                    enabled: self.0.IsEnabledi(__gl::BLEND, i) != __gl::FALSE,
+                    equation_rgb: self.state_indexed_u32(__gl::BLEND_EQUATION_RGB, i),
+                    equation_alpha: self.state_indexed_u32(__gl::BLEND_EQUATION_ALPHA, i),
+                    src_rgb: self.state_indexed_u32(__gl::BLEND_SRC_RGB, i),
+                    dst_rgb: self.state_indexed_u32(__gl::BLEND_DST_RGB, i),
+                    src_alpha: self.state_indexed_u32(__gl::BLEND_SRC_ALPHA, i),
+                    dst_alpha: self.state_indexed_u32(__gl::BLEND_DST_ALPHA, i),
+                })
+                .collect(),
+            depth_write: {
+                let mut mask: GLboolean = 0;
+                self.0.GetBooleanv(__gl::DEPTH_WRITEMASK, &mut mask);
+                mask != __gl::FALSE
+            },
+            depth_func: self.state_u32(__gl::DEPTH_FUNC),
+            stencil_front: Stencil {
+                func: self.state_u32(__gl::STENCIL_FUNC),
+                reference: self.state_u32(__gl::STENCIL_REF) as _,
+                mask: self.state_u32(__gl::STENCIL_VALUE_MASK),
+                fail: self.state_u32(__gl::STENCIL_FAIL),
+                depth_fail: self.state_u32(__gl::STENCIL_PASS_DEPTH_FAIL),
+                pass: self.state_u32(__gl::STENCIL_PASS_DEPTH_PASS),
+            },
+            stencil_back: Stencil {
+                func: self.state_u32(__gl::STENCIL_BACK_FUNC),
+                reference: self.state_u32(__gl::STENCIL_BACK_REF) as _,
+                mask: self.state_u32(__gl::STENCIL_BACK_VALUE_MASK),
+                fail: self.state_u32(__gl::STENCIL_BACK_FAIL),
+                depth_fail: self.state_u32(__gl::STENCIL_BACK_PASS_DEPTH_FAIL),
+                pass: self.state_u32(__gl::STENCIL_BACK_PASS_DEPTH_PASS),
+            },
+            polygon_mode: {
+                // Some implementations still return front and back mode.
+                let mut mode = [__gl::FILL as GLint; 2];
+                self.0.GetIntegerv(__gl::POLYGON_MODE, mode.as_mut_ptr());
+                mode[0] as _
+            },
+            polygon_offset: (
+                self.state_f32(__gl::POLYGON_OFFSET_FACTOR),
+                self.state_f32(__gl::POLYGON_OFFSET_UNITS),
+            ),
+            front_face: self.state_u32(__gl::FRONT_FACE),
+            cull_face: self.state_u32(__gl::CULL_FACE_MODE),
+            primitive_restart_index: self.state_u32(__gl::PRIMITIVE_RESTART_INDEX),
+            min_sample_shading: self.state_f32(__gl::MIN_SAMPLE_SHADING_VALUE),
+            sample_mask: (0..max_sample_mask_words)
+                .map(|i| self.state_indexed_u32(__gl::SAMPLE_MASK_VALUE, i))
+                .collect(),
+            clip_origin: self.state_u32(__gl::CLIP_ORIGIN),
+            clip_depth: self.state_u32(__gl::CLIP_DEPTH_MODE),
+            pixel_store: PIXEL_STORE
+                .iter()
+                .map(|&pname| (pname, self.state_u32(pname) as _))
+                .collect(),
+        }
+    }
+
+    /// Restore the context state captured by `save_state`.
+    ///
+    /// Commands issued after restoring the state need to rebind all state required
+    /// by the device (e.g. pipeline and vertex array).
+    #[track_caller]
+    pub unsafe fn restore_state(&self, snapshot: &StateSnapshot) {
+        let _scope = self.api_scope("restore_state");
+        #[cfg(feature = "capture")]
+        self.capture(|| crate::capture::Command::RestoreState {
+            snapshot: snapshot.clone(),
+        });
+        for &(cap, enabled) in &snapshot.capabilities {
+            if enabled {
+                self.0.Enable(cap);
+            } else {
+                self.0.Disable(cap);
+            }
+        }
+
+        self.0.UseProgram(snapshot.program);
+        self.0.BindVertexArray(snapshot.vertex_array);
+        self.0
+            .BindFramebuffer(__gl::DRAW_FRAMEBUFFER, snapshot.draw_framebuffer);
+        self.0
+            .BindFramebuffer(__gl::READ_FRAMEBUFFER, snapshot.read_framebuffer);
+
+        self.restore_indexed_buffers(__gl::UNIFORM_BUFFER, &snapshot.uniform_buffers);
+        self.restore_indexed_buffers(__gl::SHADER_STORAGE_BUFFER, &snapshot.storage_buffers);
+        for &(target, buffer) in &snapshot.buffers {
+            self.0.BindBuffer(target, buffer);
+        }
+
+        let num_texture_units = snapshot.texture_units.len() as _;
+        self.0.BindTextures(0, num_texture_units, ptr::null());
+        for (unit, texture_unit) in snapshot.texture_units.iter().enumerate() {
+            if texture_unit.textures.is_empty() {
+                continue;
+            }
+            self.0.ActiveTexture(__gl::TEXTURE0 + unit as GLuint);
+            for &(target, texture) in &texture_unit.textures {
+                self.0.BindTexture(target, texture);
+            }
+        }
+        self.0.ActiveTexture(snapshot.active_texture);
+        let samplers = snapshot
+            .texture_units
+            .iter()
+            .map(|unit| unit.sampler)
+            .collect::<Vec<_>>();
+        self.0.BindSamplers(0, num_texture_units, samplers.as_ptr());
+
+        self.0
+            .BindImageTextures(0, snapshot.image_units.len() as _, ptr::null());
+        for (unit, image) in snapshot.image_units.iter().enumerate() {
+            if image.texture != 0 {
+                self.0.BindImageTexture(
+                    unit as _,
+                    image.texture,
+                    image.level,
+                    image.layered as _,
+                    image.layer,
+                    image.access,
+                    image.format,
+                );
+            }
+        }
+
+        let viewports = snapshot.viewports.concat();
+        self.0
+            .ViewportArrayv(0, snapshot.viewports.len() as _, viewports.as_ptr());
+        let depth_ranges = snapshot.depth_ranges.concat();
+        self.0
+            .DepthRangeArrayv(0, snapshot.depth_ranges.len() as _, depth_ranges.as_ptr());
+        let scissors = snapshot.scissors.concat();
+        self.0
+            .ScissorArrayv(0, snapshot.scissors.len() as _, scissors.as_ptr());
+
+        for (slot, blend) in snapshot.blend.iter().enumerate() {
+            let slot = slot as GLuint;
+            if blend.enabled {
+                self.0.Enablei(__gl::BLEND, slot);
+            } else {
+                self.0.Disablei(__gl::BLEND, slot);
+            }
+            self.0
+                .BlendEquationSeparatei(slot, blend.equation_rgb, blend.equation_alpha);
+            self.0.BlendFuncSeparatei(
+                slot,
+                blend.src_rgb,
+                blend.dst_rgb,
+                blend.src_alpha,
+                blend.dst_alpha,
+            );
+        }
+
+        self.0.DepthMask(snapshot.depth_write as _);
+        self.0.DepthFunc(snapshot.depth_func);
+        for &(face, stencil) in &[
+            (__gl::FRONT, &snapshot.stencil_front),
+            (__gl::BACK, &snapshot.stencil_back),
+        ] {
+            self.0
+                .StencilFuncSeparate(face, stencil.func, stencil.reference, stencil.mask);
+            self.0
+                .StencilOpSeparate(face, stencil.fail, stencil.depth_fail, stencil.pass);
+        }
+
+        self.0
+            .PolygonMode(__gl::FRONT_AND_BACK, snapshot.polygon_mode);
+        self.0
+            .PolygonOffset(snapshot.polygon_offset.0, snapshot.polygon_offset.1);
+        self.0.FrontFace(snapshot.front_face);
+        self.0.CullFace(snapshot.cull_face);
+        self.0
+            .PrimitiveRestartIndex(snapshot.primitive_restart_index);
+        self.0.MinSampleShading(snapshot.min_sample_shading);
+        for (i, &mask) in snapshot.sample_mask.iter().enumerate() {
+            self.0.SampleMaski(i as _, mask);
+        }
+        self.0
+            .ClipControl(snapshot.clip_origin, snapshot.clip_depth);
+        for &(pname, param) in &snapshot.pixel_store {
+            self.0.PixelStorei(pname, param);
+        }
+
+        self.invalidate_state_cache();
+        #[cfg(feature = "validation")]
+        {
+            let buffer_bound = |target| {
+                snapshot
+                    .buffers
+                    .iter()
+                    .any(|&(bound, buffer)| bound == target && buffer != 0)
+            };
+            let scissor_test = snapshot
+                .capabilities
+                .iter()
+                .any(|&(cap, enabled)| cap == __gl::SCISSOR_TEST && enabled);
+            self.validator().restore_state(
+                snapshot.program,
+                snapshot.vertex_array,
+                buffer_bound(__gl::DRAW_INDIRECT_BUFFER),
+                buffer_bound(__gl::DISPATCH_INDIRECT_BUFFER),
+                snapshot.viewports.len() as _,
+                scissor_test,
+            );
+        }
+    }
+
+    /// Capture the context state modified by the device, restoring it when the guard is dropped.
+    ///
+    /// See [`save_state`](#method.save_state).
+    pub unsafe fn save_state_scoped(&self) -> StateGuard<'_> {
+        StateGuard {
+            device: self,
+            snapshot: self.save_state(),
+        }
+    }
+
+    unsafe fn state_u32(&self, pname: GLenum) -> GLuint {
+        let mut value = 0;
+        self.0.GetIntegerv(pname, &mut value);
+        value as _
+    }
+
+    unsafe fn state_f32(&self, pname: GLenum) -> f32 {
+        let mut value = 0.0;
+        self.0.GetFloatv(pname, &mut value);
+        value
+    }
+
+    unsafe fn state_indexed_u32(&self, pname: GLenum, index: GLuint) -> GLuint {
+        let mut value = 0;
+        self.0.GetIntegeri_v(pname, index, &mut value);
+        value as _
+    }
+
+    unsafe fn state_indexed_buffers(
+        &self,
+        max_bindings: GLenum,
+        [binding, start, size]: [GLenum; 3],
+    ) -> Vec<IndexedBuffer> {
+        (0..self.state_u32(max_bindings))
+            .map(|i| {
+                let mut buffer = IndexedBuffer {
+                    buffer: self.state_indexed_u32(binding, i),
+                    offset: 0,
+                    size: 0,
+                };
+                self.0.GetInteger64i_v(start, i, &mut buffer.offset);
+                self.0.GetInteger64i_v(size, i, &mut buffer.size);
+                buffer
+            })
+            .collect()
+    }
+
+    unsafe fn restore_indexed_buffers(&self, target: GLenum, buffers: &[IndexedBuffer]) {
+        self.0
+            .BindBuffersBase(target, 0, buffers.len() as _, ptr::null());
+        for (i, buffer) in buffers.iter().enumerate() {
+            if buffer.buffer == 0 {
+                continue;
+            }
+            // Zero size for bindings of whole buffers.
+            if buffer.size == 0 {
+                self.0.BindBufferBase(target, i as _, buffer.buffer);
+            } else {
+                self.0.BindBufferRange(
+                    target,
+                    i as _,
+                    buffer.buffer,
+                    buffer.offset as _,
+                    buffer.size as _,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,572 @@
+//! Buffer sub-allocation.
+//!
+//! Sub-allocators hand out ranges of a single buffer, aligned to the uniform and storage
+//! buffer offset alignment of the device. The ranges can be bound directly via
+//! `bind_uniform_buffers` and `bind_storage_buffers`.
+
+use crate::buffer::{Buffer, BufferRange, MemoryFlags};
+use crate::device::Device;
+use crate::error::Result;
+
+use std::collections::{BTreeSet, VecDeque};
+
+/// Allocation strategy of a buffer sub-allocator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocationStrategy {
+    /// Bump allocation, ranges are only freed all at once via `reset`.
+    ///
+    /// Suited for transient data rebuilt every frame.
+    Linear,
+    /// Circular allocation, ranges need to be freed in allocation order.
+    ///
+    /// Suited for streaming data consumed in submission order.
+    Ring,
+    /// Buddy allocation, ranges can be freed in any order.
+    ///
+    /// Allocations are rounded up to a power of two multiple of the alignment.
+    FreeList,
+}
+
+/// Usage and fragmentation statistics of a sub-allocator.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct AllocatorStats {
+    /// Size in bytes of the underlying buffer.
+    pub size: u64,
+    /// Number of live allocations.
+    pub allocations: u64,
+    /// Bytes occupied by live allocations, including alignment padding.
+    pub used: u64,
+    /// Bytes available for new allocations.
+    pub free: u64,
+    /// Size in bytes of the largest free block.
+    pub largest_free_block: u64,
+    /// Fraction of free memory not part of the largest free block, in [0; 1].
+    pub fragmentation: f32,
+}
+
+#[derive(Debug)]
+enum Strategy {
+    Linear {
+        head: u64,
+    },
+    Ring {
+        head: u64,
+        /// Offsets of live allocations in allocation order.
+        allocations: VecDeque<u64>,
+    },
+    FreeList(Buddy),
+}
+
+/// Sub-allocator over a single buffer.
+///
+/// ```no_run
+/// # unsafe fn suballoc(grr: &grr::Device) -> grr::Result<()> {
+/// let mut uniforms = grr.create_buffer_allocator(
+///     1 << 20,
+///     grr::MemoryFlags::DYNAMIC,
+///     grr::AllocationStrategy::FreeList,
+///     Some("uniforms"),
+/// )?;
+/// let range = uniforms.allocate(64).expect("out of memory");
+/// grr.copy_host_to_buffer(range.buffer, range.offset as _, &[0; 64]);
+/// grr.bind_uniform_buffers(0, &[range]);
+/// // ..
+/// uniforms.free(range);
+/// grr.delete_buffer_allocator(uniforms);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BufferAllocator {
+    buffer: Buffer,
+    size: u64,
+    alignment: u64,
+    allocations: u64,
+    used: u64,
+    strategy: Strategy,
+}
+
+impl BufferAllocator {
+    /// Underlying buffer of all allocations.
+    pub fn buffer(&self) -> Buffer {
+        self.buffer
+    }
+
+    /// Alignment in bytes of all allocations.
+    pub fn alignment(&self) -> u64 {
+        self.alignment
+    }
+
+    /// Allocate a range of `size` bytes.
+    ///
+    /// Returns `None` if there is no free block large enough.
+    pub fn allocate(&mut self, size: u64) -> Option<BufferRange> {
+        let padded = align(size.max(1), self.alignment);
+        let (offset, padded) = match self.strategy {
+            Strategy::Linear { ref mut head } => {
+                if padded > self.size - *head {
+                    return None;
+                }
+                let offset = *head;
+                *head += padded;
+                (offset, padded)
+            }
+            Strategy::Ring {
+                ref mut head,
+                ref mut allocations,
+            } => {
+                let offset = match ring_tail(allocations) {
+                    None if padded <= self.size => 0,
+                    None => return None,
+                    // Free space at the end and start of the buffer.
+                    Some((tail, false)) => {
+                        if padded <= self.size - *head {
+                            *head
+                        } else if padded <= tail {
+                            0
+                        } else {
+                            return None;
+                        }
+                    }
+                    // Wrapped around, free space between head and tail.
+                    Some((tail, true)) if padded <= tail - *head => *head,
+                    Some(_) => return None,
+                };
+                *head = offset + padded;
+                allocations.push_back(offset);
+                (offset, padded)
+            }
+            Strategy::FreeList(ref mut buddy) => buddy.allocate(padded)?,
+        };
+
+        self.allocations += 1;
+        self.used += padded;
+        Some(BufferRange {
+            buffer: self.buffer,
+            offset,
+            size,
+        })
+    }
+
+    /// Free an allocated range.
+    ///
+    /// Ranges of linear allocators are only freed by `reset`, this call has no effect.
+    ///
+    /// # Valid usage
+    ///
+    /// - `range` must have been allocated by this allocator and not freed yet.
+    /// - Ranges of ring allocators must be freed in allocation order.
+    /// - The range must not be in use by the device anymore (e.g. guarded by a fence).
+    pub fn free(&mut self, range: BufferRange) {
+        let mut padded = align(range.size.max(1), self.alignment);
+        match self.strategy {
+            Strategy::Linear { .. } => return,
+            Strategy::Ring {
+                ref mut allocations,
+                ..
+            } => {
+                assert_eq!(
+                    allocations.pop_front(),
+                    Some(range.offset),
+                    "ring allocations must be freed in allocation order"
+                );
+            }
+            Strategy::FreeList(ref mut buddy) => padded = buddy.free(range.offset, padded),
+        }
+        self.allocations -= 1;
+        self.used -= padded;
+    }
+
+    /// Free all allocations.
+    pub fn reset(&mut self) {
+        match self.strategy {
+            Strategy::Linear { ref mut head } => *head = 0,
+            Strategy::Ring {
+                ref mut head,
+                ref mut allocations,
+            } => {
+                *head = 0;
+                allocations.clear();
+            }
+            Strategy::FreeList(ref mut buddy) => *buddy = Buddy::new(self.size, self.alignment),
+        }
+        self.allocations = 0;
+        self.used = 0;
+    }
+
+    /// Usage and fragmentation statistics.
+    pub fn stats(&self) -> AllocatorStats {
+        let blocks = match self.strategy {
+            Strategy::Linear { head } => vec![self.size - head],
+            Strategy::Ring {
+                head,
+                ref allocations,
+            } => match ring_tail(allocations) {
+                None => vec![self.size],
+                Some((tail, false)) => vec![self.size - head, tail],
+                Some((tail, true)) => vec![tail - head],
+            },
+            Strategy::FreeList(ref buddy) => buddy.free_blocks(),
+        };
+        let free = blocks.iter().sum::<u64>();
+        let largest_free_block = blocks.iter().copied().max().unwrap_or(0);
+        let fragmentation = if free > 0 {
+            1.0 - largest_free_block as f32 / free as f32
+        } else {
+            0.0
+        };
+
+        AllocatorStats {
+            size: self.size,
+            allocations: self.allocations,
+            used: self.used,
+            free,
+            largest_free_block,
+            fragmentation,
+        }
+    }
+}
+
+/// Offset of the oldest ring allocation and if the newer allocations wrapped around.
+fn ring_tail(allocations: &VecDeque<u64>) -> Option<(u64, bool)> {
+    let tail = *allocations.front()?;
+    let newest = *allocations.back()?;
+    Some((tail, newest < tail))
+}
+
+fn align(value: u64, alignment: u64) -> u64 {
+    (value + alignment - 1) / alignment * alignment
+}
+
+/// Binary buddy allocator.
+///
+/// Blocks of order `k` have a size of `min_block << k` and are aligned to their size.
+/// Sizes which aren't a power of two are covered by multiple top-level blocks.
+#[derive(Debug)]
+struct Buddy {
+    min_block: u64,
+    /// Offsets of free blocks per order.
+    free: Vec<BTreeSet<u64>>,
+}
+
+impl Buddy {
+    fn new(size: u64, min_block: u64) -> Self {
+        let blocks = size / min_block;
+        let orders = (u64::BITS - blocks.leading_zeros()) as usize;
+        let mut free = vec![BTreeSet::new(); orders];
+
+        // Split the size into descending power of two blocks, each aligned to its size.
+        let mut offset = 0;
+        for order in (0..orders).rev() {
+            if blocks & (1 << order) != 0 {
+                free[order].insert(offset);
+                offset += min_block << order;
+            }
+        }
+
+        Buddy { min_block, free }
+    }
+
+    fn order(&self, size: u64) -> usize {
+        let blocks = ((size + self.min_block - 1) / self.min_block).next_power_of_two();
+        blocks.trailing_zeros() as usize
+    }
+
+    /// Allocate a block, returning its offset and size.
+    fn allocate(&mut self, size: u64) -> Option<(u64, u64)> {
+        let order = self.order(size);
+        let source = (order..self.free.len()).find(|&k| !self.free[k].is_empty())?;
+        let offset = *self.free[source].iter().next()?;
+        self.free[source].remove(&offset);
+
+        // Split the block, keeping the upper halves free.
+        for k in (order..source).rev() {
+            self.free[k].insert(offset + (self.min_block << k));
+        }
+        Some((offset, self.min_block << order))
+    }
+
+    /// Free a block, returning its size.
+    fn free(&mut self, mut offset: u64, size: u64) -> u64 {
+        let mut order = self.order(size);
+        let block = self.min_block << order;
+        // Merge with free buddies.
+        while order + 1 < self.free.len() {
+            let buddy = offset ^ (self.min_block << order);
+            if !self.free[order].remove(&buddy) {
+                break;
+            }
+            offset = offset.min(buddy);
+            order += 1;
+        }
+        self.free[order].insert(offset);
+        block
+    }
+
+    fn free_blocks(&self) -> Vec<u64> {
+        self.free
+            .iter()
+            .enumerate()
+            .flat_map(|(order, blocks)| blocks.iter().map(move |_| self.min_block << order))
+            .collect()
+    }
+}
+
+impl Device {
+    /// Create a sub-allocator over a new buffer.
+    ///
+    /// # Parameters
+    ///
+    /// - `size`: Size in bytes of the buffer, rounded down to the alignment.
+    /// - `memory`: Properties of the buffer memory (see `create_buffer`).
+    /// - `strategy`: Allocation strategy.
+    /// - `label`: Optional debug name of the buffer (see `object_name`).
+    ///
+    /// Allocations are aligned to the uniform and storage buffer offset alignment.
+    #[track_caller]
+    pub unsafe fn create_buffer_allocator(
+        &self,
+        size: u64,
+        memory: MemoryFlags,
+        strategy: AllocationStrategy,
+        label: Option<&str>,
+    ) -> Result<BufferAllocator> {
+        let _scope = self.api_scope("create_buffer_allocator");
+        let limits = self.limits();
+        let alignment = limits
+            .uniform_buffer_offset_alignment
+            .max(limits.storage_buffer_offset_alignment)
+            .max(1)
+            .next_power_of_two() as u64;
+        let size = size / alignment * alignment;
+        let buffer = self.create_buffer(size, memory, label)?;
+
+        let strategy = match strategy {
+            AllocationStrategy::Linear => Strategy::Linear { head: 0 },
+            AllocationStrategy::Ring => Strategy::Ring {
+                head: 0,
+                allocations: VecDeque::new(),
+            },
+            AllocationStrategy::FreeList => Strategy::FreeList(Buddy::new(size, alignment)),
+        };
+
+        Ok(BufferAllocator {
+            buffer,
+            size,
+            alignment,
+            allocations: 0,
+            used: 0,
+            strategy,
+        })
+    }
+
+    /// Delete a sub-allocator and its buffer.
+    #[track_caller]
+    pub unsafe fn delete_buffer_allocator(&self, allocator: BufferAllocator) {
+        let _scope = self.api_scope("delete_buffer_allocator");
+        self.delete_buffer(allocator.buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handle::Generation;
+
+    const ALIGNMENT: u64 = 256;
+
+    fn allocator(size: u64, strategy: AllocationStrategy) -> BufferAllocator {
+        let strategy = match strategy {
+            AllocationStrategy::Linear => Strategy::Linear { head: 0 },
+            AllocationStrategy::Ring => Strategy::Ring {
+                head: 0,
+                allocations: VecDeque::new(),
+            },
+            AllocationStrategy::FreeList => Strategy::FreeList(Buddy::new(size, ALIGNMENT)),
+        };
+        BufferAllocator {
+            buffer: Buffer(1, 0, Generation::UNTRACKED),
+            size,
+            alignment: ALIGNMENT,
+            allocations: 0,
+            used: 0,
+            strategy,
+        }
+    }
+
+    fn offset(allocator: &mut BufferAllocator, size: u64) -> Option<u64> {
+        allocator.allocate(size).map(|range| range.offset)
+    }
+
+    fn free_blocks(allocator: &BufferAllocator) -> Vec<BTreeSet<u64>> {
+        match allocator.strategy {
+            Strategy::FreeList(ref buddy) => buddy.free.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn stats(
+        allocator: &BufferAllocator,
+        allocations: u64,
+        used: u64,
+        blocks: &[u64],
+    ) -> AllocatorStats {
+        let free = blocks.iter().sum::<u64>();
+        let largest_free_block = blocks.iter().copied().max().unwrap_or(0);
+        AllocatorStats {
+            size: allocator.size,
+            allocations,
+            used,
+            free,
+            largest_free_block,
+            fragmentation: if free > 0 {
+                1.0 - largest_free_block as f32 / free as f32
+            } else {
+                0.0
+            },
+        }
+    }
+
+    #[test]
+    fn linear() {
+        let mut allocator = allocator(1024, AllocationStrategy::Linear);
+        assert_eq!(offset(&mut allocator, 10), Some(0));
+        let range = allocator.allocate(300).unwrap();
+        assert_eq!(range.offset, 256);
+        assert_eq!(offset(&mut allocator, 512), None);
+        assert_eq!(allocator.stats(), stats(&allocator, 2, 768, &[256]));
+
+        // Single ranges aren't freed.
+        allocator.free(range);
+        assert_eq!(allocator.stats(), stats(&allocator, 2, 768, &[256]));
+        assert_eq!(offset(&mut allocator, 256), Some(768));
+        assert_eq!(offset(&mut allocator, 1), None);
+        assert_eq!(allocator.stats(), stats(&allocator, 3, 1024, &[]));
+    }
+
+    #[test]
+    fn ring_wrap() {
+        let mut allocator = allocator(1024, AllocationStrategy::Ring);
+        let a = allocator.allocate(512).unwrap();
+        let b = allocator.allocate(256).unwrap();
+        assert_eq!((a.offset, b.offset), (0, 512));
+        allocator.free(a);
+        assert_eq!(allocator.stats(), stats(&allocator, 1, 256, &[256, 512]));
+
+        // Not enough space at the end, wraps around to the start.
+        assert_eq!(offset(&mut allocator, 300), Some(0));
+        // The skipped end isn't available until the tail passes it.
+        assert_eq!(allocator.stats(), stats(&allocator, 2, 768, &[0]));
+        assert_eq!(offset(&mut allocator, 1), None);
+
+        allocator.free(b);
+        assert_eq!(allocator.stats(), stats(&allocator, 1, 512, &[512, 0]));
+        assert_eq!(offset(&mut allocator, 512), Some(512));
+    }
+
+    #[test]
+    fn ring_tail_blocks() {
+        let mut allocator = allocator(1024, AllocationStrategy::Ring);
+        let ranges = (0..4)
+            .map(|_| allocator.allocate(256).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(offset(&mut allocator, 1), None);
+
+        allocator.free(ranges[0]);
+        assert_eq!(offset(&mut allocator, 256), Some(0));
+        // Blocked by the oldest allocation, even though the end has been allocated.
+        assert_eq!(offset(&mut allocator, 1), None);
+
+        allocator.free(ranges[1]);
+        assert_eq!(offset(&mut allocator, 512), None);
+        assert_eq!(offset(&mut allocator, 256), Some(256));
+        assert_eq!(allocator.stats(), stats(&allocator, 4, 1024, &[0]));
+
+        allocator.free(ranges[2]);
+        assert_eq!(allocator.stats(), stats(&allocator, 3, 768, &[256]));
+        allocator.free(ranges[3]);
+        assert_eq!(allocator.stats(), stats(&allocator, 2, 512, &[512, 0]));
+        assert_eq!(offset(&mut allocator, 512), Some(512));
+    }
+
+    #[test]
+    #[should_panic(expected = "allocation order")]
+    fn ring_free_out_of_order() {
+        let mut allocator = allocator(1024, AllocationStrategy::Ring);
+        let _a = allocator.allocate(256).unwrap();
+        let b = allocator.allocate(256).unwrap();
+        allocator.free(b);
+    }
+
+    #[test]
+    fn buddy_merge() {
+        // Covered by a 1024 and a 256 byte top-level block.
+        let mut allocator = allocator(1280, AllocationStrategy::FreeList);
+        let initial = free_blocks(&allocator);
+        let initial_stats = allocator.stats();
+        assert_eq!(initial_stats, stats(&allocator, 0, 0, &[256, 1024]));
+
+        let ranges = (0..5)
+            .map(|_| allocator.allocate(ALIGNMENT).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(offset(&mut allocator, 1), None);
+        assert_eq!(allocator.stats(), stats(&allocator, 5, 1280, &[]));
+
+        for &i in &[3, 0, 4, 1, 2] {
+            allocator.free(ranges[i]);
+        }
+        assert_eq!(free_blocks(&allocator), initial);
+        assert_eq!(allocator.stats(), initial_stats);
+
+        // Mixed sizes, freed in a different order than allocated.
+        let a = allocator.allocate(300).unwrap();
+        let b = allocator.allocate(1).unwrap();
+        let c = allocator.allocate(256).unwrap();
+        let d = allocator.allocate(256).unwrap();
+        assert_eq!(allocator.stats(), stats(&allocator, 4, 1280, &[]));
+        for range in [c, a, d, b] {
+            allocator.free(range);
+        }
+        assert_eq!(free_blocks(&allocator), initial);
+        assert_eq!(allocator.stats(), initial_stats);
+    }
+
+    #[test]
+    fn buddy_stats() {
+        let mut allocator = allocator(1280, AllocationStrategy::FreeList);
+        let a = allocator.allocate(256).unwrap();
+        assert_eq!(a.offset, 1024);
+        assert_eq!(offset(&mut allocator, 512), Some(0));
+        assert_eq!(allocator.stats(), stats(&allocator, 2, 768, &[512]));
+        assert_eq!(offset(&mut allocator, 200), Some(512));
+        assert_eq!(allocator.stats(), stats(&allocator, 3, 1024, &[256]));
+
+        // Blocks at 768 and 1024 aren't buddies.
+        allocator.free(a);
+        let stats = allocator.stats();
+        assert_eq!(stats, self::stats(&allocator, 2, 768, &[256, 256]));
+        assert_eq!(stats.fragmentation, 0.5);
+        assert_eq!(offset(&mut allocator, 512), None);
+    }
+
+    #[test]
+    fn reset() {
+        for strategy in [
+            AllocationStrategy::Linear,
+            AllocationStrategy::Ring,
+            AllocationStrategy::FreeList,
+        ] {
+            let mut allocator = allocator(1280, strategy);
+            let initial = allocator.stats();
+            for size in [100, 512, 256] {
+                allocator.allocate(size).unwrap();
+            }
+            assert_ne!(allocator.stats(), initial);
+
+            allocator.reset();
+            assert_eq!(allocator.stats(), initial, "{:?}", strategy);
+            let range = allocator.allocate(1024).unwrap();
+            assert_eq!(range.offset, 0, "{:?}", strategy);
+        }
+    }
+}
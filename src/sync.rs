@@ -1,9 +1,15 @@
 use crate::__gl;
+use crate::__gl::types::GLsync;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
-use crate::Device;
+use crate::debug::PtrObject;
+use crate::{Device, Error, Result};
+use std::ptr;
 
 bitflags!(
     /// Memory barrier.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Barrier: u32 {
         /// Read access to a vertex buffer.
         ///
@@ -77,6 +83,7 @@ bitflags!(
 
 bitflags!(
     /// Memory barrier for by-region dependencies.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct RegionBarrier: u32 {
         const UNIFORM_READ = __gl::UNIFORM_BARRIER_BIT;
         const SAMPLED_IMAGE_READ = __gl::TEXTURE_FETCH_BARRIER_BIT;
@@ -87,9 +94,116 @@ bitflags!(
     }
 );
 
+/// Fence sync object.
+///
+/// Fences are signaled once all commands submitted before their creation have been completed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Fence(pub(crate) GLsync);
+
+// Sync objects are shared between contexts.
+unsafe impl Send for Fence {}
+unsafe impl Sync for Fence {}
+
+impl PtrObject for Fence {
+    fn ptr(&self) -> GLsync {
+        self.0
+    }
+}
+
+/// Result of waiting on a fence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FenceStatus {
+    /// Fence was already signaled when starting to wait.
+    AlreadySignaled,
+    /// Fence has been signaled during the wait.
+    Signaled,
+    /// Fence was not signaled within the timeout.
+    TimeoutExpired,
+}
+
 impl Device {
+    /// Create a new fence, signaled when all previously submitted commands are completed.
+    #[track_caller]
+    pub unsafe fn create_fence(&self) -> Fence {
+        let _scope = self.api_scope("create_fence");
+        let fence = Fence(self.0.FenceSync(__gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateFence {
+            fence: fence.0 as _,
+        });
+        fence
+    }
+
+    /// Delete a fence.
+    ///
+    /// Fences may be deleted while pending, the sync object is released once signaled.
+    #[track_caller]
+    pub unsafe fn delete_fence(&self, fence: Fence) {
+        let _scope = self.api_scope("delete_fence");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteFence {
+            fence: fence.0 as _,
+        });
+        self.0.DeleteSync(fence.0);
+    }
+
+    /// Wait on the host until the fence is signaled or the timeout expires.
+    ///
+    /// Pending commands are flushed before waiting.
+    ///
+    /// # Parameters
+    ///
+    /// - `timeout`: Timeout in nanoseconds. A timeout of `0` only polls the fence status.
+    #[track_caller]
+    pub unsafe fn wait_fence(&self, fence: Fence, timeout: u64) -> Result<FenceStatus> {
+        let _scope = self.api_scope("wait_fence");
+        let status = self
+            .0
+            .ClientWaitSync(fence.0, __gl::SYNC_FLUSH_COMMANDS_BIT, timeout);
+        match status {
+            __gl::ALREADY_SIGNALED => Ok(FenceStatus::AlreadySignaled),
+            __gl::CONDITION_SATISFIED => Ok(FenceStatus::Signaled),
+            __gl::TIMEOUT_EXPIRED => Ok(FenceStatus::TimeoutExpired),
+            _ => {
+                self.get_error()?;
+                Err(Error::InvalidUsage(format!(
+                    "waiting on fence {:?} failed",
+                    fence.0
+                )))
+            }
+        }
+    }
+
+    /// Wait on the device until the fence is signaled.
+    ///
+    /// Commands submitted after this call won't be executed before the fence is signaled.
+    /// The host is not blocked. Can be used to synchronize work between shared contexts.
+    #[track_caller]
+    pub unsafe fn server_wait_fence(&self, fence: Fence) {
+        let _scope = self.api_scope("server_wait_fence");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::ServerWaitFence {
+            fence: fence.0 as _,
+        });
+        self.0.WaitSync(fence.0, 0, __gl::TIMEOUT_IGNORED);
+    }
+
+    /// Check if the fence is signaled without blocking.
+    #[track_caller]
+    pub unsafe fn is_fence_signaled(&self, fence: Fence) -> bool {
+        let _scope = self.api_scope("is_fence_signaled");
+        let mut status = 0;
+        self.0
+            .GetSynciv(fence.0, __gl::SYNC_STATUS, 1, ptr::null_mut(), &mut status);
+        status as u32 == __gl::SIGNALED
+    }
+
     ///
+    #[track_caller]
     pub unsafe fn memory_barrier(&self, mut flags: Barrier) {
+        let _scope = self.api_scope("memory_barrier");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::MemoryBarrier { flags });
         if flags.contains(Barrier::INPUT_ATTACHMENT_READ) {
             self.0.TextureBarrier();
         }
@@ -105,7 +219,11 @@ impl Device {
     }
 
     ///
+    #[track_caller]
     pub unsafe fn memory_barrier_by_region(&self, flags: RegionBarrier) {
+        let _scope = self.api_scope("memory_barrier_by_region");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::MemoryBarrierByRegion { flags });
         self.0.MemoryBarrierByRegion(flags.bits());
     }
 }
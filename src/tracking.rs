@@ -0,0 +1,171 @@
+//! Resource tracking.
+//!
+//! The device keeps a registry of all objects created via `create_*`, recording the creation
+//! call site, the label assigned by `object_name` and an estimate of the occupied device memory.
+//! Objects still alive when the device is dropped are reported as leaks on stderr.
+//! Shared objects of a `DeviceGroup` are reported once the group and all of its devices are dropped.
+//!
+//! Requires the `tracking` feature.
+
+use crate::__gl::types::GLuint;
+use crate::debug::ObjectType;
+use crate::device::Device;
+use crate::format::Format;
+use crate::image::ImageType;
+
+use std::collections::HashMap;
+use std::panic::Location;
+use std::sync::{Arc, MutexGuard, PoisonError};
+
+/// Object alive on the device.
+#[derive(Debug, Clone)]
+pub struct Resource {
+    /// Kind of the object.
+    pub ty: ObjectType,
+    /// Raw GL object name.
+    pub name: GLuint,
+    /// Label assigned via `Device::object_name`.
+    pub label: Option<String>,
+    /// Call site of the `create_*` call.
+    pub location: &'static Location<'static>,
+    /// Estimated device memory in bytes.
+    ///
+    /// Zero for objects without own storage (e.g. image views or texel buffers).
+    pub size: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct Registry {
+    next: u64,
+    /// Resources with their creation index, by context key (see `Device::context_key`), type and name.
+    resources: HashMap<(u32, ObjectType, GLuint), (u64, Resource)>,
+}
+
+impl Registry {
+    /// Remaining objects in creation order.
+    pub(crate) fn resources(&self) -> Vec<Resource> {
+        let mut resources = self.resources.values().cloned().collect::<Vec<_>>();
+        resources.sort_by_key(|&(index, _)| index);
+        resources
+            .into_iter()
+            .map(|(_, resource)| resource)
+            .collect()
+    }
+}
+
+/// Estimated memory of an image including all mipmap levels.
+pub(crate) fn image_size(ty: ImageType, format: Format, levels: u32) -> u64 {
+    let samples = match ty {
+        ImageType::D2 { samples, .. } => samples.max(1),
+        _ => 1,
+    };
+    let level_size = |level: u32| {
+        let extent = |size: u32| (size >> level).max(1) as u64;
+        extent(ty.width()) * extent(ty.height()) * extent(ty.depth())
+    };
+    let texels: u64 = (0..levels).map(level_size).sum();
+    texels * ty.layers() as u64 * samples as u64 * format.texel_size() as u64
+}
+
+impl Device {
+    fn tracking(&self) -> MutexGuard<'_, Registry> {
+        self.1
+            .tracking
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[track_caller]
+    pub(crate) fn track_resource(&self, ty: ObjectType, name: GLuint, size: u64) {
+        let key = (self.context_key(ty), ty, name);
+        let mut registry = self.tracking();
+        let index = registry.next;
+        registry.next += 1;
+        registry.resources.insert(
+            key,
+            (
+                index,
+                Resource {
+                    ty,
+                    name,
+                    label: None,
+                    location: Location::caller(),
+                    size,
+                },
+            ),
+        );
+    }
+
+    pub(crate) fn untrack_resource(&self, ty: ObjectType, name: GLuint) {
+        let key = (self.context_key(ty), ty, name);
+        self.tracking().resources.remove(&key);
+    }
+
+    pub(crate) fn label_resource(&self, ty: ObjectType, name: GLuint, label: &str) {
+        let key = (self.context_key(ty), ty, name);
+        if let Some((_, resource)) = self.tracking().resources.get_mut(&key) {
+            resource.label = Some(label.to_string());
+        }
+    }
+
+    /// Objects currently alive on the device, in creation order.
+    ///
+    /// Only objects created via the device are tracked. Devices of a `DeviceGroup`
+    /// also list the shared objects created by other devices of the group.
+    /// Requires the `tracking` feature.
+    pub fn resources(&self) -> impl Iterator<Item = Resource> {
+        let mut resources = self
+            .tracking()
+            .resources
+            .iter()
+            .filter(|&(&(context, ty, _), _)| context == self.context_key(ty))
+            .map(|(_, resource)| resource.clone())
+            .collect::<Vec<_>>();
+        resources.sort_by_key(|&(index, _)| index);
+        resources.into_iter().map(|(_, resource)| resource)
+    }
+}
+
+/// Report objects still alive on destruction to stderr.
+pub(crate) fn report_leaks(leaks: &[Resource], owner: &str) {
+    if leaks.is_empty() {
+        return;
+    }
+
+    let size = leaks.iter().map(|resource| resource.size).sum::<u64>();
+    eprintln!(
+        "grr: {} object(s) leaked on {} destruction ({} bytes):",
+        leaks.len(),
+        owner,
+        size
+    );
+    for resource in leaks {
+        let label = match resource.label {
+            Some(ref label) => format!(" {:?}", label),
+            None => String::new(),
+        };
+        eprintln!(
+            "    {:?} {}{} ({} bytes), created at {}",
+            resource.ty, resource.name, label, resource.size, resource.location
+        );
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        // Shared objects are reported by the last device or group referencing the registry.
+        let shared = Arc::strong_count(&self.1.tracking) > 1;
+        let leaks = self
+            .resources()
+            .filter(|resource| !shared || !resource.ty.is_shared())
+            .collect::<Vec<_>>();
+        if shared {
+            let mut registry = self.tracking();
+            for resource in &leaks {
+                let key = (self.context_key(resource.ty), resource.ty, resource.name);
+                registry.resources.remove(&key);
+            }
+        }
+        report_leaks(&leaks, "device");
+    }
+}
@@ -1,10 +1,14 @@
 use crate::{
-    BaseFormat, Buffer, BufferRange, Device, Extent, FormatLayout, Image, Offset, Region,
-    SubresourceLayers, __gl, WHOLE_SIZE,
+    __gl, BaseFormat, Buffer, BufferRange, Device, Extent, FormatLayout, Image, Offset, Pod,
+    Region, SubresourceLayers, WHOLE_SIZE,
 };
 
+#[cfg(feature = "capture")]
+use crate::capture::Command;
+
 /// Specifies the layout of the host or buffer memory.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryLayout {
     ///
     pub base_format: BaseFormat,
@@ -18,7 +22,55 @@ pub struct MemoryLayout {
     pub alignment: u32,
 }
 
+impl MemoryLayout {
+    /// Size in bytes of a single texel.
+    ///
+    /// Returns `None` for packed depth stencil layouts with another base format.
+    pub(crate) fn texel_size(&self) -> Option<u64> {
+        Some(match (self.base_format, self.format_layout) {
+            (BaseFormat::DepthStencil, FormatLayout::U24U8) => 4,
+            (BaseFormat::DepthStencil, FormatLayout::F32U8) => 8,
+            (_, FormatLayout::U24U8) | (_, FormatLayout::F32U8) => return None,
+            (base_format, format_layout) => {
+                let component_size = match format_layout {
+                    FormatLayout::U8 | FormatLayout::I8 => 1,
+                    FormatLayout::U16 | FormatLayout::I16 | FormatLayout::F16 => 2,
+                    _ => 4,
+                };
+                base_format.num_components() as u64 * component_size
+            }
+        })
+    }
+
+    /// Number of bytes touched by a pixel transfer of `(width, height, depth)` texels.
+    ///
+    /// All dimensions must be non-zero. Returns `None` if the texel size is invalid.
+    pub(crate) fn transfer_size(&self, (width, height, depth): (u32, u32, u32)) -> Option<u64> {
+        let texel_size = self.texel_size()?;
+        let row_length = if self.row_length > 0 {
+            self.row_length
+        } else {
+            width
+        };
+        let image_height = if self.image_height > 0 {
+            self.image_height
+        } else {
+            height
+        };
+        let alignment = self.alignment.max(1) as u64;
+        let row_size = (row_length as u64 * texel_size + alignment - 1) / alignment * alignment;
+        let image_size = row_size * image_height as u64;
+
+        Some(
+            (depth as u64 - 1) * image_size
+                + (height as u64 - 1) * row_size
+                + width as u64 * texel_size,
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImageCopy {
     /// Layers of the source image.
     pub src_subresource: SubresourceLayers,
@@ -33,6 +85,7 @@ pub struct ImageCopy {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BufferImageCopy {
     /// Offset in bytes from the start of the source/destination buffer.
     pub buffer_offset: u64,
@@ -47,6 +100,7 @@ pub struct BufferImageCopy {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HostImageCopy {
     /// Layout of the source/destination host memory.
     pub host_layout: MemoryLayout,
@@ -154,12 +208,21 @@ impl Device {
     }
 
     /// Copy image data from host memory to device memory.
-    pub unsafe fn copy_host_to_image<T>(
+    #[track_caller]
+    pub unsafe fn copy_host_to_image<T: Pod>(
         &self,
         src_host: &[T],
         dst_image: Image,
         region: HostImageCopy,
     ) {
+        let _scope = self.api_scope("copy_host_to_image");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyHostToImage {
+            data: crate::as_bytes(src_host).to_vec(),
+            image: dst_image,
+            region: region.clone(),
+        });
+        self.check_handle("copy_host_to_image", dst_image);
         self.unbind_pixel_unpack_buffer();
         self.copy_to_image(
             dst_image,
@@ -172,12 +235,22 @@ impl Device {
     }
 
     /// Copy image data from buffer to device memory.
+    #[track_caller]
     pub unsafe fn copy_buffer_to_image(
         &self,
         src_buffer: Buffer,
         dst_image: Image,
         region: BufferImageCopy,
     ) {
+        let _scope = self.api_scope("copy_buffer_to_image");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyBufferToImage {
+            buffer: src_buffer,
+            image: dst_image,
+            region: region.clone(),
+        });
+        self.check_handle("copy_buffer_to_image", src_buffer);
+        self.check_handle("copy_buffer_to_image", dst_image);
         self.bind_pixel_unpack_buffer(src_buffer);
         self.copy_to_image(
             dst_image,
@@ -189,7 +262,7 @@ impl Device {
         );
     }
 
-    unsafe fn map_subresource_region(
+    pub(crate) unsafe fn map_subresource_region(
         image: Image,
         subresource: &SubresourceLayers,
         offset: Offset,
@@ -282,12 +355,21 @@ impl Device {
     }
 
     /// Copy image data from device memory to a host array.
-    pub unsafe fn copy_image_to_host<T>(
+    #[track_caller]
+    pub unsafe fn copy_image_to_host<T: Pod>(
         &self,
         src_image: Image,
         dst_host: &mut [T],
         region: HostImageCopy,
     ) {
+        let _scope = self.api_scope("copy_image_to_host");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyImageToHost {
+            image: src_image,
+            size: std::mem::size_of_val(dst_host),
+            region: region.clone(),
+        });
+        self.check_handle("copy_image_to_host", src_image);
         self.unbind_pixel_pack_buffer();
         self.copy_image_to(
             src_image,
@@ -303,12 +385,22 @@ impl Device {
     }
 
     /// Copy image data from device memory to a buffer object.
+    #[track_caller]
     pub unsafe fn copy_image_to_buffer(
         &self,
         src_image: Image,
         dst_buffer: Buffer,
         region: BufferImageCopy,
     ) {
+        let _scope = self.api_scope("copy_image_to_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyImageToBuffer {
+            image: src_image,
+            buffer: dst_buffer,
+            region: region.clone(),
+        });
+        self.check_handle("copy_image_to_buffer", src_image);
+        self.check_handle("copy_image_to_buffer", dst_buffer);
         self.bind_pixel_pack_buffer(dst_buffer);
         let buffer_size = self.get_buffer_size(dst_buffer) - region.buffer_offset;
         self.copy_image_to(
@@ -334,12 +426,20 @@ impl Device {
     ///
     /// * [copy_attachement_to_buffer](struct.Device.html#method.copy_attachment_to_buffer)
     /// for an asynchronous alternative.
-    pub unsafe fn copy_attachment_to_host<T: Sized>(
+    #[track_caller]
+    pub unsafe fn copy_attachment_to_host<T: Pod>(
         &self,
         region: Region,
         layout: MemoryLayout,
         data: &mut [T],
     ) {
+        let _scope = self.api_scope("copy_attachment_to_host");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyAttachmentToHost {
+            region,
+            layout,
+            size: std::mem::size_of_val(data),
+        });
         self.set_pixel_pack_params(&layout);
         self.unbind_pixel_pack_buffer();
         self.0.ReadnPixels(
@@ -360,12 +460,21 @@ impl Device {
     /// # Remarks:
     ///
     /// The transfer for `copy_attachment_to_buffer` is asynchronous.
+    #[track_caller]
     pub unsafe fn copy_attachment_to_buffer(
         &self,
         region: Region,
         layout: MemoryLayout,
         buffer_range: BufferRange,
     ) {
+        let _scope = self.api_scope("copy_attachment_to_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyAttachmentToBuffer {
+            region,
+            layout,
+            buffer: buffer_range,
+        });
+        self.check_handle("copy_attachment_to_buffer", buffer_range.buffer);
         self.set_pixel_pack_params(&layout);
         self.bind_pixel_pack_buffer(buffer_range.buffer);
         self.0.ReadnPixels(
@@ -380,7 +489,17 @@ impl Device {
         );
     }
 
+    #[track_caller]
     pub unsafe fn copy_image(&self, src_image: Image, dst_image: Image, region: ImageCopy) {
+        let _scope = self.api_scope("copy_image");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyImage {
+            src: src_image,
+            dst: dst_image,
+            region: region.clone(),
+        });
+        self.check_handle("copy_image", src_image);
+        self.check_handle("copy_image", dst_image);
         let (src_offset, _) = Self::map_subresource_region(
             src_image,
             &region.src_subresource,
@@ -422,6 +541,7 @@ impl Device {
     /// - `size` **must** be less than or equal the size of `src_buffer` minus `src_offset`.
     /// - `size` **must** be less than or equal the size of `dst_buffer` minus `dst_offset`.
     /// - The source and destination region **must** not overlap in memory.
+    #[track_caller]
     pub unsafe fn copy_buffer(
         &self,
         src_buffer: Buffer,
@@ -430,6 +550,20 @@ impl Device {
         dst_offset: u64,
         size: u64,
     ) {
+        let _scope = self.api_scope("copy_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CopyBuffer {
+            src: src_buffer,
+            src_offset,
+            dst: dst_buffer,
+            dst_offset,
+            size,
+        });
+        self.check_handle("copy_buffer", src_buffer);
+        self.check_handle("copy_buffer", dst_buffer);
+        #[cfg(feature = "validation")]
+        self.validator()
+            .copy_buffer(src_buffer, src_offset, dst_buffer, dst_offset, size);
         self.0.CopyNamedBufferSubData(
             src_buffer.0,
             dst_buffer.0,
@@ -440,7 +574,12 @@ impl Device {
     }
 
     /// Fill a region of a buffer with a fixed value
+    #[track_caller]
     pub unsafe fn fill_buffer(&self, buffer: BufferRange, value: u32) {
+        let _scope = self.api_scope("fill_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::FillBuffer { buffer, value });
+        self.check_handle("fill_buffer", buffer.buffer);
         let size = if buffer.size == WHOLE_SIZE {
             ((self.get_buffer_size(buffer.buffer) - buffer.offset as u64) & !0x3) as _
         } else {
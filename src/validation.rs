@@ -0,0 +1,869 @@
+//! Runtime validation of `Valid usage` rules.
+//!
+//! Tracks bound state and resource metadata of a device and checks the documented
+//! valid usage rules of API calls. Violations are reported through the debug callback
+//! (`DebugReport::ERROR`, `DebugSource::ThirdParty`, `DebugType::Error`), prefixed with
+//! the offending call. Without a debug callback, violations are only recorded in the debug log
+//! (`Device::debug_log`), which requires a non-zero `DebugFilter::log_capacity`.
+//!
+//! Only objects created via the device are known to the validation layer.
+//! Devices of a `DeviceGroup` share the state of shared objects (buffers, shaders and pipelines).
+//!
+//! Requires the `validation` feature.
+
+use crate::__gl;
+use crate::__gl::types::GLuint;
+use crate::debug::{DebugHandler, DebugMessage, DebugReport, DebugSource, DebugType};
+use crate::{
+    Buffer, Device, GraphicsPipelineDesc, MappingFlags, Pipeline, Shader, ShaderStage, VertexArray,
+    VertexAttributeDesc, VertexBufferView,
+};
+
+use std::cell::RefMut;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::sync::{MutexGuard, PoisonError};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PipelineKind {
+    Graphics,
+    Mesh,
+    Compute,
+}
+
+struct BufferState {
+    size: u64,
+    mapping: Option<Mapping>,
+}
+
+/// Currently mapped range of a buffer.
+struct Mapping {
+    size: u64,
+    flags: MappingFlags,
+}
+
+#[derive(Default)]
+struct VertexArrayState {
+    /// Binding slots referenced by the vertex attributes.
+    bindings: Vec<u32>,
+    /// Binding slots with a bound vertex buffer.
+    bound: HashSet<u32>,
+    index_buffer: bool,
+}
+
+/// Tracked objects shared between contexts.
+#[derive(Default)]
+pub(crate) struct Objects {
+    buffers: HashMap<GLuint, BufferState>,
+    shaders: HashMap<GLuint, ShaderStage>,
+    pipelines: HashMap<GLuint, PipelineKind>,
+}
+
+/// Tracked device state.
+pub(crate) struct State {
+    vertex_arrays: HashMap<GLuint, VertexArrayState>,
+    pipeline: Option<GLuint>,
+    vertex_array: Option<GLuint>,
+    draw_indirect_buffer: bool,
+    dispatch_indirect_buffer: bool,
+    /// Number of viewports specified.
+    viewports: u32,
+    /// Number of scissors specified.
+    scissors: u32,
+    scissor_test: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            vertex_arrays: HashMap::new(),
+            pipeline: None,
+            vertex_array: None,
+            draw_indirect_buffer: false,
+            dispatch_indirect_buffer: false,
+            // Context state contains one viewport and scissor rectangle.
+            viewports: 1,
+            scissors: 1,
+            scissor_test: true,
+        }
+    }
+}
+
+pub(crate) struct Validator<'a> {
+    state: RefMut<'a, State>,
+    objects: MutexGuard<'a, Objects>,
+    callback: Option<&'a DebugHandler>,
+}
+
+impl Device {
+    pub(crate) fn validator(&self) -> Validator<'_> {
+        Validator {
+            state: self.1.validation.borrow_mut(),
+            objects: self
+                .1
+                .validation_objects
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+            callback: self.1.debug_callback.as_deref(),
+        }
+    }
+}
+
+impl Validator<'_> {
+    fn report<M: fmt::Display>(&self, call: &str, msg: M) {
+        let msg = format!("{}: {}", call, msg);
+        if let Some(handler) = self.callback {
+            handler.report(DebugMessage {
+                severity: DebugReport::ERROR,
+                source: DebugSource::ThirdParty,
+                ty: DebugType::Error,
+                id: 0,
+                message: &msg,
+            });
+        }
+    }
+
+    fn check_buffer(&self, call: &str, buffer: Buffer) -> Option<&BufferState> {
+        let state = self.objects.buffers.get(&buffer.0);
+        if state.is_none() {
+            self.report(
+                call,
+                format_args!("buffer {} is not a valid buffer", buffer.0),
+            );
+        }
+        state
+    }
+
+    fn check_range<T: fmt::Display + PartialOrd>(&self, call: &str, name: &str, range: &Range<T>) {
+        if range.end <= range.start {
+            self.report(
+                call,
+                format_args!(
+                    "`{0}.end` ({1}) must be larger than `{0}.start` ({2})",
+                    name, range.end, range.start
+                ),
+            );
+        }
+    }
+
+    pub fn create_buffer(&mut self, buffer: Buffer, size: u64) {
+        self.objects.buffers.insert(
+            buffer.0,
+            BufferState {
+                size,
+                mapping: None,
+            },
+        );
+    }
+
+    pub fn delete_buffers(&mut self, buffers: &[Buffer]) {
+        for buffer in buffers {
+            if self.objects.buffers.remove(&buffer.0).is_none() {
+                self.report(
+                    "delete_buffers",
+                    format_args!("buffer {} is not a valid buffer", buffer.0),
+                );
+            }
+        }
+    }
+
+    pub fn map_buffer(&mut self, buffer: Buffer, range: &Range<u64>, mapping: MappingFlags) {
+        let call = "map_buffer";
+        if let Some(state) = self.check_buffer(call, buffer) {
+            if buffer.1 & (__gl::MAP_READ_BIT | __gl::MAP_WRITE_BIT) == 0 {
+                self.report(
+                    call,
+                    format_args!(
+                        "buffer {} must be created with `CPU_MAP_READ` or `CPU_MAP_WRITE`",
+                        buffer.0
+                    ),
+                );
+            }
+            let write_only = MappingFlags::INVALIDATE_RANGE
+                | MappingFlags::INVALIDATE_BUFFER
+                | MappingFlags::FLUSH_EXPLICIT;
+            if mapping.intersects(write_only) && buffer.1 & __gl::MAP_WRITE_BIT == 0 {
+                self.report(
+                    call,
+                    format_args!(
+                        "`{:?}` requires buffer {} to be created with `CPU_MAP_WRITE`",
+                        mapping & write_only,
+                        buffer.0
+                    ),
+                );
+            }
+            if range.end > state.size {
+                self.report(
+                    call,
+                    format_args!(
+                        "`range.end` ({}) exceeds the size of buffer {} ({})",
+                        range.end, buffer.0, state.size
+                    ),
+                );
+            }
+            if state.mapping.is_some() {
+                self.report(call, format_args!("buffer {} is already mapped", buffer.0));
+            }
+        }
+        self.check_range(call, "range", range);
+
+        if let Some(state) = self.objects.buffers.get_mut(&buffer.0) {
+            state.mapping = Some(Mapping {
+                size: range.end.saturating_sub(range.start),
+                flags: mapping,
+            });
+        }
+    }
+
+    pub fn flush_mapped_buffer_range(&self, buffer: Buffer, range: &Range<u64>) {
+        let call = "flush_mapped_buffer_range";
+        if let Some(state) = self.check_buffer(call, buffer) {
+            match state.mapping {
+                Some(ref mapping) if mapping.flags.contains(MappingFlags::FLUSH_EXPLICIT) => {
+                    if range.end > mapping.size {
+                        self.report(
+                            call,
+                            format_args!(
+                                "`range.end` ({}) exceeds the size of the mapped range of buffer {} ({})",
+                                range.end, buffer.0, mapping.size
+                            ),
+                        );
+                    }
+                }
+                Some(_) => self.report(
+                    call,
+                    format_args!("buffer {} is not mapped with `FLUSH_EXPLICIT`", buffer.0),
+                ),
+                None => self.report(call, format_args!("buffer {} is not mapped", buffer.0)),
+            }
+        }
+        self.check_range(call, "range", range);
+    }
+
+    pub fn invalidate_buffer(&self, buffer: Buffer, range: Option<&Range<u64>>) {
+        let call = match range {
+            Some(_) => "invalidate_buffer_range",
+            None => "invalidate_buffer",
+        };
+        if let Some(state) = self.check_buffer(call, buffer) {
+            if state.mapping.is_some() && buffer.1 & __gl::MAP_PERSISTENT_BIT == 0 {
+                self.report(
+                    call,
+                    format_args!("buffer {} is mapped non-persistently", buffer.0),
+                );
+            }
+            if let Some(range) = range {
+                if range.end > state.size {
+                    self.report(
+                        call,
+                        format_args!(
+                            "`range.end` ({}) exceeds the size of buffer {} ({})",
+                            range.end, buffer.0, state.size
+                        ),
+                    );
+                }
+            }
+        }
+        if let Some(range) = range {
+            self.check_range(call, "range", range);
+        }
+    }
+
+    pub fn unmap_buffer(&mut self, buffer: Buffer) {
+        let call = "unmap_buffer";
+        if let Some(state) = self.check_buffer(call, buffer) {
+            if state.mapping.is_none() {
+                self.report(call, format_args!("buffer {} is not mapped", buffer.0));
+            }
+        }
+
+        if let Some(state) = self.objects.buffers.get_mut(&buffer.0) {
+            state.mapping = None;
+        }
+    }
+
+    pub fn bind_draw_indirect_buffer(&mut self, buffer: Option<Buffer>) {
+        if let Some(buffer) = buffer {
+            self.check_buffer("bind_draw_indirect_buffer", buffer);
+        }
+        self.state.draw_indirect_buffer = buffer.is_some();
+    }
+
+    pub fn bind_dispatch_indirect_buffer(&mut self, buffer: Option<Buffer>) {
+        if let Some(buffer) = buffer {
+            self.check_buffer("bind_dispatch_indirect_buffer", buffer);
+        }
+        self.state.dispatch_indirect_buffer = buffer.is_some();
+    }
+
+    pub fn copy_buffer(
+        &self,
+        src_buffer: Buffer,
+        src_offset: u64,
+        dst_buffer: Buffer,
+        dst_offset: u64,
+        size: u64,
+    ) {
+        let call = "copy_buffer";
+        let src = self.check_buffer(call, src_buffer);
+        let dst = self.check_buffer(call, dst_buffer);
+        for (name, buffer, state, offset) in [
+            ("src", src_buffer, src, src_offset),
+            ("dst", dst_buffer, dst, dst_offset),
+        ]
+        .iter()
+        {
+            if let Some(state) = state {
+                if *offset >= state.size || size > state.size - offset {
+                    self.report(
+                        call,
+                        format_args!(
+                            "region {}..{} exceeds the size of `{}_buffer` {} ({})",
+                            offset,
+                            offset + size,
+                            name,
+                            buffer.0,
+                            state.size
+                        ),
+                    );
+                }
+            }
+        }
+        if src_buffer.0 == dst_buffer.0
+            && src_offset < dst_offset + size
+            && dst_offset < src_offset + size
+        {
+            self.report(call, "source and destination regions overlap");
+        }
+    }
+
+    pub fn create_shader(&mut self, shader: Shader, stage: ShaderStage) {
+        self.objects.shaders.insert(shader.0, stage);
+    }
+
+    pub fn delete_shaders(&mut self, call: &str, shaders: &[Shader]) {
+        for shader in shaders {
+            if self.objects.shaders.remove(&shader.0).is_none() {
+                self.report(
+                    call,
+                    format_args!("shader {} is not a valid shader", shader.0),
+                );
+            }
+        }
+    }
+
+    fn check_stage(&self, call: &str, name: &str, shader: Option<Shader>, stage: ShaderStage) {
+        let shader = match shader {
+            Some(shader) => shader,
+            None => return,
+        };
+        match self.objects.shaders.get(&shader.0) {
+            Some(&actual) if actual != stage => self.report(
+                call,
+                format_args!(
+                    "`{}` ({}) must be created with `ShaderStage::{:?}`, found `ShaderStage::{:?}`",
+                    name, shader.0, stage, actual
+                ),
+            ),
+            Some(_) => {}
+            None => self.report(
+                call,
+                format_args!("`{}` ({}) is not a valid shader", name, shader.0),
+            ),
+        }
+    }
+
+    pub fn create_graphics_pipeline(&self, desc: &GraphicsPipelineDesc) {
+        let call = "create_graphics_pipeline";
+        let stages = [
+            ("vertex_shader", desc.vertex_shader, ShaderStage::Vertex),
+            (
+                "tessellation_control_shader",
+                desc.tessellation_control_shader,
+                ShaderStage::TessellationControl,
+            ),
+            (
+                "tessellation_evaluation_shader",
+                desc.tessellation_evaluation_shader,
+                ShaderStage::TessellationEvaluation,
+            ),
+            (
+                "geometry_shader",
+                desc.geometry_shader,
+                ShaderStage::Geometry,
+            ),
+            (
+                "fragment_shader",
+                desc.fragment_shader,
+                ShaderStage::Fragment,
+            ),
+            ("mesh_shader", desc.mesh_shader, ShaderStage::MeshNv),
+            ("task_shader", desc.task_shader, ShaderStage::TaskNv),
+        ];
+        for &(name, shader, stage) in stages.iter() {
+            self.check_stage(call, name, shader, stage);
+        }
+        if desc.vertex_shader.is_none() && desc.mesh_shader.is_none() {
+            self.report(call, "either a vertex or a mesh shader is required");
+        }
+    }
+
+    pub fn create_compute_pipeline(&self, compute_shader: Shader) {
+        self.check_stage(
+            "create_compute_pipeline",
+            "compute_shader",
+            Some(compute_shader),
+            ShaderStage::Compute,
+        );
+    }
+
+    /// Validate shader compatibility, returning the kind of the pipeline.
+    fn pipeline_kind(&self, shaders: &[Shader]) -> PipelineKind {
+        let call = "create_pipeline";
+        let mut stages = Vec::with_capacity(shaders.len());
+        for shader in shaders {
+            match self.objects.shaders.get(&shader.0) {
+                Some(&stage) if stages.contains(&stage) => self.report(
+                    call,
+                    format_args!("multiple shaders for `ShaderStage::{:?}`", stage),
+                ),
+                Some(&stage) => stages.push(stage),
+                None => self.report(
+                    call,
+                    format_args!("shader {} is not a valid shader", shader.0),
+                ),
+            }
+        }
+
+        let has = |stage| stages.contains(&stage);
+        let primitive = has(ShaderStage::Vertex)
+            || has(ShaderStage::TessellationControl)
+            || has(ShaderStage::TessellationEvaluation)
+            || has(ShaderStage::Geometry);
+        let mesh = has(ShaderStage::MeshNv) || has(ShaderStage::TaskNv);
+        let compute = has(ShaderStage::Compute);
+
+        if compute && stages.len() > 1 {
+            self.report(call, "compute shaders can't be combined with other stages");
+        }
+        if primitive && mesh {
+            self.report(
+                call,
+                "mesh and task shaders can't be combined with primitive shading stages",
+            );
+        }
+
+        if compute {
+            PipelineKind::Compute
+        } else if mesh {
+            PipelineKind::Mesh
+        } else {
+            PipelineKind::Graphics
+        }
+    }
+
+    pub fn create_pipeline(&mut self, shaders: &[Shader], pipeline: Option<Pipeline>) {
+        let kind = self.pipeline_kind(shaders);
+        if let Some(pipeline) = pipeline {
+            self.objects.pipelines.insert(pipeline.0, kind);
+        }
+    }
+
+    pub fn delete_pipelines(&mut self, call: &str, pipelines: &[Pipeline]) {
+        for pipeline in pipelines {
+            if self.objects.pipelines.remove(&pipeline.0).is_none() {
+                self.report(
+                    call,
+                    format_args!("pipeline {} is not a valid pipeline", pipeline.0),
+                );
+            }
+            if self.state.pipeline == Some(pipeline.0) {
+                self.state.pipeline = None;
+            }
+        }
+    }
+
+    pub fn bind_pipeline(&mut self, pipeline: Pipeline) {
+        if !self.objects.pipelines.contains_key(&pipeline.0) {
+            self.report(
+                "bind_pipeline",
+                format_args!("pipeline {} is not a valid pipeline", pipeline.0),
+            );
+        }
+        self.state.pipeline = Some(pipeline.0);
+    }
+
+    pub fn create_vertex_array(&mut self, vao: VertexArray, attributes: &[VertexAttributeDesc]) {
+        self.state.vertex_arrays.insert(
+            vao.0,
+            VertexArrayState {
+                bindings: attributes.iter().map(|attrib| attrib.binding).collect(),
+                ..Default::default()
+            },
+        );
+    }
+
+    pub fn delete_vertex_arrays(&mut self, vaos: &[VertexArray]) {
+        for vao in vaos {
+            if self.state.vertex_arrays.remove(&vao.0).is_none() {
+                self.report(
+                    "delete_vertex_arrays",
+                    format_args!("vertex array {} is not a valid vertex array", vao.0),
+                );
+            }
+            if self.state.vertex_array == Some(vao.0) {
+                self.state.vertex_array = None;
+            }
+        }
+    }
+
+    fn vertex_array_mut(&mut self, call: &str, vao: VertexArray) -> Option<&mut VertexArrayState> {
+        if !self.state.vertex_arrays.contains_key(&vao.0) {
+            self.report(
+                call,
+                format_args!("vertex array {} is not a valid vertex array", vao.0),
+            );
+        }
+        self.state.vertex_arrays.get_mut(&vao.0)
+    }
+
+    pub fn bind_vertex_array(&mut self, vao: VertexArray) {
+        self.vertex_array_mut("bind_vertex_array", vao);
+        self.state.vertex_array = Some(vao.0);
+    }
+
+    pub fn bind_vertex_buffers(
+        &mut self,
+        vao: VertexArray,
+        first: u32,
+        views: &[VertexBufferView],
+    ) {
+        let call = "bind_vertex_buffers";
+        for view in views {
+            self.check_buffer(call, view.buffer);
+        }
+        if let Some(state) = self.vertex_array_mut(call, vao) {
+            state.bound.extend(first..first + views.len() as u32);
+        }
+    }
+
+    pub fn bind_index_buffer(&mut self, vao: VertexArray, buffer: Buffer) {
+        let call = "bind_index_buffer";
+        self.check_buffer(call, buffer);
+        if let Some(state) = self.vertex_array_mut(call, vao) {
+            state.index_buffer = true;
+        }
+    }
+
+    pub fn set_viewport(&mut self, first: u32, num: usize) {
+        self.state.viewports = self.state.viewports.max(first + num as u32);
+    }
+
+    pub fn set_scissor(&mut self, first: u32, num: usize) {
+        self.state.scissors = self.state.scissors.max(first + num as u32);
+    }
+
+    /// Resync the bound state with a restored state snapshot.
+    pub fn restore_state(
+        &mut self,
+        pipeline: GLuint,
+        vertex_array: GLuint,
+        draw_indirect_buffer: bool,
+        dispatch_indirect_buffer: bool,
+        viewports: u32,
+        scissor_test: bool,
+    ) {
+        let bound = |name| if name != 0 { Some(name) } else { None };
+        self.state.pipeline = bound(pipeline);
+        self.state.vertex_array = bound(vertex_array);
+        self.state.draw_indirect_buffer = draw_indirect_buffer;
+        self.state.dispatch_indirect_buffer = dispatch_indirect_buffer;
+        // All viewports and scissors of the context are restored.
+        self.state.viewports = viewports;
+        self.state.scissors = viewports;
+        self.state.scissor_test = scissor_test;
+    }
+
+    fn check_pipeline(&self, call: &str, kind: PipelineKind) {
+        match self.state.pipeline {
+            None => self.report(call, "no pipeline is bound"),
+            Some(pipeline) => match self.objects.pipelines.get(&pipeline) {
+                Some(&bound) if bound != kind => self.report(
+                    call,
+                    format_args!(
+                        "bound pipeline {} is a {:?} pipeline, expected a {:?} pipeline",
+                        pipeline, bound, kind
+                    ),
+                ),
+                _ => {}
+            },
+        }
+    }
+
+    pub fn set_scissor_test(&mut self, enable: bool) {
+        self.state.scissor_test = enable;
+    }
+
+    fn check_rasterization(&self, call: &str) {
+        if self.state.scissor_test && self.state.viewports > self.state.scissors {
+            self.report(
+                call,
+                format_args!(
+                    "{} viewports are active, but only {} scissors are specified",
+                    self.state.viewports, self.state.scissors
+                ),
+            );
+        }
+    }
+
+    fn check_vertex_input(&self, call: &str, indexed: bool) {
+        let vao = match self.state.vertex_array {
+            Some(vao) => vao,
+            None => {
+                self.report(call, "no vertex array is bound");
+                return;
+            }
+        };
+        if let Some(state) = self.state.vertex_arrays.get(&vao) {
+            for binding in &state.bindings {
+                if !state.bound.contains(binding) {
+                    self.report(
+                        call,
+                        format_args!(
+                            "no vertex buffer bound at binding {} of vertex array {}",
+                            binding, vao
+                        ),
+                    );
+                }
+            }
+            if indexed && !state.index_buffer {
+                self.report(
+                    call,
+                    format_args!("no index buffer bound to vertex array {}", vao),
+                );
+            }
+        }
+    }
+
+    fn check_draw_indirect_buffer(&self, call: &str, from_host: bool) {
+        if from_host && self.state.draw_indirect_buffer {
+            self.report(
+                call,
+                "a draw indirect buffer is bound, host data would be interpreted as buffer offset",
+            );
+        } else if !from_host && !self.state.draw_indirect_buffer {
+            self.report(call, "no draw indirect buffer is bound");
+        }
+    }
+
+    pub fn draw(&self, vertices: &Range<u32>, instance: &Range<u32>) {
+        let call = "draw";
+        self.check_pipeline(call, PipelineKind::Graphics);
+        self.check_vertex_input(call, false);
+        self.check_rasterization(call);
+        self.check_range(call, "vertices", vertices);
+        self.check_range(call, "instance", instance);
+    }
+
+    pub fn draw_indexed(&self, indices: &Range<u32>, instance: &Range<u32>) {
+        let call = "draw_indexed";
+        self.check_pipeline(call, PipelineKind::Graphics);
+        self.check_vertex_input(call, true);
+        self.check_rasterization(call);
+        self.check_range(call, "indices", indices);
+        self.check_range(call, "instance", instance);
+    }
+
+    pub fn draw_indirect(&self, call: &str, indexed: bool, from_host: bool) {
+        self.check_pipeline(call, PipelineKind::Graphics);
+        self.check_vertex_input(call, indexed);
+        self.check_rasterization(call);
+        self.check_draw_indirect_buffer(call, from_host);
+    }
+
+    pub fn draw_mesh_tasks(&self, call: &str, indirect: bool) {
+        self.check_pipeline(call, PipelineKind::Mesh);
+        self.check_rasterization(call);
+        if indirect {
+            self.check_draw_indirect_buffer(call, false);
+        }
+    }
+
+    pub fn dispatch(&self, call: &str, indirect: bool) {
+        self.check_pipeline(call, PipelineKind::Compute);
+        if indirect && !self.state.dispatch_indirect_buffer {
+            self.report(call, "no dispatch indirect buffer is bound");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn device() -> Device {
+        let desc = DeviceDesc::default().debug_filter(DebugFilter {
+            log_capacity: 16,
+            ..Default::default()
+        });
+        unsafe { Device::with_backend_desc(RecordingBackend::new(), desc) }
+    }
+
+    /// Validation messages recorded since the last call.
+    fn errors(grr: &Device) -> Vec<String> {
+        let log = unsafe { grr.debug_log() };
+        grr.clear_debug_log();
+        log.into_iter()
+            .filter(|entry| entry.source == DebugSource::ThirdParty)
+            .map(|entry| entry.message)
+            .collect()
+    }
+
+    #[test]
+    fn draw() {
+        let grr = device();
+        unsafe {
+            grr.draw(Primitive::Triangles, 0..0, 0..1);
+            assert_eq!(
+                errors(&grr),
+                [
+                    "draw: no pipeline is bound",
+                    "draw: no vertex array is bound",
+                    "draw: `vertices.end` (0) must be larger than `vertices.start` (0)",
+                ]
+            );
+
+            let shader = |stage| {
+                grr.create_shader(
+                    stage,
+                    ShaderSource::Glsl,
+                    b"void main() {}",
+                    ShaderFlags::empty(),
+                    None,
+                )
+                .unwrap()
+            };
+            let compute = grr
+                .create_compute_pipeline(shader(ShaderStage::Compute), PipelineFlags::empty(), None)
+                .unwrap();
+            let graphics = grr
+                .create_graphics_pipeline(
+                    VertexPipelineDesc {
+                        vertex_shader: shader(ShaderStage::Vertex),
+                        tessellation_control_shader: None,
+                        tessellation_evaluation_shader: None,
+                        geometry_shader: None,
+                        fragment_shader: Some(shader(ShaderStage::Fragment)),
+                    },
+                    PipelineFlags::empty(),
+                    None,
+                )
+                .unwrap();
+            let vertex_array = grr
+                .create_vertex_array(
+                    &[VertexAttributeDesc {
+                        location: 0,
+                        binding: 0,
+                        format: VertexFormat::Xyz32Float,
+                        offset: 0,
+                    }],
+                    None,
+                )
+                .unwrap();
+            assert!(errors(&grr).is_empty());
+
+            grr.bind_pipeline(compute);
+            grr.bind_vertex_array(vertex_array);
+            grr.draw(Primitive::Triangles, 0..3, 0..1);
+            assert_eq!(
+                errors(&grr),
+                [
+                    format!(
+                        "draw: bound pipeline {} is a Compute pipeline, expected a Graphics pipeline",
+                        compute.0
+                    ),
+                    format!(
+                        "draw: no vertex buffer bound at binding 0 of vertex array {}",
+                        vertex_array.0
+                    ),
+                ]
+            );
+
+            let buffer = grr.create_buffer(36, MemoryFlags::empty(), None).unwrap();
+            grr.bind_vertex_buffers(
+                vertex_array,
+                0,
+                &[VertexBufferView {
+                    buffer,
+                    offset: 0,
+                    stride: 12,
+                    input_rate: InputRate::Vertex,
+                }],
+            );
+            grr.bind_pipeline(graphics);
+            grr.draw(Primitive::Triangles, 0..3, 0..1);
+            assert!(errors(&grr).is_empty());
+        }
+    }
+
+    #[test]
+    fn restore_state() {
+        let grr = device();
+        unsafe {
+            // `RecordingBackend` reports an empty context state.
+            let snapshot = grr.save_state();
+            let vertex_array = grr.create_vertex_array(&[], None).unwrap();
+            grr.bind_vertex_array(vertex_array);
+            grr.draw(Primitive::Triangles, 0..3, 0..1);
+            assert_eq!(errors(&grr), ["draw: no pipeline is bound"]);
+
+            grr.restore_state(&snapshot);
+            grr.draw(Primitive::Triangles, 0..3, 0..1);
+            assert_eq!(
+                errors(&grr),
+                [
+                    "draw: no pipeline is bound",
+                    "draw: no vertex array is bound"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn map_buffer() {
+        let grr = device();
+        unsafe {
+            let buffer = grr.create_buffer(16, MemoryFlags::empty(), None).unwrap();
+            grr.map_buffer::<u8>(buffer, 0..16, MappingFlags::INVALIDATE_RANGE);
+            assert_eq!(
+                errors(&grr),
+                [
+                    format!(
+                        "map_buffer: buffer {} must be created with `CPU_MAP_READ` or `CPU_MAP_WRITE`",
+                        buffer.0
+                    ),
+                    format!(
+                        "map_buffer: `INVALIDATE_RANGE` requires buffer {} to be created with `CPU_MAP_WRITE`",
+                        buffer.0
+                    ),
+                ]
+            );
+            grr.unmap_buffer(buffer);
+
+            let buffer = grr
+                .create_buffer(16, MemoryFlags::CPU_MAP_READ, None)
+                .unwrap();
+            grr.map_buffer::<u8>(buffer, 0..16, MappingFlags::empty());
+            assert!(errors(&grr).is_empty());
+            grr.map_buffer::<u8>(buffer, 4..4, MappingFlags::empty());
+            assert_eq!(
+                errors(&grr),
+                [
+                    format!("map_buffer: buffer {} is already mapped", buffer.0),
+                    "map_buffer: `range.end` (4) must be larger than `range.start` (4)".to_string(),
+                ]
+            );
+        }
+    }
+}
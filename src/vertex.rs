@@ -1,15 +1,23 @@
 use crate::__gl;
 use crate::__gl::types::GLuint;
+use crate::cache::StateKey;
+#[cfg(feature = "capture")]
+use crate::capture::Command;
 
 use crate::buffer::Buffer;
 use crate::debug::{Object, ObjectType};
 use crate::device::Device;
 use crate::error::Result;
+use crate::handle::{Generation, Handle};
 
 /// Vertex array handle.
-#[repr(transparent)]
-#[derive(Clone, Copy)]
-pub struct VertexArray(pub(crate) GLuint);
+#[cfg_attr(not(debug_assertions), repr(transparent))]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VertexArray(
+    pub(crate) GLuint,
+    #[cfg_attr(feature = "serde", serde(skip))] pub(crate) Generation,
+);
 
 impl Object for VertexArray {
     const TYPE: ObjectType = ObjectType::VertexArray;
@@ -18,8 +26,15 @@ impl Object for VertexArray {
     }
 }
 
+impl Handle for VertexArray {
+    fn generation(&self) -> Generation {
+        self.1
+    }
+}
+
 /// Buffer representation for vertex attributes
-#[derive(Clone)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexBufferView {
     /// Buffer handle for this buffer binding.
     pub buffer: Buffer,
@@ -33,6 +48,7 @@ pub struct VertexBufferView {
 
 /// Vertex attribute format and binding.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexAttributeDesc {
     /// Shader binding location of the attribute.
     pub location: u32,
@@ -48,6 +64,7 @@ pub struct VertexAttributeDesc {
 ///
 /// Specifies if the vertex attribute address depends on vertex index or instance index.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InputRate {
     /// Vertex index addressing.
     ///
@@ -63,6 +80,7 @@ pub enum InputRate {
 
 /// Vertex attribute formats.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VertexFormat {
     X8Int,
     X8Uint,
@@ -167,10 +185,13 @@ impl Device {
     ///
     /// The vertex array specified the vertex attributes and their binding to
     /// vertex buffer objects.
+    #[track_caller]
     pub unsafe fn create_vertex_array(
         &self,
         attributes: &[VertexAttributeDesc],
+        label: Option<&str>,
     ) -> Result<VertexArray> {
+        let _scope = self.api_scope("create_vertex_array");
         let mut vao = 0;
         self.0.CreateVertexArrays(1, &mut vao);
         self.get_error()?;
@@ -307,34 +328,80 @@ impl Device {
                 .VertexArrayAttribBinding(vao, desc.location, desc.binding);
         }
 
-        Ok(VertexArray(vao))
+        let vertex_array = VertexArray(vao, self.create_handle(ObjectType::VertexArray, vao));
+        #[cfg(feature = "tracking")]
+        self.track_resource(ObjectType::VertexArray, vao, 0);
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::CreateVertexArray {
+            attributes: attributes.to_vec(),
+            vertex_array,
+        });
+        #[cfg(feature = "validation")]
+        self.validator()
+            .create_vertex_array(vertex_array, attributes);
+        self.label_object(vertex_array, label);
+        Ok(vertex_array)
     }
 
     /// Delete a vertex array.
+    #[track_caller]
     pub unsafe fn delete_vertex_array(&self, vao: VertexArray) {
+        let _scope = self.api_scope("delete_vertex_array");
         self.delete_vertex_arrays(&[vao]);
     }
 
     /// Delete multiple vertex arrays.
+    #[track_caller]
     pub unsafe fn delete_vertex_arrays(&self, vao: &[VertexArray]) {
-        self.0.DeleteVertexArrays(
-            vao.len() as _,
-            vao.as_ptr() as *const _, // newtype
-        );
+        let _scope = self.api_scope("delete_vertex_arrays");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::DeleteVertexArrays {
+            vertex_arrays: vao.to_vec(),
+        });
+        self.delete_handles("delete_vertex_arrays", vao);
+        #[cfg(feature = "validation")]
+        self.validator().delete_vertex_arrays(vao);
+        // Deleting the bound vertex array resets the binding.
+        self.forget_state(StateKey::VertexArray);
+        let vao = vao.iter().map(|vao| vao.0).collect::<Vec<_>>();
+        self.0.DeleteVertexArrays(vao.len() as _, vao.as_ptr());
     }
 
     /// Bind a vertex array for usage.
+    #[track_caller]
     pub unsafe fn bind_vertex_array(&self, vao: VertexArray) {
-        self.0.BindVertexArray(vao.0);
+        let _scope = self.api_scope("bind_vertex_array");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindVertexArray { vertex_array: vao });
+        self.check_handle("bind_vertex_array", vao);
+        #[cfg(feature = "validation")]
+        self.validator().bind_vertex_array(vao);
+        if self.state_changed(StateKey::VertexArray, &[vao.0]) {
+            self.0.BindVertexArray(vao.0);
+        }
     }
 
     /// Bind vertex buffers to a vertex array.
+    #[track_caller]
     pub unsafe fn bind_vertex_buffers(
         &self,
         vao: VertexArray,
         first: u32,
         views: &[VertexBufferView],
     ) {
+        let _scope = self.api_scope("bind_vertex_buffers");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindVertexBuffers {
+            vertex_array: vao,
+            first,
+            views: views.to_vec(),
+        });
+        self.check_handle("bind_vertex_buffers", vao);
+        for view in views {
+            self.check_handle("bind_vertex_buffers", view.buffer);
+        }
+        #[cfg(feature = "validation")]
+        self.validator().bind_vertex_buffers(vao, first, views);
         let buffers = views.iter().map(|view| view.buffer.0).collect::<Vec<_>>();
 
         let offsets = views
@@ -368,7 +435,18 @@ impl Device {
     }
 
     /// Bind a index buffer to a vertex array.
+    #[track_caller]
     pub unsafe fn bind_index_buffer(&self, vao: VertexArray, buffer: Buffer) {
+        let _scope = self.api_scope("bind_index_buffer");
+        #[cfg(feature = "capture")]
+        self.capture(|| Command::BindIndexBuffer {
+            vertex_array: vao,
+            buffer,
+        });
+        self.check_handle("bind_index_buffer", vao);
+        self.check_handle("bind_index_buffer", buffer);
+        #[cfg(feature = "validation")]
+        self.validator().bind_index_buffer(vao, buffer);
         self.0.VertexArrayElementBuffer(vao.0, buffer.0);
     }
 }